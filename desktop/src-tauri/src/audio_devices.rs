@@ -0,0 +1,205 @@
+//! Stable identities for audio devices, and a watcher that reports when they change.
+//!
+//! cpal only hands out devices in enumeration order, which shifts whenever one is plugged in or
+//! removed. Devices are keyed by host, direction and name instead, so an id from
+//! `get_audio_devices` still means the same device in `start_record`, and across restarts when the
+//! frontend persists it. Two devices with the same name get `#2`, `#3`... in enumeration order.
+//!
+//! No host notifies device changes portably, so the watcher polls. It sits out recordings:
+//! enumerating devices is not free on every host, and nobody picks a device mid-recording.
+
+use crate::cmd::audio::{AudioDevice, RecordingState};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{Device, Host};
+use eyre::{Context, ContextCompat, Result};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn direction(is_input: bool) -> &'static str {
+    if is_input {
+        "input"
+    } else {
+        "output"
+    }
+}
+
+/// Ids for devices given as `(name, is_input)` in enumeration order.
+fn stable_ids(host: &str, devices: &[(String, bool)]) -> Vec<String> {
+    devices
+        .iter()
+        .enumerate()
+        .map(|(index, (name, is_input))| {
+            let id = format!("{}:{}:{}", host, direction(*is_input), name);
+            let same_before = devices[..index].iter().filter(|earlier| earlier == &&devices[index]).count();
+            if same_before == 0 {
+                id
+            } else {
+                format!("{}#{}", id, same_before + 1)
+            }
+        })
+        .collect()
+}
+
+fn enumerate(host: &Host) -> Result<Vec<(Device, String, bool)>> {
+    let mut devices = Vec::new();
+    for device in host.devices().context("Failed to list devices")? {
+        // One device that can't describe itself shouldn't hide the rest.
+        let name = match device.description() {
+            Ok(description) => description.to_string(),
+            Err(error) => {
+                tracing::warn!("skipping audio device without a description: {:?}", error);
+                continue;
+            }
+        };
+        let is_input = device.supports_input();
+        devices.push((device, name, is_input));
+    }
+    Ok(devices)
+}
+
+/// Every device of `host`, with stable ids.
+pub fn list(host: &Host) -> Result<Vec<AudioDevice>> {
+    let default_in = host
+        .default_input_device()
+        .and_then(|device| device.description().ok())
+        .map(|d| d.to_string());
+    let default_out = host
+        .default_output_device()
+        .and_then(|device| device.description().ok())
+        .map(|d| d.to_string());
+    tracing::debug!("Default Input Device:\n{:?}", default_in);
+    tracing::debug!("Default Output Device:\n{:?}", default_out);
+
+    let devices = enumerate(host)?;
+    let keys: Vec<(String, bool)> = devices.iter().map(|(_, name, is_input)| (name.clone(), *is_input)).collect();
+    let ids = stable_ids(host.id().name(), &keys);
+    Ok(devices
+        .into_iter()
+        .zip(ids)
+        .map(|((_, name, is_input), id)| {
+            let default = if is_input { &default_in } else { &default_out };
+            AudioDevice {
+                is_default: default.as_ref() == Some(&name),
+                is_input,
+                id,
+                name,
+            }
+        })
+        .collect())
+}
+
+/// The device with the given stable id.
+pub fn find(host: &Host, id: &str) -> Result<Device> {
+    let devices = enumerate(host)?;
+    let keys: Vec<(String, bool)> = devices.iter().map(|(_, name, is_input)| (name.clone(), *is_input)).collect();
+    let ids = stable_ids(host.id().name(), &keys);
+    devices
+        .into_iter()
+        .zip(ids)
+        .find(|(_, device_id)| device_id == id)
+        .map(|((device, _, _), _)| device)
+        .context(format!("Audio device not found: {id}. It may have been unplugged."))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DevicesChanged {
+    devices: Vec<AudioDevice>,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DefaultDevices {
+    input: Option<String>,
+    output: Option<String>,
+}
+
+fn defaults(devices: &[AudioDevice]) -> DefaultDevices {
+    let default = |is_input: bool| {
+        devices
+            .iter()
+            .find(|device| device.is_default && device.is_input == is_input)
+            .map(|device| device.id.clone())
+    };
+    DefaultDevices {
+        input: default(true),
+        output: default(false),
+    }
+}
+
+/// Poll the default host and emit `audio_devices_changed` when devices come or go, and
+/// `default_audio_device_changed` when the system default input or output changes. Paused while
+/// recording; changes made meanwhile are reported once it ends.
+pub fn watch(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let host = cpal::default_host();
+        let mut previous = list(&host).unwrap_or_default();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if app_handle.state::<RecordingState>().is_recording() {
+                continue;
+            }
+            let devices = match list(&host) {
+                Ok(devices) => devices,
+                Err(error) => {
+                    tracing::debug!("failed to poll audio devices: {:?}", error);
+                    continue;
+                }
+            };
+
+            let ids = |devices: &[AudioDevice]| devices.iter().map(|device| device.id.clone()).collect::<Vec<_>>();
+            let (before, after) = (ids(&previous), ids(&devices));
+            let added: Vec<String> = after.iter().filter(|id| !before.contains(id)).cloned().collect();
+            let removed: Vec<String> = before.iter().filter(|id| !after.contains(id)).cloned().collect();
+            let new_defaults = defaults(&devices);
+            let defaults_changed = defaults(&previous) != new_defaults;
+
+            if !added.is_empty() || !removed.is_empty() {
+                tracing::debug!("audio devices changed: added {:?}, removed {:?}", added, removed);
+                let payload = DevicesChanged {
+                    devices: devices.clone(),
+                    added,
+                    removed,
+                };
+                app_handle.emit("audio_devices_changed", payload).ok();
+            }
+            if defaults_changed {
+                tracing::debug!("default audio devices changed: {:?}", new_defaults);
+                app_handle.emit("default_audio_device_changed", new_defaults).ok();
+            }
+            previous = devices;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_survive_reordering_and_tell_twins_apart() {
+        let devices = [
+            ("USB Mic".to_string(), true),
+            ("Speakers".to_string(), false),
+            ("USB Mic".to_string(), true),
+        ];
+        assert_eq!(
+            stable_ids("CoreAudio", &devices),
+            [
+                "CoreAudio:input:USB Mic",
+                "CoreAudio:output:Speakers",
+                "CoreAudio:input:USB Mic#2"
+            ]
+        );
+        // A headset plugged in first does not change the others.
+        let plugged = [("Headset".to_string(), true), devices[0].clone(), devices[1].clone()];
+        assert_eq!(
+            stable_ids("CoreAudio", &plugged)[1..],
+            ["CoreAudio:input:USB Mic", "CoreAudio:output:Speakers"]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! A quick look at a recording before transcribing it (`analyze_audio`), so a clipped,
+//! near-silent or mostly-music file is called out before the model gets blamed for it.
+//!
+//! The first minutes of audio are decoded to 16 kHz mono and cut into 30 ms frames. Frame peaks
+//! use the same math as the recording level meter (`buffer_peak`), and silence the same threshold
+//! as auto-stop.
+
+use crate::cmd::audio::buffer_peak;
+use crate::recording::chunker::SAMPLE_RATE;
+use crate::recording::voice_activity::DEFAULT_SILENCE_THRESHOLD;
+use eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 30 ms frames.
+const FRAME_LEN: usize = SAMPLE_RATE as usize * 30 / 1000;
+/// Frames grouped into one second when telling speech from music.
+const FRAMES_PER_WINDOW: usize = 1000 / 30;
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.999;
+/// Levels are reported down to -100 dBFS, so digital silence stays a number in JSON.
+const MIN_LEVEL: f64 = 1e-5;
+
+const MAX_CLIPPING_RATIO: f64 = 0.001;
+const MIN_PEAK_DB: f64 = -20.0;
+const MAX_SILENCE_RATIO: f64 = 0.8;
+const MIN_SNR_DB: f64 = 10.0;
+const MIN_SPEECH_RATIO: f64 = 0.3;
+/// The start of a long file says enough about how it was recorded, and analyzing it stays quick.
+const MAX_ANALYZED_SECONDS: u64 = 10 * 60;
+/// Fewer sounding seconds than this say too little about speech or music to warn about it.
+const MIN_SPEECH_WINDOWS: usize = 10;
+
+/// Something about a recording that is likely to hurt its transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityWarning {
+    /// Nothing above the silence threshold at all.
+    Silent,
+    /// The loudest part is still quiet.
+    TooQuiet,
+    /// Samples hit full scale and the waveform was cut off.
+    Clipping,
+    /// Most of the recording is silence.
+    MostlySilent,
+    /// The background is close to as loud as the speech.
+    Noisy,
+    /// Most of what can be heard doesn't look like speech, such as music.
+    LittleSpeech,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioQuality {
+    /// Seconds analyzed, at most `MAX_ANALYZED_SECONDS`.
+    pub duration_seconds: f64,
+    pub peak_db: f64,
+    pub rms_db: f64,
+    /// Share of the samples at full scale.
+    pub clipping_ratio: f64,
+    /// Share of the frames below the silence threshold.
+    pub silence_ratio: f64,
+    /// Loud frames against the noise floor, or `None` when the floor is digital silence.
+    pub snr_db: Option<f64>,
+    /// Share of the sounding seconds whose level rises and falls like speech.
+    pub speech_ratio: f64,
+    pub warnings: Vec<QualityWarning>,
+}
+
+fn to_db(level: f32) -> f64 {
+    20.0 * f64::from(level).max(MIN_LEVEL).log10()
+}
+
+#[derive(Default)]
+struct Analyzer {
+    pending: Vec<f32>,
+    samples: u64,
+    clipped: u64,
+    sum_squares: f64,
+    peak: f32,
+    frame_peaks: Vec<f32>,
+    frame_rms: Vec<f32>,
+}
+
+impl Analyzer {
+    fn push(&mut self, samples: &[f32]) {
+        self.pending.extend_from_slice(samples);
+        let whole = self.pending.len() / FRAME_LEN * FRAME_LEN;
+        let pending = std::mem::take(&mut self.pending);
+        for frame in pending[..whole].chunks(FRAME_LEN) {
+            self.push_frame(frame);
+        }
+        self.pending = pending[whole..].to_vec();
+    }
+
+    fn push_frame(&mut self, frame: &[f32]) {
+        let peak = buffer_peak(frame);
+        let mut sum_squares = 0.0f64;
+        for &sample in frame {
+            sum_squares += f64::from(sample) * f64::from(sample);
+            if sample.abs() >= CLIP_LEVEL {
+                self.clipped += 1;
+            }
+        }
+        self.samples += frame.len() as u64;
+        self.sum_squares += sum_squares;
+        self.peak = self.peak.max(peak);
+        self.frame_peaks.push(peak);
+        self.frame_rms.push((sum_squares / frame.len() as f64).sqrt() as f32);
+    }
+
+    fn finish(mut self) -> AudioQuality {
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            self.push_frame(&rest);
+        }
+        let frames = self.frame_peaks.len().max(1) as f64;
+        let silent_frames = self
+            .frame_peaks
+            .iter()
+            .filter(|peak| **peak < DEFAULT_SILENCE_THRESHOLD)
+            .count();
+        let samples = self.samples.max(1) as f64;
+        let mut quality = AudioQuality {
+            duration_seconds: self.samples as f64 / f64::from(SAMPLE_RATE),
+            peak_db: to_db(self.peak),
+            rms_db: to_db((self.sum_squares / samples).sqrt() as f32),
+            clipping_ratio: self.clipped as f64 / samples,
+            silence_ratio: silent_frames as f64 / frames,
+            snr_db: self.snr_db(),
+            speech_ratio: 0.0,
+            warnings: Vec::new(),
+        };
+        let (speech_windows, sounding_windows) = self.speech_windows();
+        if sounding_windows > 0 {
+            quality.speech_ratio = speech_windows as f64 / sounding_windows as f64;
+        }
+
+        let warnings = &mut quality.warnings;
+        if self.peak < DEFAULT_SILENCE_THRESHOLD {
+            warnings.push(QualityWarning::Silent);
+            return quality;
+        }
+        if quality.peak_db < MIN_PEAK_DB {
+            warnings.push(QualityWarning::TooQuiet);
+        }
+        if quality.clipping_ratio > MAX_CLIPPING_RATIO {
+            warnings.push(QualityWarning::Clipping);
+        }
+        if quality.silence_ratio > MAX_SILENCE_RATIO {
+            warnings.push(QualityWarning::MostlySilent);
+        }
+        // Without pauses there is no background to measure, so music is not also called noisy.
+        if sounding_windows >= MIN_SPEECH_WINDOWS && quality.speech_ratio < MIN_SPEECH_RATIO {
+            warnings.push(QualityWarning::LittleSpeech);
+        } else if quality.snr_db.is_some_and(|snr| snr < MIN_SNR_DB) {
+            warnings.push(QualityWarning::Noisy);
+        }
+        quality
+    }
+
+    /// The loud frames (95th percentile) against the quiet ones (10th percentile), which in a
+    /// recording with pauses are the background.
+    fn snr_db(&self) -> Option<f64> {
+        let mut levels = self.frame_rms.clone();
+        if levels.len() < FRAMES_PER_WINDOW {
+            return None;
+        }
+        levels.sort_by(f32::total_cmp);
+        let at = |percentile: usize| levels[(levels.len() - 1) * percentile / 100];
+        let (noise, signal) = (at(10), at(95));
+        (f64::from(noise) >= MIN_LEVEL).then(|| to_db(signal) - to_db(noise))
+    }
+
+    /// Speech is syllables and short pauses, so within a second many frames are well below that
+    /// second's average; music and steady noise hold their level. Returns the speech-like seconds
+    /// and the seconds that are mostly not silence.
+    fn speech_windows(&self) -> (usize, usize) {
+        let (mut speech, mut sounding) = (0, 0);
+        for (peaks, rms) in self
+            .frame_peaks
+            .chunks_exact(FRAMES_PER_WINDOW)
+            .zip(self.frame_rms.chunks_exact(FRAMES_PER_WINDOW))
+        {
+            let loud = peaks.iter().filter(|peak| **peak >= DEFAULT_SILENCE_THRESHOLD).count();
+            if loud * 2 < FRAMES_PER_WINDOW {
+                continue;
+            }
+            sounding += 1;
+            let mean = rms.iter().sum::<f32>() / rms.len() as f32;
+            let low = rms.iter().filter(|level| **level < mean * 0.5).count();
+            if low * 5 >= FRAMES_PER_WINDOW {
+                speech += 1;
+            }
+        }
+        (speech, sounding)
+    }
+}
+
+/// Analyze up to `MAX_ANALYZED_SECONDS` of the first audio track of `input`, decoded with ffmpeg
+/// when it is installed and with Symphonia otherwise.
+pub fn analyze(input: &Path) -> Result<AudioQuality> {
+    let mut analyzer = Analyzer::default();
+    let on_samples = |samples: &[f32]| {
+        analyzer.push(samples);
+        if analyzer.samples >= MAX_ANALYZED_SECONDS * u64::from(SAMPLE_RATE) {
+            bail!(SampleComplete);
+        }
+        Ok(())
+    };
+    let decoded = if crate::ffmpeg::find_ffmpeg_path().is_some() {
+        crate::ffmpeg::decode_samples(input, SAMPLE_RATE, on_samples)
+    } else {
+        crate::decode::decode_samples(input, on_samples)
+    };
+    match decoded {
+        Err(error) if error.is::<SampleComplete>() => {}
+        decoded => decoded?,
+    }
+    Ok(analyzer.finish())
+}
+
+/// Stops decoding once enough has been analyzed.
+#[derive(Debug)]
+struct SampleComplete;
+
+impl std::fmt::Display for SampleComplete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "analyzed enough of the file")
+    }
+}
+
+impl std::error::Error for SampleComplete {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze_samples(samples: &[f32]) -> AudioQuality {
+        let mut analyzer = Analyzer::default();
+        for block in samples.chunks(1000) {
+            analyzer.push(block);
+        }
+        analyzer.finish()
+    }
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(seconds * SAMPLE_RATE as f32) as usize)
+            .map(|i| (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / SAMPLE_RATE as f32).sin() * amplitude)
+            .collect()
+    }
+
+    /// A tone switched on and off four times a second, like syllables.
+    fn syllables(seconds: f32) -> Vec<f32> {
+        let mut samples = tone(seconds, 0.5);
+        for (i, sample) in samples.iter_mut().enumerate() {
+            if i % (SAMPLE_RATE as usize / 4) > SAMPLE_RATE as usize / 6 {
+                *sample *= 0.01;
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn clean_speech_has_no_warnings() {
+        let quality = analyze_samples(&syllables(20.0));
+        assert_eq!(quality.warnings, []);
+        assert!((quality.duration_seconds - 20.0).abs() < 0.01);
+        assert!((quality.peak_db - to_db(0.5)).abs() < 0.1);
+        assert!(quality.speech_ratio > 0.9);
+        assert!(quality.snr_db.unwrap() > 30.0);
+    }
+
+    #[test]
+    fn warns_about_clipped_steady_sound() {
+        let samples: Vec<f32> = tone(20.0, 2.0).iter().map(|sample| sample.clamp(-1.0, 1.0)).collect();
+        let quality = analyze_samples(&samples);
+        assert_eq!(quality.peak_db, 0.0);
+        assert!(quality.clipping_ratio > 0.1);
+        assert_eq!(quality.speech_ratio, 0.0);
+        assert_eq!(quality.warnings, [QualityWarning::Clipping, QualityWarning::LittleSpeech]);
+    }
+
+    #[test]
+    fn warns_about_quiet_and_silent_recordings() {
+        let mut samples = vec![0.0; SAMPLE_RATE as usize * 20];
+        samples.extend(tone(2.0, 0.05));
+        let quality = analyze_samples(&samples);
+        assert_eq!(quality.warnings, [QualityWarning::TooQuiet, QualityWarning::MostlySilent]);
+        assert_eq!(quality.snr_db, None);
+
+        let quality = analyze_samples(&[0.0; 16_000]);
+        assert_eq!(quality.peak_db, -100.0);
+        assert_eq!(quality.warnings, [QualityWarning::Silent]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Reading the WebVTT captions a video platform already has, as downloaded by yt-dlp, so they
+//! can be compared with our own transcript.
+//!
+//! Automatic captions (YouTube's in particular) repeat each line in the next cue while it scrolls
+//! and carry per-word timing tags; the tags are stripped and only the new lines of a cue are kept.
+
+use crate::transcript::Segment;
+
+/// `HH:MM:SS.mmm` or `MM:SS.mmm` as centiseconds.
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let (clock, millis) = value.trim().split_once('.')?;
+    let mut seconds = 0i64;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<i64>().ok()?;
+    }
+    Some(seconds * 100 + millis.parse::<i64>().ok()? / 10)
+}
+
+/// Cue text without tags such as `<c>` or `<00:00:01.319>`, and with the common entities decoded.
+fn plain_text(line: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn parse_vtt(vtt: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut previous_lines: Vec<String> = Vec::new();
+    let mut lines = vtt.lines();
+    while let Some(line) = lines.next() {
+        let Some((start, rest)) = line.split_once("-->") else {
+            continue;
+        };
+        // Cue settings such as `align:start position:0%` follow the end time.
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let (Some(start), Some(stop)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+        let cue_lines: Vec<String> = lines
+            .by_ref()
+            .take_while(|line| !line.is_empty())
+            .map(plain_text)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let new_lines: Vec<&String> = cue_lines.iter().filter(|line| !previous_lines.contains(line)).collect();
+        if !new_lines.is_empty() {
+            segments.push(Segment {
+                start,
+                stop,
+                text: new_lines.iter().map(|line| line.as_str()).collect::<Vec<_>>().join(" "),
+                speaker: None,
+            });
+        }
+        if !cue_lines.is_empty() {
+            previous_lines = cue_lines;
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(segments: &[Segment]) -> Vec<(i64, i64, &str)> {
+        segments
+            .iter()
+            .map(|segment| (segment.start, segment.stop, segment.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_plain_captions() {
+        let vtt = "WEBVTT\nKind: captions\nLanguage: en\n\n1\n00:00:01.000 --> 00:00:04.500\nHello &amp; welcome\nto the course\n\n01:02:03.250 --> 01:02:05.000 align:start\n<i>Bye</i>\n";
+        let segments = parse_vtt(vtt);
+        assert_eq!(
+            texts(&segments),
+            [(100, 450, "Hello & welcome to the course"), (372325, 372500, "Bye")]
+        );
+    }
+
+    #[test]
+    fn drops_the_lines_automatic_captions_repeat() {
+        let vtt = "WEBVTT\n\n00:00:00.000 --> 00:00:02.000 align:start position:0%\n \nwelcome<00:00:00.500><c> to</c><00:00:01.000><c> the</c>\n\n00:00:02.000 --> 00:00:02.010 align:start position:0%\nwelcome to the\n \n\n00:00:02.010 --> 00:00:04.000 align:start position:0%\nwelcome to the\ncourse<00:00:03.000><c> today</c>\n";
+        let segments = parse_vtt(vtt);
+        assert_eq!(texts(&segments), [(0, 200, "welcome to the"), (201, 400, "course today")]);
+    }
+}
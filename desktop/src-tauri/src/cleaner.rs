@@ -48,6 +48,10 @@ pub fn clean_old_files() -> Result<()> {
             tracing::debug!("Skip deletion of {}", current_temp_dir.display());
             continue;
         }
+        if crate::recording::recovery::has_manifest(&path) {
+            tracing::debug!("Skip deletion of {}: it holds an interrupted recording", path.display());
+            continue;
+        }
         tracing::debug!("Clean old folder {}", path.clone().display());
         std::fs::remove_dir_all(path.clone())
             .map_err(|e| eyre!("failed to delete {}: {:?}", path.display(), e))
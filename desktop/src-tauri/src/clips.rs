@@ -0,0 +1,371 @@
+//! Audio clips of transcript segments, for pulling the audio of a few quotes out of a recording.
+//!
+//! Each clip is read with `-ss` and `-t` on its input, so ffmpeg seeks to it instead of decoding
+//! the file from the start. A highlight reel opens the file once, up to the end of its last clip,
+//! and trims the clips out of that one stream before concatenating them; one input per clip would
+//! open the file, and start a decoder, a hundred times for a hundred quotes.
+
+use crate::ffmpeg::ConversionHandle;
+use eyre::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Longest name taken from a clip's text; a whole quote makes an unwieldy file name.
+const MAX_NAME_CHARS: usize = 48;
+
+/// A `Segment`, or just a time range. In centiseconds, like segments.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipRange {
+    pub start: i64,
+    pub stop: i64,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub speaker: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipLayout {
+    /// A file per clip, in a folder.
+    #[default]
+    Separate,
+    /// One file with the clips one after another, in time order.
+    Reel,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipName {
+    /// The first words of the segment.
+    #[default]
+    Text,
+    /// "Speaker 2", falling back to the text for segments without a speaker.
+    Speaker,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    #[default]
+    Wav,
+    Mp3,
+    M4a,
+}
+
+impl ClipFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Mp3 => "mp3",
+            Self::M4a => "m4a",
+        }
+    }
+
+    /// Clips go into edits, so the lossy formats get music bitrates rather than speech ones.
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Wav => &["-c:a", "pcm_s16le"],
+            Self::Mp3 => &["-c:a", "libmp3lame", "-q:a", "2"],
+            Self::M4a => &["-c:a", "aac", "-b:a", "192k"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ClipOptions {
+    /// Added before and after each clip, so the first and last words are not cut off.
+    pub padding_ms: u32,
+    /// Fade in and out at the cuts, so clips don't start and end with a click.
+    pub fade_ms: u32,
+    pub layout: ClipLayout,
+    pub name_from: ClipName,
+    pub format: ClipFormat,
+    /// The word for speakers in names, as in transcript exports. "Speaker" when unset.
+    pub speaker_label: Option<String>,
+}
+
+/// One clip in seconds, padded and clamped to the file.
+#[derive(Debug, Clone, PartialEq)]
+struct Cut {
+    start: f64,
+    end: f64,
+    name: String,
+}
+
+impl Cut {
+    fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Turn `ranges` into what gets cut. In a reel, clips are put in time order and clips that overlap
+/// once padded are joined, so no audio plays twice.
+fn cuts(ranges: &[ClipRange], options: &ClipOptions, duration: Option<f64>) -> Result<Vec<Cut>> {
+    if ranges.is_empty() {
+        bail!("No segments to export");
+    }
+    let padding = f64::from(options.padding_ms) / 1000.0;
+    let mut cuts = Vec::new();
+    for (index, range) in ranges.iter().enumerate() {
+        if range.start < 0 || range.stop <= range.start {
+            bail!("Invalid segment {}: {}-{}", index + 1, range.start, range.stop);
+        }
+        let start = (range.start as f64 / 100.0 - padding).max(0.0);
+        let mut end = range.stop as f64 / 100.0 + padding;
+        if let Some(duration) = duration {
+            end = end.min(duration);
+        }
+        if end <= start {
+            bail!("Segment {} is past the end of the file", index + 1);
+        }
+        cuts.push(Cut {
+            start,
+            end,
+            name: clip_name(range, index, options),
+        });
+    }
+    if options.layout == ClipLayout::Reel {
+        cuts.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let mut merged: Vec<Cut> = Vec::with_capacity(cuts.len());
+        for cut in cuts {
+            match merged.last_mut() {
+                Some(last) if cut.start <= last.end => last.end = last.end.max(cut.end),
+                _ => merged.push(cut),
+            }
+        }
+        cuts = merged;
+    }
+    Ok(cuts)
+}
+
+/// `01 First words of the quote`, numbered so the clips sort in the order they were picked.
+fn clip_name(range: &ClipRange, index: usize, options: &ClipOptions) -> String {
+    let speaker = range
+        .speaker
+        .filter(|_| options.name_from == ClipName::Speaker)
+        .map(|speaker| format!("{} {}", options.speaker_label.as_deref().unwrap_or("Speaker"), speaker + 1));
+    let name = speaker.unwrap_or_else(|| file_name_from_text(range.text.as_deref().unwrap_or_default()));
+    if name.is_empty() {
+        format!("{:02}", index + 1)
+    } else {
+        format!("{:02} {}", index + 1, name)
+    }
+}
+
+/// The start of `text` as a file name: whole words, without characters file systems reject.
+fn file_name_from_text(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let mut name = String::new();
+    for word in cleaned.split_whitespace() {
+        if !name.is_empty() && name.chars().count() + 1 + word.chars().count() > MAX_NAME_CHARS {
+            break;
+        }
+        if !name.is_empty() {
+            name.push(' ');
+        }
+        name.extend(word.chars().take(MAX_NAME_CHARS));
+    }
+    name.trim_end_matches(['.', ' ']).to_string()
+}
+
+/// `afade` in and out over `fade_ms`, at most half the clip each.
+fn fade_filter(duration: f64, fade_ms: u32) -> Option<String> {
+    let fade = (f64::from(fade_ms) / 1000.0).min(duration / 2.0);
+    (fade > 0.0).then(|| format!("afade=t=in:d={fade:.3},afade=t=out:st={:.3}:d={fade:.3}", duration - fade))
+}
+
+fn input_args(input: &Path, cut: &Cut) -> Vec<String> {
+    vec![
+        "-ss".to_string(),
+        format!("{:.3}", cut.start),
+        "-t".to_string(),
+        format!("{:.3}", cut.duration()),
+        "-i".to_string(),
+        input.to_string_lossy().to_string(),
+    ]
+}
+
+fn clip_args(input: &Path, cut: &Cut, options: &ClipOptions) -> Vec<String> {
+    let mut args = input_args(input, cut);
+    args.push("-vn".to_string());
+    if let Some(fade) = fade_filter(cut.duration(), options.fade_ms) {
+        args.extend(["-af".to_string(), fade]);
+    }
+    args.extend(options.format.codec_args().iter().map(|arg| arg.to_string()));
+    args
+}
+
+fn reel_args(input: &Path, cuts: &[Cut], options: &ClipOptions) -> Vec<String> {
+    let end = cuts.iter().map(|cut| cut.end).fold(0.0, f64::max);
+    let mut args = vec![
+        "-t".to_string(),
+        format!("{end:.3}"),
+        "-i".to_string(),
+        input.to_string_lossy().to_string(),
+    ];
+    let mut graph = format!("[0:a]asplit={}", cuts.len());
+    for index in 0..cuts.len() {
+        graph.push_str(&format!("[s{index}]"));
+    }
+    graph.push(';');
+    for (index, cut) in cuts.iter().enumerate() {
+        graph.push_str(&format!(
+            "[s{index}]atrim=start={:.3}:end={:.3},asetpts=PTS-STARTPTS",
+            cut.start, cut.end
+        ));
+        if let Some(fade) = fade_filter(cut.duration(), options.fade_ms) {
+            graph.push_str(&format!(",{fade}"));
+        }
+        graph.push_str(&format!("[a{index}];"));
+    }
+    for index in 0..cuts.len() {
+        graph.push_str(&format!("[a{index}]"));
+    }
+    graph.push_str(&format!("concat=n={}:v=0:a=1[out]", cuts.len()));
+    args.extend(["-filter_complex".to_string(), graph, "-map".to_string(), "[out]".to_string()]);
+    args.extend(options.format.codec_args().iter().map(|arg| arg.to_string()));
+    args
+}
+
+/// Cut `ranges` out of `input`: a file per clip into the folder `output`, or a reel into the file
+/// `output`. Returns the files written. Clips written before a cancellation are kept.
+pub fn export(
+    input: &Path,
+    ranges: &[ClipRange],
+    output: &Path,
+    options: &ClipOptions,
+    conversion: &ConversionHandle,
+) -> Result<Vec<PathBuf>> {
+    let duration = crate::probe::probe(input).ok().and_then(|info| info.duration_seconds);
+    let cuts = cuts(ranges, options, duration)?;
+    match options.layout {
+        ClipLayout::Separate => {
+            std::fs::create_dir_all(output).context(format!("Failed to create {}", output.display()))?;
+            let mut written = Vec::new();
+            for (index, cut) in cuts.iter().enumerate() {
+                let path = crate::cmd::files::available_path(output, &cut.name, options.format.extension());
+                crate::ffmpeg::convert(&clip_args(input, cut, options), &path, None, conversion)?;
+                written.push(path);
+                conversion.report_progress(((index + 1) as f64 / cuts.len() as f64 * 100.0).floor());
+            }
+            Ok(written)
+        }
+        ClipLayout::Reel => {
+            let total = cuts.iter().map(Cut::duration).sum();
+            crate::ffmpeg::convert(&reel_args(input, &cuts, options), output, Some(total), conversion)?;
+            Ok(vec![output.to_path_buf()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: i64, stop: i64, text: &str, speaker: Option<i32>) -> ClipRange {
+        ClipRange {
+            start,
+            stop,
+            text: Some(text.to_string()),
+            speaker,
+        }
+    }
+
+    #[test]
+    fn pads_and_clamps_clips_and_joins_overlaps_in_a_reel() {
+        let ranges = [
+            range(1000, 1500, "Later", None),
+            range(10, 300, "First", None),
+            range(320, 400, "Right after", None),
+            range(5900, 7000, "At the end", None),
+        ];
+        let mut options = ClipOptions {
+            padding_ms: 250,
+            ..Default::default()
+        };
+        let separate = cuts(&ranges, &options, Some(60.0)).unwrap();
+        let times: Vec<_> = separate.iter().map(|cut| (cut.start, cut.end)).collect();
+        assert_eq!(times, [(9.75, 15.25), (0.0, 3.25), (2.95, 4.25), (58.75, 60.0)]);
+        assert_eq!(separate[1].name, "02 First");
+
+        options.layout = ClipLayout::Reel;
+        let reel = cuts(&ranges, &options, Some(60.0)).unwrap();
+        let times: Vec<_> = reel.iter().map(|cut| (cut.start, cut.end)).collect();
+        assert_eq!(times, [(0.0, 4.25), (9.75, 15.25), (58.75, 60.0)]);
+
+        assert!(cuts(&[range(500, 400, "", None)], &options, None).is_err());
+        assert!(cuts(&[range(7000, 7100, "", None)], &options, Some(60.0)).is_err());
+        assert!(cuts(&[], &options, None).is_err());
+    }
+
+    #[test]
+    fn names_clips_from_their_text_or_speaker() {
+        let options = ClipOptions::default();
+        let quote = range(
+            0,
+            100,
+            "  What we found: \"prices\" rose/fell by 3.5%, and then the rest of a long sentence.",
+            Some(1),
+        );
+        assert_eq!(
+            clip_name(&quote, 0, &options),
+            "01 What we found prices rose fell by 3.5%, and then"
+        );
+        assert_eq!(clip_name(&range(0, 100, " ... ", None), 11, &options), "12");
+
+        let options = ClipOptions {
+            name_from: ClipName::Speaker,
+            speaker_label: Some("Sprecher".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(clip_name(&quote, 2, &options), "03 Sprecher 2");
+        assert_eq!(clip_name(&range(0, 100, "No speaker", None), 3, &options), "04 No speaker");
+    }
+
+    #[test]
+    fn reels_trim_each_clip_from_one_input_and_concatenate_them() {
+        let options = ClipOptions {
+            fade_ms: 500,
+            format: ClipFormat::Mp3,
+            ..Default::default()
+        };
+        let cuts = [
+            Cut {
+                start: 1.0,
+                end: 4.0,
+                name: String::new(),
+            },
+            Cut {
+                start: 10.0,
+                end: 10.6,
+                name: String::new(),
+            },
+        ];
+        let args = reel_args(Path::new("talk.mp4"), &cuts, &options);
+        assert_eq!(args[..4], ["-t", "10.600", "-i", "talk.mp4"]);
+        assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 1);
+        let graph = &args[args.iter().position(|arg| arg == "-filter_complex").unwrap() + 1];
+        assert_eq!(
+            graph,
+            "[0:a]asplit=2[s0][s1];\
+             [s0]atrim=start=1.000:end=4.000,asetpts=PTS-STARTPTS,afade=t=in:d=0.500,afade=t=out:st=2.500:d=0.500[a0];\
+             [s1]atrim=start=10.000:end=10.600,asetpts=PTS-STARTPTS,afade=t=in:d=0.300,afade=t=out:st=0.300:d=0.300[a1];\
+             [a0][a1]concat=n=2:v=0:a=1[out]"
+        );
+        assert!(args.ends_with(&[
+            "-c:a".to_string(),
+            "libmp3lame".to_string(),
+            "-q:a".to_string(),
+            "2".to_string()
+        ]));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::config::STORE_FILENAME;
 use crate::ffmpeg;
+use crate::text_insertion::{self, InsertionMode, InsertionOptions};
 use eyre::{Context, Result};
 
 /// Return true if there's internet connection
@@ -104,14 +105,15 @@ pub fn rename_crash_file() -> Result<()> {
     .context("Can't delete file")
 }
 
+/// Insert `text` into the focused app, with `mode` or else the configured insertion mode.
 #[tauri::command]
-pub fn type_text(text: String) -> Result<()> {
-    use enigo::{Enigo, Keyboard, Settings};
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| eyre::eyre!("Failed to create enigo: {}", e))?;
-    // Small delay to let the user's key release propagate
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    enigo.text(&text).map_err(|e| eyre::eyre!("Failed to type text: {}", e))?;
-    Ok(())
+pub fn type_text(app_handle: tauri::AppHandle, text: String, mode: Option<InsertionMode>) -> Result<()> {
+    let mut options = InsertionOptions::from_config(&app_handle);
+    if let Some(mode) = mode {
+        options.mode = mode;
+        options.app_modes.clear();
+    }
+    text_insertion::insert_text(&app_handle, &text, &options)
 }
 
 #[tauri::command]
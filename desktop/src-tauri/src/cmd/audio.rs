@@ -1,3 +1,4 @@
+use crate::disk_space;
 use crate::ffmpeg::get_vibe_temp_folder;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, FromSample, Sample, SizedSample, Stream, SupportedStreamConfig};
@@ -6,96 +7,88 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
-use tauri::{AppHandle, Emitter, Listener, Manager};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
 
 use crate::error::LogError;
 use crate::ffmpeg::{get_local_time, random_string};
+use crate::recording;
+use crate::recording::archive::{self, ArchiveFormat};
+use crate::recording::live::{LiveTap, LiveTranscriber, LiveTranscriptionOptions};
+use crate::recording::recovery::{self, ManifestTrack, RecordingManifest};
+use crate::recording::voice_activity::{AutoStopOptions, StopReason, VoiceActivity};
+use crate::setup::SonaState;
+
+use super::conversion::{ConversionKind, ConversionManager};
+use super::CommandError;
 
 type WavWriterHandle = Arc<Mutex<Option<hound::WavWriter<BufWriter<File>>>>>;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioDevice {
     pub is_default: bool,
     pub is_input: bool,
+    /// Stable across enumeration order and restarts (see `audio_devices`).
     pub id: String,
     pub name: String,
 }
 
 #[tauri::command]
 pub fn get_audio_devices() -> Result<Vec<AudioDevice>> {
-    let host = cpal::default_host();
-    let mut audio_devices = Vec::new();
-
-    let default_in = host
-        .default_input_device()
-        .map(|e| e.description().map(|d| d.to_string()))
-        .context("name")?;
-    let default_out = host
-        .default_output_device()
-        .map(|e| e.description().map(|d| d.to_string()))
-        .context("name")?;
-    tracing::debug!("Default Input Device:\n{:?}", default_in);
-    tracing::debug!("Default Output Device:\n{:?}", default_out);
-
-    let devices = host.devices()?;
-    tracing::debug!("Devices: ");
-    for (device_index, device) in devices.enumerate() {
-        let name = device.description()?.to_string();
-        let is_default_in = default_in.as_ref().is_ok_and(|d| d == &name);
-        let is_default_out = default_out.as_ref().is_ok_and(|d| d == &name);
-
-        let audio_device = AudioDevice {
-            is_default: is_default_in || is_default_out,
-            is_input: device.supports_input(),
-            id: device_index.to_string(),
-            name,
-        };
-        audio_devices.push(audio_device);
-    }
-
-    Ok(audio_devices)
+    crate::audio_devices::list(&cpal::default_host())
 }
 
-struct StreamHandle(Stream);
+pub(crate) struct StreamHandle(pub(crate) Stream);
 unsafe impl Send for StreamHandle {}
 unsafe impl Sync for StreamHandle {}
 
 /// At most ~10 `record_level` events per second reach the webview.
 const LEVEL_EMIT_INTERVAL_MS: u64 = 100;
-/// Peak is taken from every Nth sample — plenty for a meter, and keeps the callback cheap.
-const LEVEL_SAMPLE_STRIDE: usize = 4;
 
 /// Shared by every capture stream of a recording session, so the emitted value is the max
-/// level across input + output (loopback) devices.
+/// level across input + output (loopback) devices. Auto-stop listens to the microphone alone.
 ///
 /// Everything here is atomic and allocation-free: the audio callback only does a few relaxed
 /// loads/stores, and once per 100ms one callback also performs the (non-blocking) `emit_to`.
 struct LevelMeter {
     app_handle: AppHandle,
+    control: Arc<RecordingControl>,
     started_at: Instant,
     /// Max level seen since the last emit, stored as `f32::to_bits` (monotonic for +0.0..=1.0).
     peak_bits: AtomicU32,
+    /// Max microphone level since the last emit. The other side of a call, heard through the
+    /// loopback, must not keep the recording going or count as the user speaking.
+    voice_bits: AtomicU32,
+    /// Without a microphone, the loopback is all there is to listen to.
+    has_microphone: bool,
     last_emit_ms: AtomicU64,
 }
 
 impl LevelMeter {
-    fn new(app_handle: AppHandle) -> Self {
+    fn new(app_handle: AppHandle, control: Arc<RecordingControl>, has_microphone: bool) -> Self {
         Self {
             app_handle,
+            control,
             started_at: Instant::now(),
             peak_bits: AtomicU32::new(0),
+            voice_bits: AtomicU32::new(0),
+            has_microphone,
             last_emit_ms: AtomicU64::new(0),
         }
     }
 
     /// Accumulate one buffer's peak and emit if the throttle window elapsed.
-    fn push(&self, peak: f32) {
+    fn push(&self, peak: f32, microphone: bool) {
         self.peak_bits.fetch_max(peak.to_bits(), Ordering::Relaxed);
+        if microphone || !self.has_microphone {
+            self.voice_bits.fetch_max(peak.to_bits(), Ordering::Relaxed);
+        }
 
         let now_ms = self.started_at.elapsed().as_millis() as u64;
         let last_ms = self.last_emit_ms.load(Ordering::Relaxed);
@@ -112,17 +105,167 @@ impl LevelMeter {
         }
         let level = f32::from_bits(self.peak_bits.swap(0, Ordering::Relaxed)).clamp(0.0, 1.0);
         self.app_handle.emit_to("main", "record_level", level).ok();
+
+        let voice = f32::from_bits(self.voice_bits.swap(0, Ordering::Relaxed)).clamp(0.0, 1.0);
+        if let Some(reason) = self.control.observe_level(voice) {
+            tracing::debug!("recording stops on its own: {:?}", reason);
+            let app_handle = self.app_handle.clone();
+            let recorded_ms = self.control.recorded_ms(self.control.elapsed_ms());
+            // Off the audio thread: stopping pauses the stream this callback belongs to.
+            tauri::async_runtime::spawn(async move {
+                app_handle
+                    .emit("record_auto_stop", json!({"reason": reason, "recordedMs": recorded_ms}))
+                    .log_error();
+                app_handle.emit("stop_record", ()).log_error();
+            });
+        }
     }
 }
 
+/// A stretch of the session during which nothing was written, in ms since the recording started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PausedInterval {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Pause state of the running recording, shared by the capture callbacks and the pause commands.
+///
+/// Pausing drops samples in the callbacks instead of stopping the streams: every track skips the
+/// same stretch, so they stay aligned and the merged file has no gap, and loopback devices that
+/// cannot be restarted reliably keep running.
+///
+/// With `auto_stop`, it also watches the microphone level to stop the recording on its own.
+struct RecordingControl {
+    started_at: Instant,
+    paused: AtomicBool,
+    /// Closed intervals, then the open one (with `end` unset) while paused.
+    intervals: Mutex<Vec<(u64, Option<u64>)>>,
+    activity: Option<Mutex<VoiceActivity>>,
+    stop_reason: Mutex<Option<StopReason>>,
+}
+
+impl RecordingControl {
+    fn new(auto_stop: Option<&AutoStopOptions>) -> Self {
+        Self {
+            started_at: Instant::now(),
+            paused: AtomicBool::new(false),
+            intervals: Mutex::new(Vec::new()),
+            activity: auto_stop.map(|options| Mutex::new(VoiceActivity::new(options))),
+            stop_reason: Mutex::new(None),
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started_at.elapsed().as_millis() as u64
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns false when already paused.
+    fn pause_at(&self, now_ms: u64) -> bool {
+        let mut intervals = self.intervals.lock().unwrap_or_else(|error| error.into_inner());
+        if self.paused.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        intervals.push((now_ms, None));
+        true
+    }
+
+    /// Returns false when not paused.
+    fn resume_at(&self, now_ms: u64) -> bool {
+        let mut intervals = self.intervals.lock().unwrap_or_else(|error| error.into_inner());
+        if !self.paused.swap(false, Ordering::Relaxed) {
+            return false;
+        }
+        if let Some((_, end @ None)) = intervals.last_mut() {
+            *end = Some(now_ms);
+        }
+        true
+    }
+
+    /// Audio written by `now_ms`, in ms: the elapsed time without the paused stretches.
+    fn recorded_ms(&self, now_ms: u64) -> u64 {
+        let paused: u64 = self
+            .paused_intervals(now_ms)
+            .iter()
+            .map(|interval| interval.end - interval.start)
+            .sum();
+        now_ms.saturating_sub(paused)
+    }
+
+    /// Feed one `record_level` window to the auto-stop; returns why the recording should stop, once.
+    fn observe_level(&self, level: f32) -> Option<StopReason> {
+        if self.is_paused() {
+            return None;
+        }
+        let activity = self.activity.as_ref()?;
+        let recorded_ms = self.recorded_ms(self.elapsed_ms());
+        let reason = activity.lock().ok()?.push(recorded_ms, level)?;
+        *self.stop_reason.lock().unwrap_or_else(|error| error.into_inner()) = Some(reason);
+        Some(reason)
+    }
+
+    fn stop_reason(&self) -> Option<StopReason> {
+        *self.stop_reason.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// The stretch of audio to keep, in ms, when silence trimming is on.
+    fn trim_span(&self, recorded_ms: u64) -> Option<(u64, u64)> {
+        self.activity.as_ref()?.lock().ok()?.trim_span(recorded_ms)
+    }
+
+    /// Every paused stretch, with one still open at `now_ms` closed there.
+    fn paused_intervals(&self, now_ms: u64) -> Vec<PausedInterval> {
+        let intervals = self.intervals.lock().unwrap_or_else(|error| error.into_inner());
+        intervals
+            .iter()
+            .map(|&(start, end)| PausedInterval {
+                start,
+                end: end.unwrap_or(now_ms),
+            })
+            .collect()
+    }
+}
+
+/// The recording `start_record` is running, so `pause_record`/`resume_record` can reach it.
+#[derive(Default)]
+pub struct RecordingState(Mutex<Option<Arc<RecordingControl>>>);
+
+impl RecordingState {
+    pub fn is_recording(&self) -> bool {
+        self.current().is_some()
+    }
+
+    fn current(&self) -> Option<Arc<RecordingControl>> {
+        self.0.lock().ok().and_then(|current| current.clone())
+    }
+
+    fn set(&self, control: Option<Arc<RecordingControl>>) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = control;
+        }
+    }
+}
+
+/// Free space a recording needs before it starts, as minutes of audio in the devices' own format.
+/// Long meetings are checked again when the tracks are merged and normalized.
+const MIN_RECORDING_MINUTES: u64 = 10;
+/// How often a recording's WAV headers are brought up to date (see `recording::recovery`).
+const HEADER_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Buffers queued between a capture callback and its WAV writer before new ones are dropped.
+const WRITE_QUEUE_CAPACITY: usize = 256;
+
 /// Peak magnitude of a buffer, normalized to 0..1.
-fn buffer_peak<T>(input: &[T]) -> f32
+pub(crate) fn buffer_peak<T>(input: &[T]) -> f32
 where
     T: Sample,
     f32: FromSample<T>,
 {
     let mut peak = 0.0f32;
-    for &sample in input.iter().step_by(LEVEL_SAMPLE_STRIDE) {
+    for &sample in input {
         let value = f32::from_sample(sample).abs();
         if value > peak {
             peak = value;
@@ -131,23 +274,95 @@ where
     peak.min(1.0)
 }
 
+/// Which side of a call a recorded device captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackSource {
+    /// The local user.
+    Microphone,
+    /// Everyone else: the loopback of what the computer plays.
+    System,
+}
+
+impl TrackSource {
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Microphone => "microphone",
+            Self::System => "system",
+        }
+    }
+
+    /// Speaker id for a segment transcribed from this track alone. The local user is always 0;
+    /// remote participants start at 1, shifted past it when Sona diarized the system track.
+    pub fn speaker(self, diarized: Option<i32>) -> i32 {
+        match self {
+            Self::Microphone => 0,
+            Self::System => 1 + diarized.unwrap_or(0),
+        }
+    }
+}
+
+/// One device's audio saved on its own, reported in `record_finish` when `separate_tracks` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedTrack {
+    pub path: String,
+    pub source: TrackSource,
+}
+
 #[tauri::command]
-/// Record audio from the given devices, store to wav, merge with ffmpeg, and return path
+/// Record audio from the given devices, store to wav, mix to 16 kHz mono, and return path.
+/// With `live_transcription`, the audio is also transcribed while it is recorded (`recording::live`).
+/// With `separate_tracks`, each device is also saved on its own (`record_finish.tracks`).
+/// With `auto_stop`, the recording stops itself on silence or at a maximum duration and can
+/// have its leading and trailing silence trimmed (`recording::voice_activity`).
+#[allow(clippy::too_many_arguments)]
 pub async fn start_record(
     app_handle: AppHandle,
     devices: Vec<AudioDevice>,
     store_in_documents: bool,
     custom_path: Option<String>,
     recording_name: Option<String>,
-) -> Result<()> {
+    live_transcription: Option<LiveTranscriptionOptions>,
+    separate_tracks: Option<bool>,
+    auto_stop: Option<AutoStopOptions>,
+) -> Result<(), CommandError> {
     let host = cpal::default_host();
+    let separate_tracks = separate_tracks.unwrap_or(false);
+
+    let mut live = match live_transcription {
+        Some(options) => {
+            let sona_state = app_handle.state::<tokio::sync::Mutex<SonaState>>();
+            let (client, base_url) = {
+                let state = sona_state.lock().await;
+                let process = state.process.as_ref().ok_or_else(|| CommandError {
+                    code: "no_model".to_string(),
+                    message: "Please load model first".to_string(),
+                    details: None,
+                })?;
+                (process.client(), process.base_url())
+            };
+            Some(LiveTranscriber::start(
+                app_handle.clone(),
+                client,
+                base_url,
+                options,
+                !separate_tracks,
+            ))
+        }
+        None => None,
+    };
 
     let mut wav_paths: Vec<(PathBuf, u32)> = Vec::new();
+    let mut track_sources = Vec::new();
     let mut stream_handles = Vec::new();
     let mut stream_writers = Vec::new();
+    let mut writer_threads = Vec::new();
     // One meter for the whole session: input and output streams both feed it, so the UI sees
     // the max of the two under a single throttled `record_level` event.
-    let meter = Arc::new(LevelMeter::new(app_handle.clone()));
+    let control = Arc::new(RecordingControl::new(auto_stop.as_ref()));
+    let has_microphone = devices.iter().any(|device| device.is_input);
+    let meter = Arc::new(LevelMeter::new(app_handle.clone(), control.clone(), has_microphone));
+    let mut required_space = 0;
 
     for device in devices {
         tracing::debug!("Recording from device: {}", device.name);
@@ -155,8 +370,7 @@ pub async fn start_record(
 
         let is_input = device.is_input;
         let (device, config) = if is_input {
-            let device_id: usize = device.id.parse().context("Failed to parse device ID")?;
-            let dev = host.devices()?.nth(device_id).context("Failed to get device by ID")?;
+            let dev = crate::audio_devices::find(&host, &device.id)?;
             let config = dev.default_input_config().context("Failed to get default input config")?;
             (dev, config)
         } else {
@@ -166,15 +380,34 @@ pub async fn start_record(
 
         let path = get_vibe_temp_folder().join(format!("{}.wav", random_string(10)));
         tracing::debug!("WAV file path: {:?}", path);
+        required_space += disk_space::wav_size(
+            Duration::from_secs(MIN_RECORDING_MINUTES * 60),
+            spec.sample_rate,
+            spec.channels,
+            spec.bits_per_sample,
+        );
+        disk_space::ensure_space(&path, required_space)?;
         wav_paths.push((path.clone(), 0));
+        let source = if is_input {
+            TrackSource::Microphone
+        } else {
+            TrackSource::System
+        };
+        track_sources.push(source);
 
-        let writer = hound::WavWriter::create(path.clone(), spec)?;
+        let writer = hound::WavWriter::create(path.clone(), spec).context("Failed to create WAV file")?;
         let writer = Arc::new(Mutex::new(Some(writer)));
         stream_writers.push(writer.clone());
         let writer_2 = writer.clone();
 
-        let stream = build_input_stream(&device, config, writer_2, meter.clone())?;
-        stream.play()?;
+        let speaker = separate_tracks.then(|| source.speaker(None));
+        let tap = live
+            .as_mut()
+            .map(|live| live.tap(config.sample_rate(), config.channels(), speaker));
+        let (stream, writer_thread) =
+            build_input_stream(&device, config, writer_2, meter.clone(), is_input, control.clone(), tap)?;
+        writer_threads.push(writer_thread);
+        stream.play().context("Failed to start stream")?;
         tracing::debug!("Stream started playing");
 
         let stream_handle = Arc::new(Mutex::new(Some(StreamHandle(stream))));
@@ -182,9 +415,46 @@ pub async fn start_record(
         tracing::debug!("Stream handle created");
     }
 
+    // Lets the next start recover this recording if the app dies before `stop_record`.
+    let manifest = RecordingManifest {
+        id: random_string(10),
+        pid: std::process::id(),
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        recording_name,
+        store_in_documents,
+        custom_path,
+        separate_tracks,
+        archive_format: ArchiveFormat::from_config(&app_handle),
+        tracks: wav_paths
+            .iter()
+            .zip(&track_sources)
+            .map(|((path, _), source)| ManifestTrack {
+                path: path.clone(),
+                source: *source,
+            })
+            .collect(),
+    };
+    let manifest_path = manifest.save().log_error();
+
+    let state = app_handle.state::<RecordingState>();
+    if state.current().is_some() {
+        tracing::warn!("start_record called while another recording is active; pause controls move to the new one");
+    }
+    state.set(Some(control.clone()));
+
     let app_handle_clone = app_handle.clone();
     app_handle.once("stop_record", move |_event| {
-        for (i, stream_handle) in stream_handles.iter().enumerate() {
+        let state = app_handle_clone.state::<RecordingState>();
+        if state.current().is_some_and(|current| Arc::ptr_eq(&current, &control)) {
+            state.set(None);
+        }
+        let now_ms = control.elapsed_ms();
+        let paused_intervals = control.paused_intervals(now_ms);
+        let trim = control.trim_span(control.recorded_ms(now_ms));
+        for (i, (stream_handle, writer_thread)) in stream_handles.iter().zip(writer_threads).enumerate() {
             let stream_handle = stream_handle.lock().map_err(|e| eyre!("{:?}", e)).log_error();
             if let Some(mut stream_handle) = stream_handle {
                 let stream = stream_handle.take();
@@ -192,6 +462,11 @@ pub async fn start_record(
                 if let Some(stream) = stream {
                     tracing::debug!("Pausing stream");
                     stream.0.pause().map_err(|e| eyre!("{:?}", e)).log_error();
+                    // Dropping the stream closes the write queue; the writer drains it and exits.
+                    drop(stream);
+                    if writer_thread.join().is_err() {
+                        tracing::error!("WAV writer thread panicked");
+                    }
                     tracing::debug!("Finalizing writer");
                     let writer = writer.lock().expect("lock").take().expect("writer");
                     let written = writer.len();
@@ -201,85 +476,361 @@ pub async fn start_record(
             }
         }
 
-        let dst = if wav_paths.len() == 1 {
-            wav_paths[0].0.clone()
-        } else if wav_paths[0].1 > 0 && wav_paths[1].1 > 0 {
-            let dst = get_vibe_temp_folder().join(format!("{}.wav", random_string(10)));
-            tracing::debug!("Merging WAV files");
-            crate::ffmpeg::merge_wav_files(wav_paths[0].0.clone(), wav_paths[1].0.clone(), dst.clone()).map_err(|e| eyre!("{e:?}")).log_error();
-            dst
-        } else if wav_paths[0].1 > wav_paths[1].1 {
-            // First WAV file has a larger sample count, choose it
-            wav_paths[0].0.clone()
-        } else {
-            // Second WAV file has a larger sample count or both have non-positive sample counts,
-            // choose the second WAV file or fallback to the first one
-            wav_paths[1].0.clone()
-        };
-
+        let finished = finish_recording(&app_handle_clone, &manifest, &wav_paths, trim);
+        if let Some(manifest_path) = &manifest_path {
+            std::fs::remove_file(manifest_path).map_err(|e| eyre!("{e:?}")).log_error();
+        }
         tracing::debug!("Emitting record_finish event");
-        let recording_stem = recording_name
-            .as_deref()
-            .map(crate::cmd::files::sanitize_filename_stem)
-            .filter(|name| !name.is_empty())
-            .unwrap_or_else(get_local_time);
-        let temp_dir = get_vibe_temp_folder();
-        let mut normalized = crate::cmd::files::available_path(&temp_dir, &recording_stem, "wav");
-        crate::ffmpeg::normalize(dst.clone(), normalized.clone(), None).map_err(|e| eyre!("{e:?}")).log_error();
-
-        if store_in_documents {
-            if normalized.file_name().is_some() {
-                let save_dir = if let Some(ref cp) = custom_path {
-                    Some(PathBuf::from(cp))
-                } else {
-                    app_handle_clone.path().document_dir().map(|d| d.join(crate::config::DOCUMENTS_SUBFOLDER)).map_err(|e| eyre!("{e:?}")).log_error()
-                };
-                if let Some(save_dir) = save_dir {
-                    if std::fs::create_dir_all(&save_dir)
-                        .context("Failed to create recording directory")
-                        .map_err(|e| eyre!("{e:?}"))
-                        .is_ok()
-                    {
-                        let target_path = crate::cmd::files::available_path(&save_dir, &recording_stem, "wav");
-                        let moved = std::fs::rename(&normalized, &target_path)
-                            .context("Failed to move file to directory")
-                            .map_err(|e| eyre!("{e:?}"))
-                            .is_ok();
-                        let copied = if moved {
-                            false
-                        } else {
-                            // Cross-filesystem moves can fail; copy as fallback.
-                            std::fs::copy(&normalized, &target_path)
-                                .context("Failed to copy file to directory")
-                                .map_err(|e| eyre!("{e:?}"))
-                                .is_ok()
-                        };
-
-                        if moved || copied {
-                            if copied {
-                                std::fs::remove_file(&normalized).map_err(|e| eyre!("{e:?}")).log_error();
-                            }
-                            normalized = target_path;
-                        }
-                    }
-                }
-            } else {
-                tracing::error!("Failed to retrieve file name from destination path");
+        app_handle_clone
+            .emit(
+                "record_finish",
+                json!({
+                    "path": finished.path,
+                    "name": finished.name,
+                    "pausedIntervals": paused_intervals,
+                    "tracks": finished.tracks,
+                    "archivePath": finished.archive_path,
+                    "format": finished.format,
+                    "stopReason": control.stop_reason(),
+                    "trimmed": trim.map(|(start, end)| json!({"start": start, "end": end})),
+                }),
+            )
+            .map_err(|e| eyre!("{e:?}"))
+            .log_error();
+    });
+
+    Ok(())
+}
+
+/// A recording once its tracks are mixed, normalized and stored.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishedRecording {
+    /// The WAV to transcribe.
+    pub path: String,
+    pub name: String,
+    pub tracks: Vec<RecordedTrack>,
+    /// The copy kept in the recordings folder, when the recording is stored there. The same file
+    /// as `path` for WAV.
+    pub archive_path: Option<String>,
+    pub format: ArchiveFormat,
+}
+
+/// Mix the raw tracks of a stopped (or recovered) recording, normalize and store the result, and
+/// delete the raw files. `wav_paths` holds each track with the number of samples written to it;
+/// `trim` is the stretch to keep, in ms, when silence trimming cut the rest.
+fn finish_recording(
+    app_handle: &AppHandle,
+    manifest: &RecordingManifest,
+    wav_paths: &[(PathBuf, u32)],
+    trim: Option<(u64, u64)>,
+) -> FinishedRecording {
+    // Mix both tracks when both captured audio, otherwise keep the one that did.
+    let sources: Vec<&Path> = if wav_paths.len() == 1 {
+        vec![&wav_paths[0].0]
+    } else if wav_paths[0].1 > 0 && wav_paths[1].1 > 0 {
+        vec![&wav_paths[0].0, &wav_paths[1].0]
+    } else if wav_paths[0].1 > wav_paths[1].1 {
+        vec![&wav_paths[0].0]
+    } else {
+        vec![&wav_paths[1].0]
+    };
+
+    let recording_stem = manifest
+        .recording_name
+        .as_deref()
+        .map(crate::cmd::files::sanitize_filename_stem)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(get_local_time);
+    let save_dir = if manifest.store_in_documents {
+        recording_folder(app_handle, manifest.custom_path.as_deref())
+    } else {
+        None
+    };
+    let normalized = normalize_recording(app_handle, &sources, &recording_stem);
+    if let Some((start, end)) = trim {
+        trim_recording(&normalized, start, end).log_error();
+    }
+    let archive = save_dir
+        .as_deref()
+        .filter(|_| manifest.archive_format != ArchiveFormat::Wav)
+        .and_then(|save_dir| archive_recording(app_handle, &normalized, save_dir, &recording_stem, manifest.archive_format));
+    let (normalized, archive, format) = match archive {
+        // The WAV stays in the temp folder for Sona; the compressed copy is the one kept.
+        Some(archive) => (normalized, Some(archive), manifest.archive_format),
+        None => {
+            let stored = store_recording(normalized, save_dir.as_deref(), &recording_stem);
+            let archive = save_dir.is_some().then(|| stored.clone());
+            (stored, archive, ArchiveFormat::Wav)
+        }
+    };
+
+    // Each device on its own next to the mix, so the tracks can be transcribed per speaker.
+    let mut tracks = Vec::new();
+    if manifest.separate_tracks && wav_paths.len() > 1 {
+        for ((path, written), track) in wav_paths.iter().zip(&manifest.tracks) {
+            if *written == 0 || !path.exists() {
+                continue;
+            }
+            let stem = format!("{}.{}", recording_stem, track.source.suffix());
+            let normalized = normalize_recording(app_handle, &[path], &stem);
+            if let Some((start, end)) = trim {
+                trim_recording(&normalized, start, end).log_error();
             }
+            let stored = store_recording(normalized, save_dir.as_deref(), &stem);
+            tracks.push(RecordedTrack {
+                path: stored.to_string_lossy().to_string(),
+                source: track.source,
+            });
+        }
+    }
+
+    // Clean files
+    for (path, _) in wav_paths {
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|e| eyre!("{e:?}")).log_error();
         }
+    }
+    FinishedRecording {
+        path: normalized.to_string_lossy().to_string(),
+        name: normalized
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        tracks,
+        archive_path: archive.map(|archive| archive.to_string_lossy().to_string()),
+        format,
+    }
+}
+
+/// Where `start_record` saves recordings when asked to keep them.
+fn recording_folder(app_handle: &AppHandle, custom_path: Option<&str>) -> Option<PathBuf> {
+    match custom_path {
+        Some(custom_path) => Some(PathBuf::from(custom_path)),
+        None => app_handle
+            .path()
+            .document_dir()
+            .map(|d| d.join(crate::config::DOCUMENTS_SUBFOLDER))
+            .map_err(|e| eyre!("{e:?}"))
+            .log_error(),
+    }
+}
 
-        // Clean files
-        for (path, _) in wav_paths {
-            if path.exists() {
-                std::fs::remove_file(path).map_err(|e| eyre!("{e:?}")).log_error();
+/// Mix finished tracks into the 16 kHz WAV Sona reads, named after the recording.
+fn normalize_recording(app_handle: &AppHandle, sources: &[&Path], stem: &str) -> PathBuf {
+    let normalized = crate::cmd::files::available_path(&get_vibe_temp_folder(), stem, "wav");
+    let durations: Vec<Duration> = sources
+        .iter()
+        .map(|source| disk_space::wav_duration(source).unwrap_or_default())
+        .collect();
+    let longest = durations.iter().max().copied().unwrap_or_default();
+    match disk_space::ensure_space(&normalized, disk_space::normalized_wav_size(longest)) {
+        Ok(()) => {
+            if let Err(error) = recording::normalize::normalize(sources, &normalized) {
+                tracing::error!("failed to normalize the recording: {:?}", error);
+                // ffmpeg, or Symphonia without it, reads WAV variants that hound does not.
+                let conversion = ConversionManager::start(app_handle, ConversionKind::Normalize);
+                crate::ffmpeg::normalize(sources[0].to_path_buf(), normalized.clone(), None, &conversion.handle)
+                    .map_err(|e| eyre!("{e:?}"))
+                    .log_error();
             }
         }
-        app_handle_clone.emit(
-            "record_finish",
-            json!({"path": normalized.to_string_lossy(), "name": normalized.file_name().map(|n| n.to_str().unwrap_or_default()).unwrap_or_default()}),
-        ).map_err(|e| eyre!("{e:?}")).log_error();
-    });
+        Err(error) => {
+            // Keep the longest raw track rather than lose it to a half-written conversion.
+            tracing::warn!("not normalizing the recording: {:?}", error);
+            let longest_source = sources
+                .iter()
+                .zip(&durations)
+                .max_by_key(|(_, duration)| **duration)
+                .map(|(source, _)| *source);
+            if let Some(source) = longest_source {
+                std::fs::rename(source, &normalized).map_err(|e| eyre!("{e:?}")).log_error();
+            }
+        }
+    }
+    normalized
+}
 
+/// Keep only `start_ms..end_ms` of a WAV file, in place.
+fn trim_recording(path: &Path, start_ms: u64, end_ms: u64) -> Result<()> {
+    let mut reader = hound::WavReader::open(path).context(format!("Failed to open {}", path.display()))?;
+    let spec = reader.spec();
+    let frame = |ms: u64| ms * u64::from(spec.sample_rate) / 1000 * u64::from(spec.channels);
+    let (start, end) = (frame(start_ms), frame(end_ms));
+    let trimmed = path.with_extension("trimmed.wav");
+    let mut writer = hound::WavWriter::create(&trimmed, spec).context("Failed to create the trimmed recording")?;
+    for sample in reader
+        .samples::<i32>()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+    {
+        writer.write_sample(sample?)?;
+    }
+    writer.finalize()?;
+    drop(reader);
+    std::fs::rename(&trimmed, path).context("Failed to replace the recording with its trimmed copy")?;
+    Ok(())
+}
+
+/// Encode a compressed copy of `source` into `save_dir`. None when that fails or is cancelled
+/// (`cancel_conversion`), so the caller can keep the WAV instead.
+fn archive_recording(
+    app_handle: &AppHandle,
+    source: &Path,
+    save_dir: &Path,
+    stem: &str,
+    format: ArchiveFormat,
+) -> Option<PathBuf> {
+    std::fs::create_dir_all(save_dir)
+        .context("Failed to create recording directory")
+        .map_err(|e| eyre!("{e:?}"))
+        .log_error()?;
+    let target_path = crate::cmd::files::available_path(save_dir, stem, format.extension());
+    // The compressed file is smaller than the WAV it comes from.
+    let size = std::fs::metadata(source).map(|metadata| metadata.len()).unwrap_or(0);
+    let conversion = ConversionManager::start(app_handle, ConversionKind::Archive);
+    let encoded = disk_space::ensure_space(&target_path, size)
+        .and_then(|_| archive::encode(source, &target_path, format, &conversion.handle));
+    if let Err(error) = encoded {
+        tracing::error!("failed to archive the recording as {:?}: {:?}", format, error);
+        if target_path.exists() {
+            std::fs::remove_file(&target_path).map_err(|e| eyre!("{e:?}")).log_error();
+        }
+        return None;
+    }
+    Some(target_path)
+}
+
+/// Move a finished file into `save_dir`, or leave it where it is when there is none or the move fails.
+fn store_recording(file: PathBuf, save_dir: Option<&Path>, stem: &str) -> PathBuf {
+    let Some(save_dir) = save_dir else {
+        return file;
+    };
+    if std::fs::create_dir_all(save_dir)
+        .context("Failed to create recording directory")
+        .map_err(|e| eyre!("{e:?}"))
+        .log_error()
+        .is_none()
+    {
+        return file;
+    }
+    let extension = file.extension().and_then(|extension| extension.to_str()).unwrap_or("wav");
+    let target_path = crate::cmd::files::available_path(save_dir, stem, extension);
+    if std::fs::rename(&file, &target_path).is_ok() {
+        return target_path;
+    }
+    // Cross-filesystem moves can fail; copy as fallback.
+    let size = std::fs::metadata(&file).map(|metadata| metadata.len()).unwrap_or(0);
+    let copied = disk_space::ensure_space(&target_path, size)
+        .and_then(|_| std::fs::copy(&file, &target_path).context("Failed to copy file to directory"))
+        .map_err(|e| eyre!("{e:?}"))
+        .log_error()
+        .is_some();
+    if !copied {
+        return file;
+    }
+    std::fs::remove_file(&file).map_err(|e| eyre!("{e:?}")).log_error();
+    target_path
+}
+
+/// A recording from an earlier run that never stopped.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedRecording {
+    pub id: String,
+    pub started_at: u64,
+    pub recording_name: Option<String>,
+    /// Bytes of raw audio left on disk.
+    pub size: u64,
+}
+
+/// Recordings interrupted by a crash, for the user to recover or discard.
+#[tauri::command]
+pub fn list_orphaned_recordings() -> Result<Vec<OrphanedRecording>> {
+    Ok(recovery::find_orphans()?
+        .into_iter()
+        .map(|(_, manifest)| OrphanedRecording {
+            size: manifest
+                .tracks
+                .iter()
+                .filter_map(|track| std::fs::metadata(&track.path).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+            id: manifest.id,
+            started_at: manifest.started_at,
+            recording_name: manifest.recording_name,
+        })
+        .collect())
+}
+
+fn find_orphan(id: &str) -> Result<(PathBuf, RecordingManifest), CommandError> {
+    recovery::find_orphans()?
+        .into_iter()
+        .find(|(_, manifest)| manifest.id == id)
+        .ok_or_else(|| CommandError {
+            code: "invalid_request".to_string(),
+            message: format!("No interrupted recording with id {id}"),
+            details: None,
+        })
+}
+
+/// Repair the tracks of an interrupted recording and finish it as `stop_record` would have.
+#[tauri::command]
+pub async fn recover_recording(app_handle: AppHandle, id: String) -> Result<FinishedRecording, CommandError> {
+    let (manifest_path, manifest) = find_orphan(&id)?;
+    let finished = tauri::async_runtime::spawn_blocking(move || {
+        let wav_paths: Vec<(PathBuf, u32)> = manifest
+            .tracks
+            .iter()
+            .filter(|track| track.path.exists())
+            .map(|track| {
+                let written = recovery::repair_wav(&track.path).log_error().unwrap_or(0);
+                (track.path.clone(), written)
+            })
+            .collect();
+        if wav_paths.iter().all(|(_, written)| *written == 0) {
+            bail!("The interrupted recording holds no audio");
+        }
+        let finished = finish_recording(&app_handle, &manifest, &wav_paths, None);
+        std::fs::remove_file(&manifest_path).map_err(|e| eyre!("{e:?}")).log_error();
+        Ok(finished)
+    })
+    .await
+    .map_err(|e| eyre!("{e:?}"))??;
+    Ok(finished)
+}
+
+/// Delete an interrupted recording without recovering it.
+#[tauri::command]
+pub fn discard_recording(id: String) -> Result<(), CommandError> {
+    let (manifest_path, manifest) = find_orphan(&id)?;
+    for track in manifest.tracks {
+        if track.path.exists() {
+            std::fs::remove_file(&track.path).map_err(|e| eyre!("{e:?}")).log_error();
+        }
+    }
+    std::fs::remove_file(&manifest_path).context("Failed to delete the recording manifest")?;
+    Ok(())
+}
+
+/// Stop writing samples until `resume_record`, keeping the WAV files open.
+#[tauri::command]
+pub fn pause_record(app_handle: AppHandle, state: State<'_, RecordingState>) -> Result<()> {
+    let control = state.current().context("No recording in progress")?;
+    if control.pause_at(control.elapsed_ms()) {
+        tracing::debug!("recording paused");
+        app_handle.emit("record_paused", ()).log_error();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_record(app_handle: AppHandle, state: State<'_, RecordingState>) -> Result<()> {
+    let control = state.current().context("No recording in progress")?;
+    if control.resume_at(control.elapsed_ms()) {
+        tracing::debug!("recording resumed");
+        app_handle.emit("record_resumed", ()).log_error();
+    }
     Ok(())
 }
 
@@ -298,8 +849,7 @@ fn get_output_device_and_config(host: &cpal::Host, audio_device: &AudioDevice) -
 
     #[cfg(not(target_os = "macos"))]
     {
-        let device_id: usize = audio_device.id.parse().context("Failed to parse device ID")?;
-        let device = host.devices()?.nth(device_id).context("Failed to get device by ID")?;
+        let device = crate::audio_devices::find(host, &audio_device.id)?;
         let config = device
             .default_output_config()
             .context("Failed to get default output config")?;
@@ -312,21 +862,34 @@ fn build_input_stream_typed<T>(
     config: SupportedStreamConfig,
     writer: WavWriterHandle,
     meter: Arc<LevelMeter>,
-) -> Result<Stream>
+    microphone: bool,
+    control: Arc<RecordingControl>,
+    tap: Option<LiveTap>,
+) -> Result<(Stream, JoinHandle<()>)>
 where
-    T: SizedSample + hound::Sample + FromSample<T> + Mul<Output = T> + Copy,
+    T: SizedSample + hound::Sample + Send + 'static,
     f32: FromSample<T>,
 {
+    let (sender, receiver) = sync_channel::<Vec<T>>(WRITE_QUEUE_CAPACITY);
+    let writer_thread = spawn_wav_writer(writer, receiver);
     let stream = device.build_input_stream(
         config.into(),
         move |data: &[T], _: &_| {
-            meter.push(buffer_peak(data));
-            write_input_data::<T, T>(data, &writer)
+            if control.is_paused() {
+                meter.push(0.0, microphone);
+                return;
+            }
+            meter.push(buffer_peak(data), microphone);
+            if let Some(ref tap) = tap {
+                tap.feed(data);
+            }
+            // Never block the audio thread on disk: if the writer fell that far behind, lose a buffer.
+            let _ = sender.try_send(data.to_vec());
         },
         |err| tracing::error!("An error occurred on stream: {}", err),
         None,
     )?;
-    Ok(stream)
+    Ok((stream, writer_thread))
 }
 
 fn build_input_stream(
@@ -334,12 +897,15 @@ fn build_input_stream(
     config: SupportedStreamConfig,
     writer: WavWriterHandle,
     meter: Arc<LevelMeter>,
-) -> Result<Stream> {
+    microphone: bool,
+    control: Arc<RecordingControl>,
+    tap: Option<LiveTap>,
+) -> Result<(Stream, JoinHandle<()>)> {
     match config.sample_format() {
-        cpal::SampleFormat::I8 => build_input_stream_typed::<i8>(device, config, writer, meter),
-        cpal::SampleFormat::I16 => build_input_stream_typed::<i16>(device, config, writer, meter),
-        cpal::SampleFormat::I32 => build_input_stream_typed::<i32>(device, config, writer, meter),
-        cpal::SampleFormat::F32 => build_input_stream_typed::<f32>(device, config, writer, meter),
+        cpal::SampleFormat::I8 => build_input_stream_typed::<i8>(device, config, writer, meter, microphone, control, tap),
+        cpal::SampleFormat::I16 => build_input_stream_typed::<i16>(device, config, writer, meter, microphone, control, tap),
+        cpal::SampleFormat::I32 => build_input_stream_typed::<i32>(device, config, writer, meter, microphone, control, tap),
+        cpal::SampleFormat::F32 => build_input_stream_typed::<f32>(device, config, writer, meter, microphone, control, tap),
         sample_format => bail!("Unsupported sample format '{}'", sample_format),
     }
 }
@@ -361,19 +927,90 @@ fn wav_spec_from_config(config: &cpal::SupportedStreamConfig) -> hound::WavSpec
     }
 }
 
-use std::ops::Mul;
-
-fn write_input_data<T, U>(input: &[T], writer: &WavWriterHandle)
+/// Write a stream's buffers to its WAV file off the audio thread, and bring the header up to date
+/// now and then so a crash loses at most the audio since. Runs until the stream is dropped.
+fn spawn_wav_writer<T>(writer: WavWriterHandle, buffers: Receiver<Vec<T>>) -> JoinHandle<()>
 where
-    T: Sample,
-    U: Sample + hound::Sample + FromSample<T> + Mul<Output = U> + Copy,
+    T: hound::Sample + Send + 'static,
 {
-    if let Ok(mut guard) = writer.try_lock() {
-        if let Some(writer) = guard.as_mut() {
-            for &sample in input.iter() {
-                let sample: U = U::from_sample(sample);
+    std::thread::spawn(move || {
+        let mut last_flush = Instant::now();
+        for buffer in buffers {
+            let Ok(mut guard) = writer.lock() else {
+                return;
+            };
+            let Some(writer) = guard.as_mut() else {
+                return;
+            };
+            for sample in buffer {
                 writer.write_sample(sample).ok();
             }
+            if last_flush.elapsed() >= HEADER_FLUSH_INTERVAL {
+                last_flush = Instant::now();
+                writer.flush().map_err(|e| eyre!("{e:?}")).log_error();
+            }
         }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{buffer_peak, spawn_wav_writer, PausedInterval, RecordingControl};
+    use std::sync::{mpsc::sync_channel, Arc, Mutex};
+
+    #[test]
+    fn paused_intervals_close_on_resume_and_at_stop() {
+        let control = RecordingControl::new(None);
+        assert!(control.pause_at(1_000));
+        assert!(!control.pause_at(1_500));
+        assert!(control.is_paused());
+        assert!(control.resume_at(3_000));
+        assert!(!control.resume_at(3_500));
+        assert!(control.pause_at(5_000));
+
+        assert_eq!(
+            control.paused_intervals(6_000),
+            [
+                PausedInterval {
+                    start: 1_000,
+                    end: 3_000
+                },
+                PausedInterval {
+                    start: 5_000,
+                    end: 6_000
+                },
+            ]
+        );
+        assert_eq!(control.recorded_ms(6_000), 3_000);
+    }
+
+    #[test]
+    fn peaks_come_from_every_sample() {
+        let mut samples = [0.1f32; 8];
+        samples[5] = -0.8;
+        assert_eq!(buffer_peak(&samples), 0.8);
+        assert_eq!(buffer_peak(&[0i16, i16::MIN, 0]), 1.0);
+    }
+
+    #[test]
+    fn wav_writer_drains_its_queue_once_the_stream_is_gone() {
+        let path = std::env::temp_dir().join(format!("vibe-writer-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = Arc::new(Mutex::new(Some(hound::WavWriter::create(&path, spec).unwrap())));
+        let (sender, receiver) = sync_channel::<Vec<i16>>(4);
+        let thread = spawn_wav_writer(writer.clone(), receiver);
+        sender.send(vec![1, 2, 3]).unwrap();
+        sender.send(vec![4]).unwrap();
+        drop(sender);
+        thread.join().unwrap();
+
+        writer.lock().unwrap().take().unwrap().finalize().unwrap();
+        assert_eq!(hound::WavReader::open(&path).unwrap().len(), 4);
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,107 @@
+use crate::error::LogError;
+use crate::ffmpeg::ConversionHandle;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// What a conversion is for, so the frontend can word its progress.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionKind {
+    /// Into the 16 kHz WAV Sona reads.
+    Normalize,
+    /// A compressed copy of a recording.
+    Archive,
+    /// Through the audio filters, before transcribing.
+    Preprocess,
+    /// Audio clips of transcript segments.
+    Clips,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConversionProgress<'a> {
+    id: &'a str,
+    kind: ConversionKind,
+    /// Percent of the input converted.
+    progress: f64,
+}
+
+/// Tracks running ffmpeg conversions by id so each can be cancelled on its own.
+pub struct ConversionManager {
+    next_id: AtomicU64,
+    active: Mutex<HashMap<String, ConversionHandle>>,
+}
+
+impl Default for ConversionManager {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// A registered conversion; hand `handle` to ffmpeg. Unregistered when dropped.
+pub struct TrackedConversion {
+    app_handle: AppHandle,
+    id: String,
+    pub handle: ConversionHandle,
+}
+
+impl Drop for TrackedConversion {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.app_handle.state::<ConversionManager>().active.lock() {
+            active.remove(&self.id);
+        }
+    }
+}
+
+impl ConversionManager {
+    /// Register a conversion that reports `conversion_progress` events and stops on `cancel_conversion`.
+    pub fn start(app_handle: &AppHandle, kind: ConversionKind) -> TrackedConversion {
+        let manager = app_handle.state::<ConversionManager>();
+        let id = format!("conversion-{}", manager.next_id.fetch_add(1, Ordering::Relaxed));
+        let app_handle_c = app_handle.clone();
+        let id_c = id.clone();
+        let handle = ConversionHandle::with_progress(move |progress| {
+            let payload = ConversionProgress {
+                id: &id_c,
+                kind,
+                progress,
+            };
+            app_handle_c.emit("conversion_progress", payload).log_error();
+        });
+        if let Ok(mut active) = manager.active.lock() {
+            active.insert(id.clone(), handle.clone());
+        }
+        TrackedConversion {
+            app_handle: app_handle.clone(),
+            id,
+            handle,
+        }
+    }
+
+    /// Returns whether a conversion with this id was running.
+    pub fn cancel(&self, id: &str) -> bool {
+        let Ok(active) = self.active.lock() else {
+            return false;
+        };
+        match active.get(id) {
+            Some(handle) => {
+                handle.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Cancel one conversion by the id of its `conversion_progress` events. Returns false when it
+/// already finished.
+#[tauri::command]
+pub fn cancel_conversion(manager: State<'_, ConversionManager>, id: String) -> bool {
+    manager.cancel(&id)
+}
@@ -1,15 +1,37 @@
+use crate::config::STORE_FILENAME;
+use crate::disk_space;
 use crate::error::LogError;
+use crate::model_manifest::{self, ChecksumMismatch, ExpectedDigest};
 use eyre::{bail, Context, Result};
-use futures::future::{AbortHandle, Abortable};
+use futures::future::{AbortHandle, AbortRegistration, Abortable};
 use futures_util::StreamExt;
-use serde::Serialize;
+use reqwest::header::{HeaderName, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     io::Write,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
 };
-use tauri::{Emitter, Listener, Manager};
+use tauri::{Emitter, Listener, Manager, State};
+use tauri_plugin_store::StoreExt;
 
-use super::ui::set_progress_bar;
+use super::{ui::set_progress_bar, CommandError};
+
+/// Attempts per download, counting the first one.
+const MAX_ATTEMPTS: u32 = 5;
+const MAX_BACKOFF_MS: u64 = 30_000;
+/// Downloads transferring at once; the rest wait as `queued`.
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+
+/// Keys in `app_config.json` (`lib/config-keys.ts`).
+const CONFIG_KEY_PROXY: &str = "downloads.proxy";
+const CONFIG_KEY_MAX_BYTES_PER_SECOND: &str = "downloads.maxBytesPerSecond";
 
 #[derive(Debug, Serialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
@@ -70,104 +92,623 @@ fn publish_download(partial: &Path, destination: &Path) -> Result<()> {
     }
 }
 
-async fn download_to_partial(
-    app_handle: &tauri::AppHandle,
-    url: &str,
-    destination: &Path,
+/// Validators of the response a `.part` file was started from. Kept next to the partial file so a
+/// later attempt only resumes when the server still serves the same bytes; without them a resumed
+/// download could splice two different versions of a model together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PartialValidator {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartialValidator {
+    fn from_response(url: &str, response: &reqwest::Response) -> Option<Self> {
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validator = Self {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        validator.if_range().is_some().then_some(validator)
+    }
+
+    /// Value for `If-Range`. Weak ETags are not allowed there, so they fall back to the date.
+    fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+fn validator_path(partial: &Path) -> PathBuf {
+    let mut path = partial.as_os_str().to_os_string();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+fn read_validator(partial: &Path) -> Option<PartialValidator> {
+    let contents = std::fs::read_to_string(validator_path(partial)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_validator(partial: &Path, validator: Option<&PartialValidator>) -> Result<()> {
+    let path = validator_path(partial);
+    match validator {
+        Some(validator) => std::fs::write(&path, serde_json::to_string(validator)?)
+            .context(format!("Failed to write download state {}", path.display())),
+        None => remove_if_exists(&path),
+    }
+}
+
+/// Remove the partial file together with its validator.
+fn discard_partial(partial: &Path) -> Result<()> {
+    remove_if_exists(partial)?;
+    remove_if_exists(&validator_path(partial))
+}
+
+/// `Content-Range: bytes <start>-<end>/<total>` as `(start, total)`; the total is `None` for `*`.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _end) = span.split_once('-')?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start.trim().parse().ok()?, total))
+}
+
+/// The server refused or garbled a resume. The partial file is gone by the time this is returned,
+/// so the retry starts from zero.
+#[derive(Debug)]
+struct ResumeRejected;
+
+impl std::fmt::Display for ResumeRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "server rejected the resume request")
+    }
+}
+
+impl std::error::Error for ResumeRejected {}
+
+/// Connection drops, timeouts and overloaded servers are worth another try; a 404 or a disk error
+/// will fail the same way again.
+fn is_transient(error: &eyre::Report) -> bool {
+    if error.downcast_ref::<ResumeRejected>().is_some() {
+        return true;
+    }
+    error.chain().any(|cause| {
+        let Some(error) = cause.downcast_ref::<reqwest::Error>() else {
+            return false;
+        };
+        if let Some(status) = error.status() {
+            return status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+        }
+        error.is_timeout() || error.is_connect() || error.is_request() || error.is_body() || error.is_decode()
+    })
+}
+
+/// 1s, 2s, 4s, 8s... capped so a flaky network never leaves the user waiting on a silent retry.
+fn backoff_delay(attempt: u32) -> Duration {
+    Duration::from_millis((1000 * (1u64 << attempt.min(16))).min(MAX_BACKOFF_MS))
+}
+
+/// Per-download progress, so several downloads can be shown side by side.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadItemProgress<'a> {
+    id: &'a str,
+    downloaded: u64,
+    total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DownloadStatus {
+    /// Waiting for a free slot.
+    Queued,
+    Downloading,
+    Verifying,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadItemStatus<'a> {
+    id: &'a str,
+    status: DownloadStatus,
+    /// The mirror being used, once a transfer started.
+    url: Option<&'a str>,
+    error: Option<String>,
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, id: &str, status: DownloadStatus, url: Option<&str>, error: Option<String>) {
+    app_handle
+        .emit("download_item_status", DownloadItemStatus { id, status, url, error })
+        .log_error();
+}
+
+/// Progress carried across attempts, so a retry continues the bar instead of restarting it.
+struct DownloadProgress<'a> {
+    app_handle: &'a tauri::AppHandle,
+    id: &'a str,
     show_system_progress: bool,
-) -> Result<DownloadOutcome> {
-    let partial = partial_path(destination);
-    remove_if_exists(&partial)?;
-
-    let (abort_handle, abort_registration) = AbortHandle::new_pair();
-    let app_handle_listener = app_handle.clone();
-    let listener_id = app_handle.listen("abort_download", move |_| {
-        if show_system_progress {
-            set_progress_bar(&app_handle_listener, None).log_error();
-        }
-        abort_handle.abort();
-    });
-
-    let operation = async {
-        let client = reqwest::Client::new();
-        let response = client.get(url).send().await?.error_for_status()?;
-        let total_size = response.content_length().unwrap_or(0);
-        let mut file = std::fs::File::create(&partial).context(format!("Failed to create file {}", partial.display()))?;
-        let mut downloaded: u64 = 0;
+    callback_offset: u64,
+}
+
+impl DownloadProgress<'_> {
+    fn report(&mut self, downloaded: u64, total_size: u64) {
         let callback_limit: u64 = 1024 * 1024 * 2;
-        let mut callback_offset: u64 = 0;
-        let mut stream = response.bytes_stream();
-
-        while let Some(item) = stream.next().await {
-            let chunk = item.context("Error while downloading file")?;
-            file.write_all(&chunk)
-                .context(format!("Error while writing to file {}", partial.display()))?;
-            downloaded += chunk.len() as u64;
-
-            if total_size > 0 && downloaded > callback_offset + callback_limit {
-                let percentage = (downloaded as f64 / total_size as f64) * 100.0;
-                tracing::trace!("percentage: {}", percentage);
-                if show_system_progress {
-                    set_progress_bar(app_handle, Some(percentage)).log_error();
-                }
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    window.emit("download_progress", (downloaded, total_size)).log_error();
-                }
-                callback_offset = downloaded;
+        if total_size == 0 || downloaded <= self.callback_offset + callback_limit {
+            return;
+        }
+        let percentage = (downloaded as f64 / total_size as f64) * 100.0;
+        tracing::trace!("percentage: {}", percentage);
+        self.app_handle
+            .emit(
+                "download_item_progress",
+                DownloadItemProgress {
+                    id: self.id,
+                    downloaded,
+                    total: total_size,
+                },
+            )
+            .log_error();
+        // The system progress bar and the legacy event describe a single download: the model one.
+        if self.show_system_progress {
+            set_progress_bar(self.app_handle, Some(percentage)).log_error();
+            if let Some(window) = self.app_handle.get_webview_window("main") {
+                window.emit("download_progress", (downloaded, total_size)).log_error();
             }
         }
+        self.callback_offset = downloaded;
+    }
+}
+
+/// How long to wait so `received` bytes over `elapsed` stay under `limit` bytes per second.
+fn throttle_delay(received: u64, limit: u64, elapsed: Duration) -> Option<Duration> {
+    if limit == 0 {
+        return None;
+    }
+    let allowed = Duration::from_secs_f64(received as f64 / limit as f64);
+    allowed.checked_sub(elapsed).filter(|delay| !delay.is_zero())
+}
 
-        if total_size > 0 && downloaded != total_size {
-            bail!("Incomplete download: expected {} bytes, received {}", total_size, downloaded);
+/// One request, resuming from whatever is already in the partial file when the server agrees to.
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    partial: &Path,
+    max_bytes_per_sec: Option<u64>,
+    progress: &mut DownloadProgress<'_>,
+) -> Result<()> {
+    let existing = std::fs::metadata(partial).map(|metadata| metadata.len()).unwrap_or(0);
+    let validator = read_validator(partial).filter(|validator| validator.url == url);
+
+    let mut request = client.get(url);
+    let resume_from = match validator.as_ref().and_then(PartialValidator::if_range) {
+        Some(if_range) if existing > 0 => {
+            tracing::debug!("resuming download of {} from byte {}", url, existing);
+            request = request.header(RANGE, format!("bytes={existing}-")).header(IF_RANGE, if_range);
+            existing
         }
+        _ => 0,
+    };
 
-        file.flush().context(format!("Failed to flush file {}", partial.display()))?;
-        drop(file);
-        publish_download(&partial, destination)?;
-        Ok(DownloadOutcome::Completed)
+    let response = request.send().await?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The server says our offset is past the end. Either the partial already holds everything,
+        // or it belongs to a different file; only the first case is safe to keep.
+        let total = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range)
+            .and_then(|(_, total)| total);
+        if total == Some(existing) {
+            return Ok(());
+        }
+        discard_partial(partial)?;
+        return Err(ResumeRejected.into());
+    }
+    let response = response.error_for_status()?;
+
+    let (mut file, mut downloaded, total_size) = if response.status() == StatusCode::PARTIAL_CONTENT {
+        let content_range = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range);
+        let Some((start, total)) = content_range.filter(|(start, _)| *start == resume_from) else {
+            discard_partial(partial)?;
+            return Err(ResumeRejected.into());
+        };
+        if let Some(total) = total {
+            disk_space::ensure_space(partial, total.saturating_sub(start))?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(partial)
+            .context(format!("Failed to open file {}", partial.display()))?;
+        (file, start, total.unwrap_or(0))
+    } else {
+        // A plain 200: the server ignored the range (no support, or the file changed), so start over.
+        if resume_from > 0 {
+            tracing::debug!("server does not resume {}; restarting from zero", url);
+        }
+        let total = response.content_length().unwrap_or(0);
+        // The partial file is about to be truncated, so the space it holds counts as free.
+        disk_space::ensure_space(partial, total.saturating_sub(existing))?;
+        write_validator(partial, PartialValidator::from_response(url, &response).as_ref())?;
+        let file = std::fs::File::create(partial).context(format!("Failed to create file {}", partial.display()))?;
+        (file, 0, total)
     };
 
-    let result = match Abortable::new(operation, abort_registration).await {
-        Ok(result) => result,
-        Err(_) => Ok(DownloadOutcome::Cancelled),
+    let started = Instant::now();
+    let mut received = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(item) = stream.next().await {
+        let chunk = item.context("Error while downloading file")?;
+        file.write_all(&chunk)
+            .context(format!("Error while writing to file {}", partial.display()))?;
+        downloaded += chunk.len() as u64;
+        received += chunk.len() as u64;
+        progress.report(downloaded, total_size);
+        if let Some(delay) = max_bytes_per_sec.and_then(|limit| throttle_delay(received, limit, started.elapsed())) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    if total_size > 0 && downloaded != total_size {
+        bail!("Incomplete download: expected {} bytes, received {}", total_size, downloaded);
+    }
+
+    file.flush().context(format!("Failed to flush file {}", partial.display()))?;
+    Ok(())
+}
+
+/// Everything `DownloadManager::run` needs to fetch one file.
+struct DownloadRequest {
+    /// Mirrors, tried in order until one delivers the expected bytes.
+    urls: Vec<String>,
+    destination: PathBuf,
+    /// Drive the taskbar progress and the legacy `download_progress` event, and stop on an
+    /// `abort_download` without an id. Model downloads ask for it; only one of them gets it.
+    show_system_progress: bool,
+    expected: Option<ExpectedDigest>,
+    max_bytes_per_sec: Option<u64>,
+}
+
+/// Tracks downloads by id so each can be cancelled on its own, and caps how many transfer at once.
+pub struct DownloadManager {
+    next_id: AtomicU64,
+    active: Mutex<HashMap<String, AbortHandle>>,
+    /// The download holding the taskbar progress, so concurrent model downloads don't fight over it.
+    system_progress_owner: Mutex<Option<String>>,
+    slots: tokio::sync::Semaphore,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            active: Mutex::new(HashMap::new()),
+            system_progress_owner: Mutex::new(None),
+            slots: tokio::sync::Semaphore::new(MAX_CONCURRENT_DOWNLOADS),
+        }
+    }
+}
+
+impl DownloadManager {
+    fn register(&self, id: Option<String>) -> Result<(String, AbortHandle, AbortRegistration)> {
+        let id = id.unwrap_or_else(|| format!("download-{}", self.next_id.fetch_add(1, Ordering::Relaxed)));
+        let mut active = self.active.lock().map_err(|error| eyre::eyre!("{error}"))?;
+        if active.contains_key(&id) {
+            bail!("Download {} is already running", id);
+        }
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        active.insert(id.clone(), abort_handle.clone());
+        Ok((id, abort_handle, abort_registration))
+    }
+
+    fn finish(&self, id: &str) {
+        if let Ok(mut active) = self.active.lock() {
+            active.remove(id);
+        }
+    }
+
+    /// Returns false when another download already holds the taskbar progress.
+    fn claim_system_progress(&self, id: &str) -> bool {
+        let Ok(mut owner) = self.system_progress_owner.lock() else {
+            return false;
+        };
+        if owner.is_some() {
+            return false;
+        }
+        *owner = Some(id.to_string());
+        true
+    }
+
+    fn release_system_progress(&self, id: &str) {
+        if let Ok(mut owner) = self.system_progress_owner.lock() {
+            if owner.as_deref() == Some(id) {
+                *owner = None;
+            }
+        }
+    }
+
+    /// Returns whether a download with this id was running.
+    pub fn cancel(&self, id: &str) -> bool {
+        let Ok(active) = self.active.lock() else {
+            return false;
+        };
+        match active.get(id) {
+            Some(abort_handle) => {
+                abort_handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn run(
+        &self,
+        app_handle: &tauri::AppHandle,
+        id: Option<String>,
+        mut request: DownloadRequest,
+    ) -> Result<DownloadOutcome> {
+        let (id, abort_handle, abort_registration) = self.register(id)?;
+        let partial = partial_path(&request.destination);
+        request.show_system_progress = request.show_system_progress && self.claim_system_progress(&id);
+
+        // `abort_download` with an id cancels that download; without one it keeps its old meaning
+        // of "cancel the model download", which is the one holding the taskbar progress.
+        let id_c = id.clone();
+        let show_system_progress = request.show_system_progress;
+        let listener_id = app_handle.listen("abort_download", move |event| {
+            let target: Option<String> = serde_json::from_str(event.payload()).ok().flatten();
+            let matches = match target {
+                Some(target) => target == id_c,
+                None => show_system_progress,
+            };
+            if matches {
+                abort_handle.abort();
+            }
+        });
+
+        let operation = async {
+            emit_status(app_handle, &id, DownloadStatus::Queued, None, None);
+            let _slot = self.slots.acquire().await?;
+            let client = http_client(app_handle)?;
+            download_from_mirrors(app_handle, &client, &id, &request, &partial).await
+        };
+
+        let result = match Abortable::new(operation, abort_registration).await {
+            Ok(result) => result,
+            Err(_) => Ok(DownloadOutcome::Cancelled),
+        };
+
+        self.finish(&id);
+        app_handle.unlisten(listener_id);
+        if request.show_system_progress {
+            set_progress_bar(app_handle, None).log_error();
+            self.release_system_progress(&id);
+        }
+
+        // A failed download keeps its partial file so the next attempt resumes; a cancelled one was
+        // abandoned on purpose and should not linger next to the models.
+        match &result {
+            Ok(DownloadOutcome::Completed) => emit_status(app_handle, &id, DownloadStatus::Completed, None, None),
+            Ok(DownloadOutcome::Cancelled) => {
+                discard_partial(&partial).log_error();
+                emit_status(app_handle, &id, DownloadStatus::Cancelled, None, None);
+            }
+            Err(error) => emit_status(app_handle, &id, DownloadStatus::Failed, None, Some(format!("{error:#}"))),
+        }
+        result
+    }
+}
+
+/// Try each mirror in turn. A mirror that keeps failing, or serves bytes that do not verify, hands
+/// over to the next one; the last error is returned when none of them worked.
+async fn download_from_mirrors(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    id: &str,
+    request: &DownloadRequest,
+    partial: &Path,
+) -> Result<DownloadOutcome> {
+    let mut last_error = None;
+    for url in &request.urls {
+        emit_status(app_handle, id, DownloadStatus::Downloading, Some(url), None);
+        match download_verified(app_handle, client, id, url, request, partial).await {
+            Ok(()) => {
+                publish_download(partial, &request.destination)?;
+                return Ok(DownloadOutcome::Completed);
+            }
+            // Another mirror will not make the disk any bigger.
+            Err(error) if disk_space::find(&error).is_some() => return Err(error),
+            Err(error) => {
+                tracing::warn!(
+                    "download of {} from {} failed: {:?}",
+                    request.destination.display(),
+                    url,
+                    error
+                );
+                last_error = Some(error);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| eyre::eyre!("No download URL for {}", request.destination.display())))
+}
+
+async fn download_verified(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    id: &str,
+    url: &str,
+    request: &DownloadRequest,
+    partial: &Path,
+) -> Result<()> {
+    let mut progress = DownloadProgress {
+        app_handle,
+        id,
+        show_system_progress: request.show_system_progress,
+        callback_offset: 0,
     };
+    let mut attempt = 0;
+    loop {
+        match download_attempt(client, url, partial, request.max_bytes_per_sec, &mut progress).await {
+            Ok(()) => break,
+            Err(error) if attempt + 1 < MAX_ATTEMPTS && is_transient(&error) => {
+                let delay = backoff_delay(attempt);
+                tracing::warn!("download of {} failed, retrying in {:?}: {:?}", url, delay, error);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
 
-    app_handle.unlisten(listener_id);
-    if show_system_progress {
-        set_progress_bar(app_handle, None).log_error();
+    remove_if_exists(&validator_path(partial))?;
+    if let Some(ref expected) = request.expected {
+        emit_status(app_handle, id, DownloadStatus::Verifying, Some(url), None);
+        // Hashing gigabytes is blocking work; keep it off the async runtime.
+        let (partial_c, expected) = (partial.to_path_buf(), expected.clone());
+        let verified = tokio::task::spawn_blocking(move || model_manifest::verify(&partial_c, &expected)).await?;
+        if let Err(error) = verified {
+            if error.downcast_ref::<ChecksumMismatch>().is_some() {
+                model_manifest::quarantine(partial, &request.destination).log_error();
+            }
+            return Err(error.wrap_err(format!("Refusing to install {}", request.destination.display())));
+        }
     }
+    Ok(())
+}
 
-    if !matches!(&result, Ok(DownloadOutcome::Completed)) {
-        remove_if_exists(&partial).log_error();
+fn config_value(app_handle: &tauri::AppHandle, key: &str) -> Option<serde_json::Value> {
+    app_handle.store(STORE_FILENAME).ok()?.get(key)
+}
+
+/// The HTTP client every download uses, honouring the proxy from the config file. Without one,
+/// reqwest still picks up the system proxy variables.
+pub(crate) fn http_client(app_handle: &tauri::AppHandle) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().user_agent("vibe-app");
+    let proxy = config_value(app_handle, CONFIG_KEY_PROXY).and_then(|value| value.as_str().map(str::to_string));
+    if let Some(proxy) = proxy.filter(|proxy| !proxy.trim().is_empty()) {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.trim()).context(format!("Invalid proxy {}", proxy))?);
     }
+    Ok(builder.build()?)
+}
 
-    result
+/// The explicit limit wins; otherwise the one from the config file. Zero means unlimited.
+fn bandwidth_limit(app_handle: &tauri::AppHandle, max_bytes_per_sec: Option<u64>) -> Option<u64> {
+    max_bytes_per_sec
+        .or_else(|| config_value(app_handle, CONFIG_KEY_MAX_BYTES_PER_SECOND).and_then(|value| value.as_u64()))
+        .filter(|limit| *limit > 0)
 }
 
+/// `url` first, then the extra mirrors, without duplicates.
+fn mirror_list(url: String, mirrors: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut urls = vec![url];
+    for mirror in mirrors {
+        if !urls.contains(&mirror) {
+            urls.push(mirror);
+        }
+    }
+    urls
+}
+
+/// Downloads a model and publishes it only after it matches the manifest. `sha256` overrides the
+/// manifest for URLs it does not list; a URL it does list is verified even when `sha256` is omitted,
+/// and its other manifest URLs are used as mirrors.
 #[tauri::command]
-pub async fn download_model(app_handle: tauri::AppHandle, url: String, path: String) -> Result<DownloadModelResult> {
+#[allow(clippy::too_many_arguments)]
+pub async fn download_model(
+    app_handle: tauri::AppHandle,
+    manager: State<'_, DownloadManager>,
+    url: String,
+    path: String,
+    sha256: Option<String>,
+    id: Option<String>,
+    mirrors: Option<Vec<String>>,
+    max_bytes_per_sec: Option<u64>,
+) -> Result<DownloadModelResult, CommandError> {
     tracing::debug!("Download model invoked! with path {}", path);
 
-    match download_to_partial(&app_handle, &url, Path::new(&path), true).await? {
+    let manifest = model_manifest::load(&app_handle);
+    let entry = manifest.find_by_url(&url);
+    let mut expected = entry.map(ExpectedDigest::from);
+    if let Some(sha256) = sha256 {
+        expected.get_or_insert_with(ExpectedDigest::default).sha256 = Some(sha256.to_lowercase());
+    }
+    let manifest_mirrors = entry.map(|entry| entry.urls.clone()).unwrap_or_default();
+
+    let request = DownloadRequest {
+        urls: mirror_list(url, mirrors.unwrap_or_default().into_iter().chain(manifest_mirrors)),
+        destination: PathBuf::from(&path),
+        show_system_progress: true,
+        expected,
+        max_bytes_per_sec: bandwidth_limit(&app_handle, max_bytes_per_sec),
+    };
+    match manager.run(&app_handle, id, request).await? {
         DownloadOutcome::Completed => Ok(DownloadModelResult::Completed { path }),
         DownloadOutcome::Cancelled => Ok(DownloadModelResult::Cancelled),
     }
 }
 
 #[tauri::command]
-pub async fn download_file(app_handle: tauri::AppHandle, url: String, path: String) -> Result<()> {
+pub async fn download_file(
+    app_handle: tauri::AppHandle,
+    manager: State<'_, DownloadManager>,
+    url: String,
+    path: String,
+    id: Option<String>,
+    mirrors: Option<Vec<String>>,
+    max_bytes_per_sec: Option<u64>,
+) -> Result<(), CommandError> {
     tracing::debug!("Download file invoked! with path {}", path);
 
-    match download_to_partial(&app_handle, &url, Path::new(&path), false).await? {
+    let request = DownloadRequest {
+        urls: mirror_list(url, mirrors.unwrap_or_default()),
+        destination: PathBuf::from(path),
+        show_system_progress: false,
+        expected: None,
+        max_bytes_per_sec: bandwidth_limit(&app_handle, max_bytes_per_sec),
+    };
+    match manager.run(&app_handle, id, request).await? {
         DownloadOutcome::Completed => Ok(()),
-        DownloadOutcome::Cancelled => bail!("Download cancelled"),
+        DownloadOutcome::Cancelled => Err(eyre::eyre!("Download cancelled").into()),
     }
 }
 
+/// Cancel one download by the id it was started with. Returns false when it already finished.
+#[tauri::command]
+pub fn cancel_download(manager: State<'_, DownloadManager>, id: String) -> bool {
+    manager.cancel(&id)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{partial_path, publish_download, remove_if_exists};
-    use std::{fs, path::PathBuf, time::SystemTime};
+    use super::{
+        backoff_delay, mirror_list, parse_content_range, partial_path, publish_download, remove_if_exists, throttle_delay,
+        DownloadManager, PartialValidator,
+    };
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
 
     fn test_dir(name: &str) -> PathBuf {
         let unique = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos();
@@ -182,6 +723,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn content_range_gives_the_start_and_total() {
+        assert_eq!(parse_content_range("bytes 100-199/200"), Some((100, Some(200))));
+        assert_eq!(parse_content_range("bytes 0-0/*"), Some((0, None)));
+        assert_eq!(parse_content_range("bytes */200"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+
+    #[test]
+    fn if_range_never_uses_a_weak_etag() {
+        let validator = PartialValidator {
+            url: "https://example.com/model.bin".into(),
+            etag: Some("W/\"abc\"".into()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".into()),
+        };
+        assert_eq!(validator.if_range(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+
+        let strong = PartialValidator {
+            etag: Some("\"abc\"".into()),
+            ..validator
+        };
+        assert_eq!(strong.if_range(), Some("\"abc\""));
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        assert_eq!(backoff_delay(0).as_millis(), 1000);
+        assert_eq!(backoff_delay(2).as_millis(), 4000);
+        assert_eq!(backoff_delay(30).as_millis(), 30_000);
+    }
+
+    #[test]
+    fn throttle_waits_only_when_ahead_of_the_limit() {
+        assert_eq!(
+            throttle_delay(1000, 1000, Duration::from_millis(400)),
+            Some(Duration::from_millis(600))
+        );
+        assert_eq!(throttle_delay(1000, 1000, Duration::from_secs(2)), None);
+        assert_eq!(throttle_delay(1000, 0, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn mirrors_follow_the_requested_url_without_duplicates() {
+        let urls = mirror_list("a".into(), ["b".to_string(), "a".to_string(), "c".to_string()]);
+        assert_eq!(urls, ["a", "b", "c"]);
+    }
+
     #[test]
     fn publish_download_moves_a_complete_partial_file() {
         let dir = test_dir("publish");
@@ -215,4 +803,16 @@ mod tests {
         remove_if_exists(&destination).unwrap();
         fs::remove_dir_all(dir).unwrap();
     }
+
+    #[test]
+    fn only_one_download_holds_the_system_progress() {
+        let manager = DownloadManager::default();
+        assert!(manager.claim_system_progress("model"));
+        assert!(!manager.claim_system_progress("diarize"));
+
+        manager.release_system_progress("diarize");
+        assert!(!manager.claim_system_progress("vad"));
+        manager.release_system_progress("model");
+        assert!(manager.claim_system_progress("vad"));
+    }
 }
@@ -1,7 +1,12 @@
-use eyre::{ContextCompat, Result};
+use super::conversion::{ConversionKind, ConversionManager};
+use super::CommandError;
+use crate::clips::{ClipOptions, ClipRange};
+use crate::error::LogError;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::path::{Path, PathBuf};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 #[tauri::command]
 pub async fn glob_files(folder: String, patterns: Vec<String>, recursive: bool) -> Vec<String> {
@@ -130,6 +135,75 @@ pub fn get_ffmpeg_path() -> String {
         .unwrap_or_default()
 }
 
+/// Container, duration, audio and video streams, and embedded metadata of a media file.
+#[tauri::command]
+pub async fn probe_media(path: PathBuf) -> Result<crate::probe::MediaInfo> {
+    crate::probe::probe_in_background(path).await
+}
+
+/// Levels, clipping, silence, noise and how much of a media file sounds like speech, with warnings
+/// about what is likely to hurt its transcript. Decodes the first ten minutes at most.
+#[tauri::command]
+pub async fn analyze_audio(path: PathBuf) -> Result<crate::audio_quality::AudioQuality> {
+    tauri::async_runtime::spawn_blocking(move || crate::audio_quality::analyze(&path)).await?
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WaveformProgress<'a> {
+    path: &'a Path,
+    /// Percent of the file decoded.
+    progress: f64,
+}
+
+/// Min/max peaks of a media file at several zoom levels, decoded once and then cached. The first
+/// decode of a long file takes a while and reports `waveform_progress` events; read the peaks with
+/// `get_waveform_peaks`.
+#[tauri::command]
+pub async fn load_waveform(app_handle: AppHandle, path: PathBuf) -> Result<crate::waveform::WaveformInfo> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let duration = crate::probe::probe(&path).ok().and_then(|info| info.duration_seconds);
+        let on_progress = |seconds: f64| {
+            if let Some(duration) = duration.filter(|duration| *duration > 0.0) {
+                let payload = WaveformProgress {
+                    path: &path,
+                    progress: (seconds / duration * 100.0).clamp(0.0, 100.0).floor(),
+                };
+                app_handle.emit("waveform_progress", payload).log_error();
+            }
+        };
+        crate::waveform::load(&path, &on_progress)
+    })
+    .await?
+}
+
+/// Peaks `start..start + count` of one level of a waveform from `load_waveform`, as interleaved
+/// min and max values from -127 to 127. At most `waveform::MAX_CHUNK_PEAKS` per call.
+#[tauri::command]
+pub async fn get_waveform_peaks(path: PathBuf, level: usize, start: u32, count: u32) -> Result<Vec<i8>> {
+    tauri::async_runtime::spawn_blocking(move || crate::waveform::read_peaks(&path, level, start, count)).await?
+}
+
+/// Cut segments of `path` into audio clips in the folder `output`, or into one highlight reel at
+/// `output` (see `clips::ClipOptions`). Reports `conversion_progress` and stops on
+/// `cancel_conversion`. Returns the files written.
+#[tauri::command]
+pub async fn export_clips(
+    app_handle: AppHandle,
+    path: PathBuf,
+    segments: Vec<ClipRange>,
+    output: PathBuf,
+    options: ClipOptions,
+) -> std::result::Result<Vec<PathBuf>, CommandError> {
+    let conversion = ConversionManager::start(&app_handle, ConversionKind::Clips);
+    let written = tauri::async_runtime::spawn_blocking(move || {
+        crate::clips::export(&path, &segments, &output, &options, &conversion.handle)
+    })
+    .await
+    .map_err(|e| eyre!("{e:?}"))??;
+    Ok(written)
+}
+
 /// Media picker that accepts files *and* folders in one dialog.
 ///
 /// Only macOS' open panel can offer both at once (`NSOpenPanel` takes two independent flags); the
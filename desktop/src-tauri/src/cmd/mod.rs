@@ -2,9 +2,11 @@ use serde::Serialize;
 pub mod app;
 pub mod audio;
 pub mod config;
+pub mod conversion;
 pub mod download;
 pub mod files;
 pub mod handoff_cmd;
+pub mod models;
 pub mod permissions;
 pub mod sona_cmd;
 pub mod transcribe;
@@ -18,6 +20,9 @@ pub use transcribe::TranscribeOptions;
 pub struct CommandError {
     pub code: String,
     pub message: String,
+    /// Machine-readable fields for codes that carry more than a message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 impl std::fmt::Display for CommandError {
@@ -28,9 +33,28 @@ impl std::fmt::Display for CommandError {
 
 impl From<eyre::Error> for CommandError {
     fn from(err: eyre::Error) -> Self {
+        if let Some(insufficient) = crate::disk_space::find(&err) {
+            return CommandError {
+                code: "insufficient_space".to_string(),
+                message: insufficient.to_string(),
+                details: Some(serde_json::json!({
+                    "path": insufficient.path,
+                    "required": insufficient.required,
+                    "available": insufficient.available,
+                })),
+            };
+        }
+        if crate::ffmpeg::is_cancelled(&err) {
+            return CommandError {
+                code: "cancelled".to_string(),
+                message: err.to_string(),
+                details: None,
+            };
+        }
         CommandError {
             code: "internal_error".to_string(),
             message: err.to_string(),
+            details: None,
         }
     }
 }
@@ -0,0 +1,50 @@
+use crate::model_inventory::{self, InstalledModel};
+use crate::model_manifest::{self, ModelManifest};
+use eyre::Result;
+use std::path::PathBuf;
+
+/// The downloadable models. With `refresh`, the published manifest is fetched first; a network
+/// failure then falls back to the cached or bundled copy instead of failing the UI.
+#[tauri::command]
+pub async fn get_model_manifest(app_handle: tauri::AppHandle, refresh: Option<bool>) -> Result<ModelManifest> {
+    if refresh.unwrap_or(false) {
+        match model_manifest::refresh(&app_handle).await {
+            Ok(manifest) => return Ok(manifest),
+            Err(error) => tracing::warn!("could not refresh the model manifest: {:?}", error),
+        }
+    }
+    Ok(model_manifest::load(&app_handle))
+}
+
+/// Every model file in the models folder, with size, engine and when it was last loaded.
+#[tauri::command]
+pub async fn list_installed_models(app_handle: tauri::AppHandle) -> Result<Vec<InstalledModel>> {
+    let folder = super::app::get_models_folder(app_handle.clone())?;
+    model_inventory::scan(&app_handle, &folder)
+}
+
+/// Deletes the file and forgets it in `model.path` / `model.displayNames`.
+#[tauri::command]
+pub async fn delete_model(app_handle: tauri::AppHandle, path: PathBuf) -> Result<()> {
+    let folder = super::app::get_models_folder(app_handle.clone())?;
+    model_inventory::ensure_managed(&app_handle, &folder, &path)?;
+    model_inventory::delete(&app_handle, &path)
+}
+
+/// Returns the new path; config keys that pointed at the old one follow it.
+#[tauri::command]
+pub async fn rename_model(app_handle: tauri::AppHandle, path: PathBuf, new_name: String) -> Result<PathBuf> {
+    let folder = super::app::get_models_folder(app_handle.clone())?;
+    model_inventory::ensure_managed(&app_handle, &folder, &path)?;
+    model_inventory::rename(&app_handle, &path, &new_name)
+}
+
+/// Moves a model out of the models folder, e.g. to a larger drive, or back into it. Returns the new
+/// path; a moved model stays in `list_installed_models`.
+#[tauri::command]
+pub async fn move_model(app_handle: tauri::AppHandle, path: PathBuf, folder: PathBuf) -> Result<PathBuf> {
+    let models_folder = super::app::get_models_folder(app_handle.clone())?;
+    model_inventory::ensure_managed(&app_handle, &models_folder, &path)?;
+    // Copying gigabytes across volumes blocks; keep it off the async runtime.
+    tauri::async_runtime::spawn_blocking(move || model_inventory::move_to(&app_handle, &path, &folder, &models_folder)).await?
+}
@@ -1,6 +1,7 @@
+use crate::error::LogError;
 use crate::setup::SonaState;
 use eyre::{bail, Context, ContextCompat, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{Manager, State};
 use tokio::sync::Mutex;
 
@@ -66,7 +67,8 @@ pub fn resolve_ffmpeg_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
         return Some(sidecar_path);
     }
 
-    None
+    // No sidecar is bundled on Linux; use an installed ffmpeg when there is one.
+    crate::ffmpeg::find_ffmpeg_path()
 }
 
 #[tauri::command]
@@ -78,39 +80,61 @@ pub async fn load_model(
 ) -> Result<String> {
     let sona_state: State<'_, Mutex<SonaState>> = app_handle.state();
     let mut state_guard = sona_state.lock().await;
+    let gpu_fallback = load_model_into(
+        &app_handle,
+        &mut state_guard.process,
+        &model_path,
+        gpu_device,
+        unload_timeout_minutes,
+    )
+    .await?;
+    if gpu_fallback {
+        Ok("gpu_fallback".to_string())
+    } else {
+        Ok(model_path)
+    }
+}
 
-    let process_is_alive = state_guard.process.as_mut().is_some_and(crate::sona::SonaProcess::is_alive);
+/// Load `model_path` into the Sona process in `slot`, spawning or restarting it as needed.
+/// Returns true when the GPU failed and the model was loaded on the CPU instead.
+pub async fn load_model_into(
+    app_handle: &tauri::AppHandle,
+    slot: &mut Option<crate::sona::SonaProcess>,
+    model_path: &str,
+    gpu_device: Option<i32>,
+    unload_timeout_minutes: u32,
+) -> Result<bool> {
+    let process_is_alive = slot.as_mut().is_some_and(crate::sona::SonaProcess::is_alive);
     if !process_is_alive {
-        if state_guard.process.is_some() {
+        if slot.is_some() {
             tracing::warn!("cached sona process is no longer running; restarting it");
         }
-        state_guard.process = None;
+        *slot = None;
     }
 
-    if state_guard
-        .process
+    if slot
         .as_ref()
         .is_some_and(|process| process.unload_timeout_minutes() != unload_timeout_minutes)
     {
         tracing::debug!(unload_timeout_minutes, "restarting sona to apply unload timeout");
         // Dropping SonaProcess kills and waits for its child process via its Drop implementation.
-        state_guard.process = None;
+        *slot = None;
     }
 
     let spawn_sona = || -> Result<crate::sona::SonaProcess> {
-        let binary_path = resolve_sona_binary(&app_handle)?;
-        let ffmpeg_path = resolve_ffmpeg_path(&app_handle);
+        let binary_path = resolve_sona_binary(app_handle)?;
+        let ffmpeg_path = resolve_ffmpeg_path(app_handle);
         crate::sona::SonaProcess::spawn(&binary_path, ffmpeg_path.as_deref(), unload_timeout_minutes)
     };
 
     // Spawn sona if not running
-    if state_guard.process.is_none() {
+    if slot.is_none() {
         match spawn_sona() {
-            Ok(process) => state_guard.process = Some(process),
+            Ok(process) => *slot = Some(process),
             Err(e) => {
                 let error_msg = format!("{:#}", e);
                 crate::analytics::track_event_handle_with_props(
-                    &app_handle,
+                    app_handle,
                     crate::analytics::events::SONA_SPAWN_FAILED,
                     Some(serde_json::json!({"error_message": error_msg})),
                 );
@@ -121,8 +145,8 @@ pub async fn load_model(
 
     // Load model via HTTP
     let load_result = {
-        let sona = state_guard.process.as_mut().unwrap();
-        sona.load_model(&model_path, gpu_device, false).await
+        let sona = slot.as_mut().unwrap();
+        sona.load_model(model_path, gpu_device, false).await
     };
 
     let gpu_fallback = match load_result {
@@ -131,22 +155,17 @@ pub async fn load_model(
             tracing::warn!("model load failed with GPU enabled, falling back to CPU: {:#}", e);
 
             // Kill existing process and respawn, then reload with no_gpu
-            if let Some(mut old) = state_guard.process.take() {
+            if let Some(mut old) = slot.take() {
                 old.kill();
             }
             let process = spawn_sona().context("failed to respawn sona")?;
-            state_guard.process = Some(process);
-
-            let sona = state_guard.process.as_mut().unwrap();
-            sona.load_model(&model_path, gpu_device, true).await?;
+            let sona = slot.insert(process);
+            sona.load_model(model_path, gpu_device, true).await?;
             true
         }
     };
-    if gpu_fallback {
-        Ok("gpu_fallback".to_string())
-    } else {
-        Ok(model_path)
-    }
+    crate::model_inventory::record_used(app_handle, Path::new(model_path)).log_error();
+    Ok(gpu_fallback)
 }
 
 #[tauri::command]
@@ -165,7 +184,9 @@ pub async fn get_model_metadata(app_handle: tauri::AppHandle, model_path: String
         let ffmpeg_path = resolve_ffmpeg_path(&app_handle);
         state.process = Some(crate::sona::SonaProcess::spawn(&binary_path, ffmpeg_path.as_deref(), 5)?);
     }
-    state.process.as_ref().unwrap().model_metadata(&model_path).await
+    let metadata = state.process.as_ref().unwrap().model_metadata(&model_path).await?;
+    crate::model_inventory::record_metadata(&app_handle, Path::new(&model_path), &metadata).log_error();
+    Ok(metadata)
 }
 
 #[tauri::command]
@@ -1,4 +1,5 @@
 use crate::error::LogError;
+use crate::preprocess::FfmpegOptions;
 use crate::setup::SonaState;
 use crate::sona::SonaEvent;
 use crate::transcript::{Segment, Transcript};
@@ -13,23 +14,11 @@ use std::sync::{
 use tauri::{Emitter, Listener, State};
 use tokio::sync::Mutex;
 
-use super::{ui::set_progress_bar, CommandError};
-
-#[allow(dead_code)]
-#[derive(Deserialize, Serialize, Clone)]
-pub struct FfmpegOptions {
-    pub normalize_loudness: bool,
-    pub custom_command: Option<String>,
-}
-
-impl Default for FfmpegOptions {
-    fn default() -> Self {
-        Self {
-            normalize_loudness: true,
-            custom_command: None,
-        }
-    }
-}
+use super::{
+    audio::{RecordedTrack, TrackSource},
+    ui::set_progress_bar,
+    CommandError,
+};
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct TranscribeOptions {
@@ -51,6 +40,25 @@ pub struct TranscribeOptions {
     pub vad_model: Option<String>,
 }
 
+/// Check a custom filter chain for the FFmpeg settings before it is saved. Only its form is checked
+/// when there is no ffmpeg.
+#[tauri::command]
+pub async fn validate_audio_filter(filter: String) -> Result<(), CommandError> {
+    let invalid = |e: eyre::Report| CommandError {
+        code: "invalid_request".to_string(),
+        message: e.to_string(),
+        details: None,
+    };
+    let filter = crate::preprocess::validate_custom_filter(&filter).map_err(invalid)?;
+    if crate::ffmpeg::find_ffmpeg_path().is_none() {
+        return Ok(());
+    }
+    tauri::async_runtime::spawn_blocking(move || crate::ffmpeg::check_filter(&filter))
+        .await
+        .map_err(|e| CommandError::from(eyre::eyre!("{e:?}")))?
+        .map_err(invalid)
+}
+
 #[tauri::command]
 pub async fn transcribe(
     app_handle: tauri::AppHandle,
@@ -63,20 +71,55 @@ pub async fn transcribe(
         return Err(CommandError {
             code: "invalid_request".to_string(),
             message: format!("Audio file not found: {}", options.path),
+            details: None,
         });
     }
     if !audio_path.is_file() {
         return Err(CommandError {
             code: "invalid_request".to_string(),
             message: format!("Path is not a file: {}", options.path),
+            details: None,
         });
     }
 
+    match crate::probe::probe_in_background(audio_path.clone()).await {
+        Ok(info) => {
+            if let Some(reason) = info.unusable_reason() {
+                return Err(CommandError {
+                    code: "invalid_audio".to_string(),
+                    message: format!("{}: {}", reason, options.path),
+                    details: None,
+                });
+            }
+        }
+        // Sona may still read what could not be probed, and explains it if not.
+        Err(error) => tracing::warn!("could not probe {}: {:?}", options.path, error),
+    }
+
+    let filter_chain = FfmpegOptions::from_config(&app_handle).filter_chain();
+    let preprocessed = match filter_chain {
+        Some(filter_chain) => crate::preprocess::preprocess_for_sona(&app_handle, &audio_path, filter_chain).await?,
+        None => None,
+    };
+    // Sona reads other formats through ffmpeg; without it, hand over a WAV decoded here.
+    let decoded = match preprocessed {
+        Some(preprocessed) => Some(preprocessed),
+        None => crate::decode::decode_for_sona(&app_handle, &audio_path).await?,
+    };
+    let options = match &decoded {
+        Some(decoded) => TranscribeOptions {
+            path: decoded.path().to_string_lossy().to_string(),
+            ..options
+        },
+        None => options,
+    };
+
     let (client, base_url) = {
         let state = sona_state.lock().await;
         let process = state.process.as_ref().ok_or_else(|| CommandError {
             code: "no_model".to_string(),
             message: "Please load model first".to_string(),
+            details: None,
         })?;
         (process.client(), process.base_url())
     }; // lock released here, before any I/O
@@ -99,6 +142,7 @@ pub async fn transcribe(
                 CommandError {
                     code: api_err.code.clone(),
                     message: api_err.message.clone(),
+                    details: None,
                 }
             } else {
                 CommandError::from(e)
@@ -146,6 +190,7 @@ pub async fn transcribe(
                     return Err(CommandError {
                         code: code.unwrap_or_else(|| "internal_error".to_string()),
                         message,
+                        details: None,
                     });
                 }
             },
@@ -163,6 +208,7 @@ pub async fn transcribe(
         return Err(CommandError {
             code: "internal_error".to_string(),
             message: "Sona transcription stream ended before completion".to_string(),
+            details: None,
         });
     }
 
@@ -174,3 +220,90 @@ pub async fn transcribe(
 
     Ok(transcript)
 }
+
+/// Transcribe the tracks of a recording made with `separate_tracks` one by one, and merge them
+/// into one transcript where the microphone is the local speaker and the system audio the
+/// remote participants (see `TrackSource::speaker`).
+#[tauri::command]
+pub async fn transcribe_tracks(
+    app_handle: tauri::AppHandle,
+    options: TranscribeOptions,
+    tracks: Vec<RecordedTrack>,
+    sona_state: State<'_, Mutex<SonaState>>,
+) -> Result<Transcript, CommandError> {
+    let aborted = Arc::new(AtomicBool::new(false));
+    let aborted_c = aborted.clone();
+    let listener = app_handle.listen("abort_transcribe", move |_| aborted_c.store(true, Ordering::Relaxed));
+
+    let mut transcripts = Vec::new();
+    for track in tracks {
+        if aborted.load(Ordering::Relaxed) {
+            break;
+        }
+        let options = TranscribeOptions {
+            path: track.path,
+            ..options.clone()
+        };
+        match transcribe(app_handle.clone(), options, sona_state.clone()).await {
+            Ok(transcript) => transcripts.push((track.source, transcript)),
+            Err(error) => {
+                app_handle.unlisten(listener);
+                return Err(error);
+            }
+        }
+    }
+    app_handle.unlisten(listener);
+    Ok(merge_tracks(transcripts))
+}
+
+fn merge_tracks(transcripts: Vec<(TrackSource, Transcript)>) -> Transcript {
+    let processing_time_sec = transcripts.iter().map(|(_, transcript)| transcript.processing_time_sec).sum();
+    let mut segments: Vec<Segment> = transcripts
+        .into_iter()
+        .flat_map(|(source, transcript)| {
+            transcript.segments.into_iter().map(move |segment| Segment {
+                speaker: Some(source.speaker(segment.speaker)),
+                ..segment
+            })
+        })
+        .collect();
+    segments.sort_by_key(|segment| segment.start);
+    Transcript {
+        processing_time_sec,
+        segments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: i64, speaker: Option<i32>) -> Segment {
+        Segment {
+            start,
+            stop: start + 100,
+            text: String::new(),
+            speaker,
+        }
+    }
+
+    #[test]
+    fn merged_tracks_keep_local_and_remote_speakers_apart() {
+        let microphone = Transcript {
+            processing_time_sec: 1,
+            segments: vec![segment(0, None), segment(300, None)],
+        };
+        let system = Transcript {
+            processing_time_sec: 2,
+            segments: vec![segment(100, Some(0)), segment(200, Some(1))],
+        };
+        let merged = merge_tracks(vec![(TrackSource::Microphone, microphone), (TrackSource::System, system)]);
+        assert_eq!(merged.processing_time_sec, 3);
+        let speakers: Vec<_> = merged
+            .segments
+            .iter()
+            .map(|segment| (segment.start, segment.speaker))
+            .collect();
+        assert_eq!(speakers, [(0, Some(0)), (100, Some(1)), (200, Some(2)), (300, Some(0))]);
+    }
+}
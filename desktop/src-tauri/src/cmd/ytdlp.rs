@@ -1,14 +1,16 @@
 use crate::ffmpeg::get_vibe_temp_folder;
 use eyre::{bail, Context, ContextCompat, Result};
+use serde::Serialize;
 use serde_json::Value;
 use std::{
     io::{BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
 };
 use tauri::{AppHandle, Emitter, Listener, Manager};
 
 use crate::error::LogError;
+use crate::transcript::Segment;
 
 use super::files::get_ffmpeg_path;
 
@@ -19,6 +21,13 @@ use std::process::Stdio;
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// Entries listed from one playlist or channel; a channel's whole history is rarely what anyone
+/// wants to transcribe.
+const MAX_PLAYLIST_ENTRIES: usize = 1000;
+
+/// Fields of yt-dlp's info JSON printed once the audio is saved.
+const DETAILS_TEMPLATE: &str = "after_move:%(.{title,uploader,channel,upload_date,description,duration,webpage_url,chapters})j";
+
 fn get_binary_name() -> &'static str {
     if cfg!(windows) {
         if cfg!(target_arch = "aarch64") {
@@ -38,8 +47,8 @@ fn get_binary_name() -> &'static str {
 }
 
 #[tauri::command]
-pub async fn get_latest_ytdlp_version() -> Result<String> {
-    let client = reqwest::Client::builder().user_agent("vibe-app").build()?;
+pub async fn get_latest_ytdlp_version(app_handle: AppHandle) -> Result<String> {
+    let client = super::download::http_client(&app_handle)?;
     let resp = client
         .get("https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest")
         .send()
@@ -75,14 +84,128 @@ pub fn get_temp_path(app_handle: AppHandle, ext: String, in_documents: Option<bo
     base_path.to_string_lossy().to_string()
 }
 
-#[tauri::command]
-pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String) -> Result<()> {
-    tracing::debug!("download audio {}", url);
+/// What the platform says about a downloaded video.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaDetails {
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    /// `YYYY-MM-DD`
+    pub upload_date: Option<String>,
+    pub description: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub url: Option<String>,
+    pub chapters: Vec<Chapter>,
+    /// The platform's own captions, when they were asked for and it has some.
+    pub captions: Vec<Captions>,
+}
+
+/// A chapter of a video, in centiseconds like transcript segments.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    pub start: i64,
+    pub stop: i64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Captions {
+    pub language: String,
+    pub segments: Vec<Segment>,
+}
+
+fn parse_details(value: &Value) -> MediaDetails {
+    let text = |key: &str| value[key].as_str().map(str::to_string).filter(|text| !text.trim().is_empty());
+    let centiseconds = |value: &Value| value.as_f64().map(|seconds| (seconds * 100.0).round() as i64);
+    let chapters = value["chapters"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|chapter| {
+            Some(Chapter {
+                start: centiseconds(&chapter["start_time"])?,
+                stop: centiseconds(&chapter["end_time"])?,
+                title: chapter["title"].as_str().unwrap_or_default().to_string(),
+            })
+        })
+        .collect();
+    MediaDetails {
+        title: text("title"),
+        uploader: text("uploader").or_else(|| text("channel")),
+        upload_date: text("upload_date")
+            .filter(|date| date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()))
+            .map(|date| format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])),
+        description: text("description"),
+        duration_seconds: value["duration"].as_f64(),
+        url: text("webpage_url"),
+        chapters,
+        captions: Vec::new(),
+    }
+}
+
+/// Download the platform's captions of `url` next to `out_path` as WebVTT, read them and remove
+/// the files. Automatic captions come in every language YouTube can translate to, so they are only
+/// fetched for a known `language`; without one only captions uploaded by the author are.
+fn fetch_captions(app_handle: &AppHandle, url: &str, out_path: &Path, language: Option<&str>) -> Result<Vec<Captions>> {
+    let folder = out_path.parent().context("The download has no folder")?;
+    let stem = out_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .context("The download has no name")?;
+    let mut cmd = ytdlp_command(app_handle)?;
+    cmd.args([
+        "--skip-download",
+        "--no-playlist",
+        "--write-subs",
+        "--sub-format",
+        "vtt/best",
+        "--convert-subs",
+        "vtt",
+    ])
+    .args(["--ffmpeg-location", &get_ffmpeg_path()]);
+    match language {
+        Some(language) => cmd.args(["--write-auto-subs", "--sub-langs", &format!("{language}.*")]),
+        None => cmd.args(["--sub-langs", "all,-live_chat"]),
+    };
+    let template = format!("subtitle:{}", folder.join(format!("{stem}.%(ext)s")).display());
+    let output = cmd.args(["-o", &template, url]).stdin(Stdio::null()).output()?;
+    if !output.status.success() {
+        bail!(
+            "Failed to download captions: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut captions = Vec::new();
+    for entry in std::fs::read_dir(folder)?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        // `<stem>.<language>.vtt`
+        let Some(language) = name
+            .strip_prefix(&format!("{stem}."))
+            .and_then(|rest| rest.strip_suffix(".vtt"))
+        else {
+            continue;
+        };
+        let segments = crate::captions::parse_vtt(&std::fs::read_to_string(&path)?);
+        std::fs::remove_file(&path).log_error();
+        if !segments.is_empty() {
+            captions.push(Captions {
+                language: language.to_string(),
+                segments,
+            });
+        }
+    }
+    captions.sort_by(|a, b| a.language.cmp(&b.language));
+    Ok(captions)
+}
+
+/// The downloaded yt-dlp binary, made executable.
+fn ytdlp_command(app_handle: &AppHandle) -> Result<std::process::Command> {
     let name = get_binary_name();
     let path = app_handle.path().app_local_data_dir().context("Can't get data directory")?;
     let path = path.join(name);
     tracing::debug!("path is {}", path.display());
-    let ffmpeg_path = get_ffmpeg_path();
 
     // Set permission
     #[cfg(unix)]
@@ -95,11 +218,35 @@ pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String
         std::fs::set_permissions(path.clone(), perm)?;
     }
 
+    #[allow(unused_mut)]
     let mut cmd = std::process::Command::new(path);
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    Ok(cmd)
+}
+
+/// Download the audio of `url` to `out_path` as m4a, and return what the platform says about it.
+/// With `captions`, the platform's captions in `language` are fetched too; failing to get them
+/// doesn't fail the download.
+#[tauri::command]
+pub async fn download_audio(
+    app_handle: AppHandle,
+    url: String,
+    out_path: String,
+    captions: Option<bool>,
+    language: Option<String>,
+) -> Result<MediaDetails> {
+    tracing::debug!("download audio {}", url);
+    let ffmpeg_path = get_ffmpeg_path();
+
+    let mut cmd = ytdlp_command(&app_handle)?;
     let cmd = cmd
         .args([
             "--progress-template",
             "{\"progress\": \"%(progress.percent)s\", \"total_bytes\": \"%(progress.total_bytes)s\", \"progress_str\": \"%(progress._percent_str)s\"}\n",
+            "--progress",
+            "--print",
+            DETAILS_TEMPLATE,
             "--no-playlist",
             "-x",
             "--audio-format",
@@ -113,9 +260,6 @@ pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
-    #[cfg(windows)]
-    let cmd = cmd.creation_flags(CREATE_NO_WINDOW);
-
     let cancel_flag = std::sync::Arc::new(AtomicBool::new(false));
     let cancel_flag_c = cancel_flag.clone();
     app_handle.once("ytdlp-cancel", move |_| {
@@ -123,6 +267,7 @@ pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String
     });
 
     let mut child = cmd.spawn()?;
+    let mut details = MediaDetails::default();
 
     if let Some(stdout) = child.stdout.take() {
         let reader = BufReader::new(stdout);
@@ -149,6 +294,11 @@ pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String
                             .log_error();
                     }
                 }
+            } else if line.starts_with('{') {
+                match serde_json::from_str(&line) {
+                    Ok(value) => details = parse_details(&value),
+                    Err(error) => tracing::warn!("failed to read the details of {}: {:?}", url, error),
+                }
             }
         }
     }
@@ -166,5 +316,238 @@ pub async fn download_audio(app_handle: AppHandle, url: String, out_path: String
         }
         bail!("Failed to download audio: {}", stderr_output);
     }
-    Ok(())
+    if captions.unwrap_or_default() && !cancel_flag.load(Ordering::Relaxed) {
+        let language = language.as_deref().filter(|language| *language != "auto");
+        match fetch_captions(&app_handle, &url, Path::new(&out_path), language) {
+            Ok(captions) => details.captions = captions,
+            Err(error) => tracing::warn!("no captions for {}: {:?}", url, error),
+        }
+    }
+    Ok(details)
+}
+
+/// One video of a playlist or channel, as listed by `list_playlist`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntry {
+    pub url: String,
+    pub title: Option<String>,
+    pub duration_seconds: Option<f64>,
+    /// A playlist of its own, such as a channel's Videos or Shorts tab.
+    #[serde(skip)]
+    nested: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+fn entry_from(value: &Value) -> Option<PlaylistEntry> {
+    let url = value["webpage_url"].as_str().or(value["url"].as_str())?;
+    Some(PlaylistEntry {
+        url: url.to_string(),
+        title: value["title"].as_str().map(str::to_string),
+        duration_seconds: value["duration"].as_f64(),
+        nested: value["ie_key"].as_str().is_some_and(|key| key.ends_with("Tab")),
+    })
+}
+
+fn push_entries(entries: &Value, playlist: &mut Vec<PlaylistEntry>) {
+    for entry in entries.as_array().into_iter().flatten() {
+        if entry["entries"].is_array() {
+            push_entries(&entry["entries"], playlist);
+        } else if let Some(entry) = entry_from(entry) {
+            playlist.push(entry);
+        }
+    }
+}
+
+/// Parse the `--flat-playlist --dump-single-json` output of yt-dlp. A single video becomes a
+/// playlist of one.
+fn parse_playlist(value: &Value) -> Result<Playlist> {
+    let title = value["title"].as_str().map(str::to_string);
+    if value["_type"].as_str() != Some("playlist") {
+        let entry = entry_from(value).context("yt-dlp listed neither a playlist nor a video")?;
+        return Ok(Playlist {
+            title,
+            entries: vec![entry],
+        });
+    }
+    let mut entries = Vec::new();
+    push_entries(&value["entries"], &mut entries);
+    Ok(Playlist { title, entries })
+}
+
+fn dump_playlist(app_handle: &AppHandle, url: &str) -> Result<Playlist> {
+    let output = ytdlp_command(app_handle)?
+        .args([
+            "--flat-playlist",
+            "--dump-single-json",
+            "--playlist-end",
+            &MAX_PLAYLIST_ENTRIES.to_string(),
+            url,
+        ])
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        bail!("Failed to list {}: {}", url, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    parse_playlist(&serde_json::from_slice(&output.stdout)?)
+}
+
+/// Replace the tabs among `entries` by the videos `dump` lists in them. A tab that cannot be listed,
+/// such as a channel's empty Shorts tab, is skipped; only when no tab lists and nothing else is
+/// left is its error returned.
+fn expand_tabs(entries: Vec<PlaylistEntry>, mut dump: impl FnMut(&str) -> Result<Playlist>) -> Result<Vec<PlaylistEntry>> {
+    let mut expanded = Vec::new();
+    let mut first_error = None;
+    for entry in entries {
+        if !entry.nested {
+            expanded.push(entry);
+            continue;
+        }
+        match dump(&entry.url) {
+            Ok(tab) => expanded.extend(tab.entries),
+            Err(error) => {
+                tracing::warn!("skipping {}: {:?}", entry.url, error);
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    if let (true, Some(error)) = (expanded.is_empty(), first_error) {
+        return Err(error);
+    }
+    expanded.truncate(MAX_PLAYLIST_ENTRIES);
+    Ok(expanded)
+}
+
+/// The videos of a playlist or channel link, with titles and durations, without downloading them.
+/// A link to one video lists just that video.
+#[tauri::command]
+pub async fn list_playlist(app_handle: AppHandle, url: String) -> Result<Playlist> {
+    tracing::debug!("list playlist {}", url);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut playlist = dump_playlist(&app_handle, &url)?;
+        // A channel link lists its tabs; list the videos in them instead.
+        if playlist.entries.iter().any(|entry| entry.nested) {
+            playlist.entries = expand_tabs(playlist.entries, |url| dump_playlist(&app_handle, url))?;
+        }
+        Ok(playlist)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_media_details() {
+        let value = json!({
+            "title": "Lecture 1",
+            "uploader": null,
+            "channel": "Uni",
+            "upload_date": "20240131",
+            "description": "",
+            "duration": 3600.5,
+            "webpage_url": "https://www.youtube.com/watch?v=a",
+            "chapters": [
+                {"start_time": 0.0, "end_time": 90.25, "title": "Intro"},
+                {"start_time": 90.25, "end_time": 3600.5, "title": "Limits"},
+            ]
+        });
+        let details = parse_details(&value);
+        assert_eq!(details.title.as_deref(), Some("Lecture 1"));
+        assert_eq!(details.uploader.as_deref(), Some("Uni"));
+        assert_eq!(details.upload_date.as_deref(), Some("2024-01-31"));
+        assert_eq!(details.description, None);
+        assert_eq!(
+            details.chapters,
+            [
+                Chapter {
+                    start: 0,
+                    stop: 9025,
+                    title: "Intro".to_string()
+                },
+                Chapter {
+                    start: 9025,
+                    stop: 360050,
+                    title: "Limits".to_string()
+                },
+            ]
+        );
+        assert_eq!(parse_details(&json!({"upload_date": "NA"})), MediaDetails::default());
+    }
+
+    #[test]
+    fn lists_playlist_entries() {
+        let value = json!({
+            "_type": "playlist",
+            "title": "Course",
+            "entries": [
+                {"_type": "url", "ie_key": "Youtube", "url": "https://www.youtube.com/watch?v=a", "title": "Intro", "duration": 61.0},
+                {"_type": "url", "ie_key": "Youtube", "url": "https://www.youtube.com/watch?v=b", "title": null, "duration": null},
+                {"_type": "url", "title": "no link"},
+            ]
+        });
+        let playlist = parse_playlist(&value).unwrap();
+        assert_eq!(playlist.title.as_deref(), Some("Course"));
+        let entries: Vec<_> = playlist
+            .entries
+            .iter()
+            .map(|entry| (entry.url.as_str(), entry.title.as_deref(), entry.duration_seconds))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("https://www.youtube.com/watch?v=a", Some("Intro"), Some(61.0)),
+                ("https://www.youtube.com/watch?v=b", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattens_channel_tabs_and_single_videos() {
+        let channel = json!({
+            "_type": "playlist",
+            "entries": [
+                {"_type": "url", "ie_key": "YoutubeTab", "url": "https://www.youtube.com/@vibe/videos", "title": "Videos"},
+                {"_type": "playlist", "entries": [{"url": "https://www.youtube.com/watch?v=c"}]},
+            ]
+        });
+        let entries = parse_playlist(&channel).unwrap().entries;
+        assert!(entries[0].nested);
+        assert_eq!(entries[1].url, "https://www.youtube.com/watch?v=c");
+        assert!(!entries[1].nested);
+
+        let video = json!({"id": "a", "title": "Talk", "duration": 30, "webpage_url": "https://vimeo.com/1"});
+        let playlist = parse_playlist(&video).unwrap();
+        assert_eq!(playlist.entries.len(), 1);
+        assert_eq!(playlist.entries[0].duration_seconds, Some(30.0));
+        assert!(parse_playlist(&json!({"title": "nothing"})).is_err());
+    }
+
+    #[test]
+    fn skips_channel_tabs_that_fail_to_list() {
+        let tab = |url: &str| PlaylistEntry {
+            url: url.to_string(),
+            title: None,
+            duration_seconds: None,
+            nested: true,
+        };
+        let dump = |url: &str| match url {
+            "videos" => parse_playlist(&json!({"_type": "playlist", "entries": [{"url": "https://www.youtube.com/watch?v=a"}]})),
+            _ => Err(eyre::eyre!("This channel does not have a shorts tab")),
+        };
+        let entries = expand_tabs(vec![tab("shorts"), tab("videos")], dump).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, "https://www.youtube.com/watch?v=a");
+
+        let error = expand_tabs(vec![tab("shorts")], dump).unwrap_err();
+        assert!(error.to_string().contains("shorts tab"));
+    }
 }
@@ -46,6 +46,8 @@ fn reload_if_externally_changed(app: &AppHandle, path: &Path) -> Result<()> {
     tracing::info!("reloaded {} after an external edit", STORE_FILENAME);
 
     app.emit(CONFIG_CHANGED_EVENT, Value::Object(on_disk))?;
+    // The dictation shortcut is registered from the backend, which has no other way to see the edit.
+    crate::dictation::sync_shortcut(app);
     Ok(())
 }
 
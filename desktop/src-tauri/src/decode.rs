@@ -0,0 +1,222 @@
+//! Decoding media into the 16 kHz mono WAV Sona reads, for when there is no ffmpeg.
+//!
+//! ffmpeg is a sidecar on Windows and macOS but is not bundled on Linux, and without it Sona can
+//! only read WAV. Symphonia covers the common audio formats (WAV, FLAC, MP3, AAC/M4A, ALAC and
+//! Ogg Vorbis); it has no Opus decoder, so Opus, video containers and anything rarer still need
+//! ffmpeg.
+
+use crate::error::LogError;
+use crate::ffmpeg::{get_vibe_temp_folder, random_string};
+use crate::recording::chunker::SAMPLE_RATE;
+use crate::recording::normalize::{create_wav, is_sona_wav};
+use crate::recording::resample::{downmix, LinearResampler, LowPass};
+use cpal::Sample;
+use eyre::{bail, eyre, Context, ContextCompat, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tauri::AppHandle;
+
+/// Whether media has to be decoded here, because neither the sidecar nor an installed ffmpeg exists.
+pub fn needs_native_decoding(app_handle: &AppHandle) -> bool {
+    crate::cmd::sona_cmd::resolve_ffmpeg_path(app_handle).is_none()
+}
+
+/// A WAV decoded for Sona, deleted when dropped.
+pub struct DecodedAudio {
+    path: PathBuf,
+}
+
+impl DecodedAudio {
+    /// A new path in Vibe's temp folder, for a WAV still to be written.
+    pub fn in_temp_folder() -> Self {
+        Self {
+            path: get_vibe_temp_folder().join(format!("{}.wav", random_string(10))),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for DecodedAudio {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path).map_err(|e| eyre!("{e:?}")).log_error();
+        }
+    }
+}
+
+/// Decode `input` into a temporary WAV when Sona has no ffmpeg to read it with.
+pub async fn decode_for_sona(app_handle: &AppHandle, input: &Path) -> Result<Option<DecodedAudio>> {
+    if !needs_native_decoding(app_handle) || is_sona_wav(input) {
+        return Ok(None);
+    }
+    tracing::debug!("ffmpeg not found, decoding {} natively", input.display());
+    let decoded = DecodedAudio::in_temp_folder();
+    let (input, output) = (input.to_path_buf(), decoded.path.clone());
+    tauri::async_runtime::spawn_blocking(move || decode_to_wav(&input, &output)).await??;
+    Ok(Some(decoded))
+}
+
+/// Converts decoded blocks of one stream into 16 kHz mono.
+struct Conversion {
+    channels: usize,
+    low_pass: Option<LowPass>,
+    resampler: LinearResampler,
+}
+
+impl Conversion {
+    fn new(spec: SignalSpec) -> Self {
+        Self {
+            channels: spec.channels.count(),
+            low_pass: LowPass::for_resampling(spec.rate, SAMPLE_RATE),
+            resampler: LinearResampler::new(spec.rate, SAMPLE_RATE),
+        }
+    }
+
+    fn process(&mut self, interleaved: &[f32]) -> Vec<f32> {
+        let mut mono = downmix(interleaved.iter().copied(), self.channels);
+        if let Some(low_pass) = self.low_pass.as_mut() {
+            mono = low_pass.process(&mono);
+        }
+        self.resampler.process(&mono)
+    }
+}
+
+/// Decode the first audio track of `input` into a 16 kHz mono 16-bit WAV at `output`.
+pub fn decode_to_wav(input: &Path, output: &Path) -> Result<()> {
+    let mut writer = create_wav(output)?;
+    decode_samples(input, |samples| {
+        for sample in samples {
+            writer.write_sample(i16::from_sample(sample.clamp(-1.0, 1.0)))?;
+        }
+        Ok(())
+    })?;
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Decode the first audio track of `input`, handing its samples as 16 kHz mono to `on_samples`
+/// block by block.
+pub fn decode_samples(input: &Path, mut on_samples: impl FnMut(&[f32]) -> Result<()>) -> Result<()> {
+    let file = File::open(input).context(format!("Failed to open {}", input.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = input.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| eyre!("Unsupported media format without ffmpeg: {}", e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .context("No audio track found")?;
+    let track_id = track.id;
+    if track.codec_params.codec == CODEC_TYPE_OPUS {
+        bail!(
+            "{} is Opus audio, which needs ffmpeg; install ffmpeg to transcribe it",
+            input.display()
+        );
+    }
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| eyre!("Unsupported audio codec without ffmpeg: {}", e))?;
+
+    // The stream's layout is only certain once a packet is decoded.
+    let mut conversion: Option<Conversion> = None;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(error) => bail!("Failed to read {}: {}", input.display(), error),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(error)) => {
+                tracing::debug!("skipping a corrupt packet: {}", error);
+                continue;
+            }
+            Err(error) => bail!("Failed to decode {}: {}", input.display(), error),
+        };
+        let spec = *decoded.spec();
+        let frames = decoded.capacity() as u64;
+        let samples = match buffer.as_mut() {
+            Some(samples) if samples.capacity() as u64 >= frames * spec.channels.count() as u64 => samples,
+            _ => buffer.insert(SampleBuffer::new(frames, spec)),
+        };
+        samples.copy_interleaved_ref(decoded);
+        let conversion = conversion.get_or_insert_with(|| Conversion::new(spec));
+        on_samples(&conversion.process(samples.samples()))?;
+    }
+    if conversion.is_none() {
+        bail!("No audio could be decoded from {}", input.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_wav_into_16khz_mono() {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let (input, output) = (
+            dir.join(format!("vibe-decode-in-{id}.wav")),
+            dir.join(format!("vibe-decode-out-{id}.wav")),
+        );
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&input, spec).unwrap();
+        for frame in 0..44_100 {
+            let value = (frame as f32 * 2.0 * std::f32::consts::PI * 440.0 / 44_100.0).sin() * 0.5;
+            for _ in 0..2 {
+                writer.write_sample((value * 8_388_607.0) as i32).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+
+        decode_to_wav(&input, &output).unwrap();
+        assert!(is_sona_wav(&output));
+        let mut reader = hound::WavReader::open(&output).unwrap();
+        assert!((15_900..=16_000).contains(&reader.len()), "{}", reader.len());
+        let peak = reader
+            .samples::<i16>()
+            .map(|sample| sample.unwrap().unsigned_abs())
+            .max()
+            .unwrap();
+        assert!((14_000..=17_000).contains(&peak), "{peak}");
+
+        for path in [input, output] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_decode() {
+        let path = std::env::temp_dir().join(format!("vibe-decode-garbage-{}.mp4", std::process::id()));
+        std::fs::write(&path, b"not media at all").unwrap();
+        assert!(decode_to_wav(&path, &path.with_extension("wav")).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -9,6 +9,10 @@ pub fn get_app_info() -> String {
     let os_ver = version();
     let os_type = type_();
     let models = "List of models"; // Replace with actual models fetching logic
+                                   // Without ffmpeg, media is decoded natively and only common audio formats open.
+    let ffmpeg = crate::ffmpeg::find_ffmpeg_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "not found (native decoding)".to_string());
 
     let info = format!(
         "Commit Hash: {}\n\
@@ -17,6 +21,7 @@ pub fn get_app_info() -> String {
          OS: {}\n\
          OS Version: {}\n\
          Models: {}\n\
+         FFmpeg: {}\n\
          AVX2: {}",
         commit,
         arch,
@@ -24,6 +29,7 @@ pub fn get_app_info() -> String {
         os_type,
         os_ver,
         models,
+        ffmpeg,
         is_avx2_enabled()
     );
 
@@ -0,0 +1,153 @@
+//! Microphone capture for dictation, kept in memory.
+//!
+//! A dictated phrase is seconds long, so unlike `start_record` nothing is written while it is
+//! spoken: the callback downmixes into a buffer, and the buffer is resampled to 16 kHz once the
+//! shortcut is released.
+
+use crate::cmd::audio::{buffer_peak, StreamHandle};
+use crate::recording::chunker::SAMPLE_RATE;
+use crate::recording::resample::{downmix, LinearResampler, LowPass};
+use crate::recording::voice_activity::{AutoStopOptions, StopReason, VoiceActivity};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, Sample, SizedSample, Stream, SupportedStreamConfig};
+use eyre::{bail, eyre, Context, ContextCompat, Result};
+use std::sync::{Arc, Mutex};
+
+/// Length of the windows whose peaks drive auto-stop, like `record_level`.
+const WINDOW_MS: u64 = 100;
+
+type AutoStop = Box<dyn FnOnce(StopReason) + Send>;
+
+struct Recorded {
+    /// Mono, at the device rate.
+    samples: Vec<f32>,
+    activity: VoiceActivity,
+    window_peak: f32,
+    window_frames: u64,
+}
+
+pub struct Capture {
+    stream: StreamHandle,
+    recorded: Arc<Mutex<Recorded>>,
+    sample_rate: u32,
+}
+
+impl Capture {
+    /// Start capturing from the input with the given stable id, or the default input when it is
+    /// unset or unplugged. `on_auto_stop` is called once, from the audio thread, when `auto_stop`
+    /// decides the phrase is over.
+    pub fn start(
+        device_id: Option<&str>,
+        auto_stop: &AutoStopOptions,
+        on_auto_stop: impl FnOnce(StopReason) + Send + 'static,
+    ) -> Result<Self> {
+        let host = cpal::default_host();
+        let device = match device_id.map(|id| crate::audio_devices::find(&host, id)) {
+            Some(Ok(device)) => device,
+            Some(Err(error)) => {
+                tracing::debug!("dictating from the default input instead: {:?}", error);
+                host.default_input_device().context("No input device found")?
+            }
+            None => host.default_input_device().context("No input device found")?,
+        };
+        let config = device.default_input_config().context("Failed to get default input config")?;
+        let sample_rate = config.sample_rate();
+        let recorded = Arc::new(Mutex::new(Recorded {
+            samples: Vec::new(),
+            activity: VoiceActivity::new(auto_stop),
+            window_peak: 0.0,
+            window_frames: 0,
+        }));
+
+        let stream = build_stream(&device, config, recorded.clone(), Box::new(on_auto_stop))?;
+        stream.play().context("Failed to start stream")?;
+        Ok(Self {
+            stream: StreamHandle(stream),
+            recorded,
+            sample_rate,
+        })
+    }
+
+    /// Stop capturing and return the phrase as 16 kHz mono, with the silence around it trimmed
+    /// when `auto_stop` asked for that.
+    pub fn finish(self) -> Result<Vec<f32>> {
+        self.stream.0.pause().map_err(|e| eyre!("{:?}", e))?;
+        drop(self.stream);
+        let recorded = self.recorded.lock().map_err(|e| eyre!("{:?}", e))?;
+
+        let recorded_ms = recorded.samples.len() as u64 * 1000 / u64::from(self.sample_rate);
+        let samples = match recorded.activity.trim_span(recorded_ms) {
+            Some((start, end)) => {
+                let index = |ms: u64| ((ms * u64::from(self.sample_rate) / 1000) as usize).min(recorded.samples.len());
+                &recorded.samples[index(start)..index(end)]
+            }
+            None => &recorded.samples[..],
+        };
+
+        let mut samples = samples.to_vec();
+        if let Some(mut low_pass) = LowPass::for_resampling(self.sample_rate, SAMPLE_RATE) {
+            samples = low_pass.process(&samples);
+        }
+        Ok(LinearResampler::new(self.sample_rate, SAMPLE_RATE).process(&samples))
+    }
+}
+
+fn build_stream_typed<T>(
+    device: &Device,
+    config: SupportedStreamConfig,
+    recorded: Arc<Mutex<Recorded>>,
+    on_auto_stop: AutoStop,
+) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = usize::from(config.channels());
+    let window_frames = u64::from(config.sample_rate()) * WINDOW_MS / 1000;
+    let sample_rate = u64::from(config.sample_rate());
+    let mut on_auto_stop = Some(on_auto_stop);
+    let stream = device.build_input_stream(
+        config.into(),
+        move |data: &[T], _: &_| {
+            let peak = buffer_peak(data);
+            let mono = downmix(data.iter().map(|&sample| f32::from_sample(sample)), channels);
+            let Ok(mut recorded) = recorded.lock() else {
+                return;
+            };
+            recorded.samples.extend_from_slice(&mono);
+            recorded.window_peak = recorded.window_peak.max(peak);
+            recorded.window_frames += mono.len() as u64;
+            if recorded.window_frames < window_frames {
+                return;
+            }
+            let recorded_ms = recorded.samples.len() as u64 * 1000 / sample_rate;
+            let level = recorded.window_peak;
+            recorded.window_peak = 0.0;
+            recorded.window_frames = 0;
+            if let Some(reason) = recorded.activity.push(recorded_ms, level) {
+                drop(recorded);
+                if let Some(on_auto_stop) = on_auto_stop.take() {
+                    on_auto_stop(reason);
+                }
+            }
+        },
+        |err| tracing::error!("An error occurred on the dictation stream: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+fn build_stream(
+    device: &Device,
+    config: SupportedStreamConfig,
+    recorded: Arc<Mutex<Recorded>>,
+    on_auto_stop: AutoStop,
+) -> Result<Stream> {
+    match config.sample_format() {
+        cpal::SampleFormat::I8 => build_stream_typed::<i8>(device, config, recorded, on_auto_stop),
+        cpal::SampleFormat::I16 => build_stream_typed::<i16>(device, config, recorded, on_auto_stop),
+        cpal::SampleFormat::I32 => build_stream_typed::<i32>(device, config, recorded, on_auto_stop),
+        cpal::SampleFormat::F32 => build_stream_typed::<f32>(device, config, recorded, on_auto_stop),
+        sample_format => bail!("Unsupported sample format '{}'", sample_format),
+    }
+}
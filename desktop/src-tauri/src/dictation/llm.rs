@@ -0,0 +1,129 @@
+//! Rewriting dictated text with the LLM configured for summaries (`summarize.llm`), the way the
+//! frontend clients in `lib/llm` ask it.
+
+use eyre::{bail, ContextCompat, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const CLAUDE_URL: &str = "https://api.anthropic.com/v1/messages";
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+/// Same as `llmDefaultMaxTokens` in the frontend config.
+const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    #[default]
+    Claude,
+    Ollama,
+    Openai,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LlmConfig {
+    pub platform: Platform,
+    pub enabled: bool,
+    /// `%s` is replaced by the dictated text.
+    pub prompt: String,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    pub claude_api_key: String,
+    pub ollama_base_url: String,
+    pub openai_base_url: Option<String>,
+    pub openai_api_key: Option<String>,
+}
+
+/// The answer text of a response from `platform`.
+fn answer(platform: Platform, response: &Value) -> Option<String> {
+    let text = match platform {
+        Platform::Claude => &response["content"][0]["text"],
+        Platform::Ollama => &response["response"],
+        Platform::Openai => &response["choices"][0]["message"]["content"],
+    };
+    text.as_str().map(str::to_string)
+}
+
+/// Ask the configured LLM to rewrite `text` with the configured prompt.
+pub async fn rewrite(config: &LlmConfig, text: &str) -> Result<String> {
+    let prompt = config.prompt.replace("%s", text);
+    let max_tokens = config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let client = reqwest::Client::new();
+    let request = match config.platform {
+        Platform::Claude => client
+            .post(CLAUDE_URL)
+            .header("x-api-key", &config.claude_api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&json!({
+                "model": config.model,
+                "max_tokens": max_tokens,
+                "messages": [{ "role": "user", "content": prompt }],
+            })),
+        Platform::Ollama => client
+            .post(format!("{}/api/generate", config.ollama_base_url.trim_end_matches('/')))
+            // Ollama only answers origins it allows.
+            .header("Origin", "http://127.0.0.1")
+            .json(&json!({ "model": config.model, "prompt": prompt, "stream": false })),
+        Platform::Openai => {
+            let base_url = config
+                .openai_base_url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .unwrap_or(DEFAULT_OPENAI_BASE_URL);
+            let request = client
+                .post(format!("{}/chat/completions", base_url.trim_end_matches('/')))
+                .json(&json!({
+                    "model": config.model,
+                    "max_tokens": max_tokens,
+                    "messages": [{ "role": "user", "content": prompt }],
+                }));
+            match config.openai_api_key.as_deref().filter(|key| !key.is_empty()) {
+                Some(key) => request.bearer_auth(key),
+                None => request,
+            }
+        }
+    };
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("LLM request failed: {}", status);
+    }
+    let body: Value = response.json().await?;
+    answer(config.platform, &body).context("LLM response has no text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_answer_of_each_platform() {
+        assert_eq!(
+            answer(Platform::Claude, &json!({"content": [{"type": "text", "text": "hi"}]})).as_deref(),
+            Some("hi")
+        );
+        assert_eq!(answer(Platform::Ollama, &json!({"response": "hi"})).as_deref(), Some("hi"));
+        assert_eq!(
+            answer(Platform::Openai, &json!({"choices": [{"message": {"content": "hi"}}]})).as_deref(),
+            Some("hi")
+        );
+        assert_eq!(answer(Platform::Openai, &json!({"choices": []})), None);
+    }
+
+    #[test]
+    fn reads_the_frontend_config() {
+        let config: LlmConfig = serde_json::from_value(json!({
+            "platform": "openai",
+            "enabled": true,
+            "prompt": "Fix: %s",
+            "model": "gpt-4o-mini",
+            "claudeApiKey": "",
+            "ollamaBaseUrl": "",
+            "openaiBaseUrl": "https://api.openai.com/v1",
+        }))
+        .unwrap();
+        assert_eq!(config.platform, Platform::Openai);
+        assert!(config.enabled);
+        assert_eq!(config.max_tokens, None);
+    }
+}
@@ -0,0 +1,533 @@
+//! Global push-to-talk dictation, run entirely in the backend.
+//!
+//! The shortcut is registered from Rust, the microphone is captured in memory (`capture`), the
+//! phrase is transcribed, optionally rewritten by the configured LLM (`llm`), and the text is
+//! typed or copied. A dictation model other than the main one gets a Sona process of its own, so
+//! it never swaps the model a transcription is using; otherwise the shared process is reused, so
+//! the same model isn't loaded twice. The dictation indicator follows each session, and every
+//! status change is also emitted as `dictation_state`. Nothing here waits on the webview, so
+//! dictation keeps working while the main window is hidden, throttled or closed to the tray.
+//!
+//! Settings are read from the store when the shortcut is pressed, so changes apply to the next
+//! phrase; only the shortcut itself has to be registered again (`set_dictation_shortcut`).
+
+mod capture;
+mod llm;
+mod postprocess;
+
+use crate::cmd::TranscribeOptions;
+use crate::config::STORE_FILENAME;
+use crate::dictation_indicator::DictationIndicatorPayload;
+use crate::error::LogError;
+use crate::ffmpeg::{get_vibe_temp_folder, random_string};
+use crate::recording::chunker::SAMPLE_RATE;
+use crate::recording::voice_activity::AutoStopOptions;
+use crate::setup::SonaState;
+use crate::sona::{SonaEvent, SonaProcess};
+use crate::text_insertion::{self, InsertionOptions};
+use capture::Capture;
+use eyre::{bail, eyre, ContextCompat, Result};
+use futures_util::StreamExt;
+use postprocess::{PostProcessOptions, PreviousDictation, Snippet};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+
+const CONFIG_KEY_ENABLED: &str = "dictation.enabled";
+const CONFIG_KEY_SHORTCUT: &str = "dictation.shortcut";
+const CONFIG_KEY_ACTIVATION_MODE: &str = "dictation.activationMode";
+const CONFIG_KEY_OUTPUT_MODE: &str = "dictation.outputMode";
+const CONFIG_KEY_NORMALIZE_OUTPUT: &str = "dictation.normalizeOutput";
+const CONFIG_KEY_SILENCE_SECONDS: &str = "dictation.autoStopSilenceSeconds";
+const CONFIG_KEY_MAX_DURATION_SECONDS: &str = "dictation.maxDurationSeconds";
+/// Turns "comma", "new line", "delete that"... into what they name (`postprocess`). Off unless
+/// chosen: the same words also occur in ordinary speech ("a point", "punto").
+const CONFIG_KEY_SPOKEN_COMMANDS: &str = "dictation.spokenCommands";
+const CONFIG_KEY_TRAILING_SPACE: &str = "dictation.trailingSpace";
+/// `[{ phrase, text }]`: saying the phrase types the text.
+const CONFIG_KEY_SNIPPETS: &str = "dictation.snippets";
+/// A small, fast model for dictation; the transcription model when unset.
+const CONFIG_KEY_MODEL_PATH: &str = "dictation.modelPath";
+const CONFIG_KEY_INPUT_DEVICE_ID: &str = "recording.inputDeviceId";
+const CONFIG_KEY_TRANSCRIPTION_MODEL_PATH: &str = "model.path";
+const CONFIG_KEY_GPU_DEVICE: &str = "model.gpuDevice";
+const CONFIG_KEY_UNLOAD_TIMEOUT_MINUTES: &str = "model.unloadTimeoutMinutes";
+const CONFIG_KEY_MODEL_OPTIONS: &str = "transcription.modelOptions";
+/// The LLM used for summaries; when enabled it rewrites dictated text with its prompt too.
+const CONFIG_KEY_LLM: &str = "summarize.llm";
+
+/// Same as `DEFAULT_HOTKEY_SHORTCUT` in the frontend.
+const DEFAULT_SHORTCUT: &str = "CmdOrCtrl+Shift+Space";
+/// Same as `vadModelFilename` in the frontend config; models that require VAD fail without it.
+const VAD_MODEL_FILENAME: &str = "ggml-silero-v6.2.0.bin";
+const DEFAULT_UNLOAD_TIMEOUT_MINUTES: u32 = 5;
+/// Anything shorter is a shortcut tapped by mistake, not a phrase.
+const MIN_PHRASE_SAMPLES: usize = SAMPLE_RATE as usize / 10;
+const COMPLETED_INDICATOR_DELAY: Duration = Duration::from_millis(1500);
+const ERROR_INDICATOR_DELAY: Duration = Duration::from_millis(3500);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ActivationMode {
+    /// Record while the shortcut is held.
+    #[default]
+    PushToTalk,
+    /// The first press starts, the next one stops.
+    Toggle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputMode {
+    #[default]
+    Clipboard,
+    /// Inserted into the focused app (`text_insertion`).
+    Type,
+}
+
+impl OutputMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Clipboard => "clipboard",
+            Self::Type => "type",
+        }
+    }
+}
+
+fn setting<T: DeserializeOwned>(app_handle: &AppHandle, key: &str) -> Option<T> {
+    app_handle
+        .store(STORE_FILENAME)
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+/// What a dictation session needs from settings, read when it starts.
+struct Settings {
+    output_mode: OutputMode,
+    insertion: InsertionOptions,
+    normalize_output: bool,
+    post_process: PostProcessOptions,
+    auto_stop: AutoStopOptions,
+    input_device_id: Option<String>,
+    model_path: Option<String>,
+    /// `model_path` differs from the main model, so it needs dictation's own Sona process.
+    dedicated_model: bool,
+    gpu_device: Option<i32>,
+    unload_timeout_minutes: u32,
+    model_options: serde_json::Value,
+    llm: Option<llm::LlmConfig>,
+}
+
+impl Settings {
+    fn read(app_handle: &AppHandle) -> Self {
+        // 0 turns either limit off.
+        let seconds = |key: &str| setting::<f64>(app_handle, key).filter(|seconds| *seconds > 0.0);
+        let model_options = setting(app_handle, CONFIG_KEY_MODEL_OPTIONS).unwrap_or_else(|| serde_json::json!({}));
+        let main_model_path = setting::<String>(app_handle, CONFIG_KEY_TRANSCRIPTION_MODEL_PATH);
+        let dictation_model_path = setting::<String>(app_handle, CONFIG_KEY_MODEL_PATH)
+            .filter(|path| Path::new(path).exists())
+            .filter(|path| main_model_path.as_ref() != Some(path));
+        Self {
+            output_mode: setting(app_handle, CONFIG_KEY_OUTPUT_MODE).unwrap_or_default(),
+            insertion: InsertionOptions::from_config(app_handle),
+            normalize_output: setting(app_handle, CONFIG_KEY_NORMALIZE_OUTPUT).unwrap_or(true),
+            post_process: PostProcessOptions {
+                lang: model_options["lang"].as_str().map(str::to_string),
+                spoken_commands: setting(app_handle, CONFIG_KEY_SPOKEN_COMMANDS).unwrap_or(false),
+                trailing_space: setting(app_handle, CONFIG_KEY_TRAILING_SPACE).unwrap_or(false),
+                snippets: setting::<Vec<Snippet>>(app_handle, CONFIG_KEY_SNIPPETS).unwrap_or_default(),
+            },
+            auto_stop: AutoStopOptions {
+                silence_seconds: seconds(CONFIG_KEY_SILENCE_SECONDS),
+                max_duration_seconds: seconds(CONFIG_KEY_MAX_DURATION_SECONDS),
+                trim_silence: Some(true),
+                silence_threshold: None,
+            },
+            input_device_id: setting(app_handle, CONFIG_KEY_INPUT_DEVICE_ID),
+            dedicated_model: dictation_model_path.is_some(),
+            model_path: dictation_model_path.or(main_model_path),
+            gpu_device: setting(app_handle, CONFIG_KEY_GPU_DEVICE),
+            unload_timeout_minutes: setting(app_handle, CONFIG_KEY_UNLOAD_TIMEOUT_MINUTES)
+                .unwrap_or(DEFAULT_UNLOAD_TIMEOUT_MINUTES),
+            model_options,
+            llm: setting::<llm::LlmConfig>(app_handle, CONFIG_KEY_LLM).filter(|config| config.enabled),
+        }
+    }
+
+    /// The transcription options from settings, for the phrase at `path`.
+    fn transcribe_options(&self, path: &Path) -> Result<TranscribeOptions> {
+        let mut options = match &self.model_options {
+            serde_json::Value::Object(options) => options.clone(),
+            _ => serde_json::Map::new(),
+        };
+        options.insert("path".to_string(), path.to_string_lossy().into());
+        Ok(serde_json::from_value(options.into())?)
+    }
+}
+
+#[derive(Default)]
+enum Session {
+    #[default]
+    Idle,
+    Recording {
+        id: u64,
+        capture: Capture,
+        settings: Settings,
+    },
+    /// Transcribing and delivering; presses are ignored until it is done.
+    Busy,
+}
+
+#[derive(Default)]
+pub struct DictationState {
+    session: Mutex<Session>,
+    /// The registered shortcut, so it can be released when it changes.
+    shortcut: Mutex<Option<String>>,
+    /// In toggle mode, whether the shortcut is held: a held key repeats its press.
+    held: AtomicBool,
+    next_id: AtomicU64,
+    /// What the last dictation typed, for capitalization and "delete that".
+    previous: Mutex<Option<PreviousDictation>>,
+    /// Dictation's own Sona process, for a dictation model other than the main one: loading it into
+    /// the shared process would swap the model under a transcription that is running.
+    sona: tokio::sync::Mutex<Option<SonaProcess>>,
+}
+
+impl DictationState {
+    fn is_recording(&self) -> bool {
+        self.session
+            .lock()
+            .is_ok_and(|session| matches!(*session, Session::Recording { .. }))
+    }
+
+    fn set_idle(&self) {
+        if let Ok(mut session) = self.session.lock() {
+            *session = Session::Idle;
+        }
+    }
+
+    /// Stop dictation's Sona process, for when the app exits.
+    pub fn kill_sona(&self) {
+        if let Ok(mut sona) = self.sona.try_lock() {
+            if let Some(ref mut process) = *sona {
+                process.kill();
+            }
+        }
+    }
+}
+
+/// Register the shortcut from settings, or release it when dictation is off. Called on startup
+/// and after the config file was edited outside the app.
+pub fn sync_shortcut(app_handle: &AppHandle) {
+    let shortcut = setting::<bool>(app_handle, CONFIG_KEY_ENABLED)
+        .unwrap_or(false)
+        .then(|| setting(app_handle, CONFIG_KEY_SHORTCUT).unwrap_or_else(|| DEFAULT_SHORTCUT.to_string()));
+    register(app_handle, shortcut).log_error();
+}
+
+fn register(app_handle: &AppHandle, shortcut: Option<String>) -> Result<()> {
+    let state = app_handle.state::<DictationState>();
+    let mut registered = state.shortcut.lock().map_err(|e| eyre!("{:?}", e))?;
+    if *registered == shortcut {
+        return Ok(());
+    }
+    if let Some(previous) = registered.take() {
+        app_handle
+            .global_shortcut()
+            .unregister(previous.as_str())
+            .map_err(|e| eyre!("Failed to unregister {previous}: {e:?}"))
+            .log_error();
+    }
+    if let Some(shortcut) = shortcut {
+        app_handle
+            .global_shortcut()
+            .on_shortcut(shortcut.as_str(), |app_handle, _shortcut, event| {
+                on_shortcut(app_handle, event.state)
+            })
+            .map_err(|e| eyre!("Failed to register {shortcut}: {e:?}"))?;
+        tracing::debug!("dictation shortcut registered: {shortcut}");
+        *registered = Some(shortcut);
+    }
+    Ok(())
+}
+
+/// Register `shortcut` for dictation in place of the current one; None releases it, which the
+/// settings do while a new shortcut is being recorded so it cannot trigger dictation.
+#[tauri::command]
+pub fn set_dictation_shortcut(app_handle: AppHandle, shortcut: Option<String>) -> Result<()> {
+    register(&app_handle, shortcut)
+}
+
+fn on_shortcut(app_handle: &AppHandle, key: ShortcutState) {
+    let state = app_handle.state::<DictationState>();
+    let mode: ActivationMode = setting(app_handle, CONFIG_KEY_ACTIVATION_MODE).unwrap_or_default();
+    match (mode, key) {
+        (ActivationMode::PushToTalk, ShortcutState::Pressed) => start(app_handle),
+        (ActivationMode::PushToTalk, ShortcutState::Released) => stop(app_handle, None),
+        (ActivationMode::Toggle, ShortcutState::Pressed) => {
+            if state.held.swap(true, Ordering::Relaxed) {
+                return;
+            }
+            if state.is_recording() {
+                stop(app_handle, None);
+            } else {
+                start(app_handle);
+            }
+        }
+        (ActivationMode::Toggle, ShortcutState::Released) => state.held.store(false, Ordering::Relaxed),
+    }
+}
+
+fn start(app_handle: &AppHandle) {
+    let state = app_handle.state::<DictationState>();
+    let Ok(mut session) = state.session.lock() else {
+        return;
+    };
+    if !matches!(*session, Session::Idle) {
+        return;
+    }
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let settings = Settings::read(app_handle);
+
+    let app_handle_c = app_handle.clone();
+    let capture = Capture::start(settings.input_device_id.as_deref(), &settings.auto_stop, move |reason| {
+        tracing::debug!("dictation stopped on its own: {:?}", reason);
+        // Off the audio thread: stopping pauses the stream that is calling this.
+        std::thread::spawn(move || stop(&app_handle_c, Some(id)));
+    });
+    match capture {
+        Ok(capture) => {
+            *session = Session::Recording { id, capture, settings };
+            drop(session);
+            show_status(app_handle, id, "recording", None, None);
+        }
+        Err(error) => {
+            drop(session);
+            finish(app_handle, id, Err(error.wrap_err("Failed to start dictation")));
+        }
+    }
+}
+
+/// Stop the session being recorded, or only session `only` (for auto-stop, which must not stop
+/// a newer one), and transcribe it in the background.
+fn stop(app_handle: &AppHandle, only: Option<u64>) {
+    let state = app_handle.state::<DictationState>();
+    let (id, capture, settings) = {
+        let Ok(mut session) = state.session.lock() else {
+            return;
+        };
+        match std::mem::replace(&mut *session, Session::Busy) {
+            Session::Recording { id, capture, settings } if only.is_none_or(|only| only == id) => (id, capture, settings),
+            other => {
+                *session = other;
+                return;
+            }
+        }
+    };
+
+    let samples = match capture.finish() {
+        Ok(samples) => samples,
+        Err(error) => {
+            state.set_idle();
+            finish(app_handle, id, Err(error));
+            return;
+        }
+    };
+    if samples.len() < MIN_PHRASE_SAMPLES {
+        tracing::debug!("dictation too short to transcribe: {} samples", samples.len());
+        state.set_idle();
+        crate::dictation_indicator::hide_dictation_indicator(app_handle.clone(), id).log_error();
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        show_status(&app_handle, id, "transcribing", None, None);
+        let result = match transcribe(&app_handle, &settings, &samples).await {
+            Ok(text) => deliver(&app_handle, &settings, &text).await,
+            Err(error) => Err(error),
+        };
+        app_handle.state::<DictationState>().set_idle();
+        finish(&app_handle, id, result.map(|()| settings.output_mode));
+    });
+}
+
+async fn transcribe(app_handle: &AppHandle, settings: &Settings, samples: &[f32]) -> Result<String> {
+    let model_path = settings.model_path.clone().context("No model selected")?;
+    let path = get_vibe_temp_folder().join(format!("dictation-{}.wav", random_string(10)));
+    crate::recording::normalize::write_wav(&path, samples)?;
+    let result = transcribe_file(app_handle, settings, &model_path, &path).await;
+    std::fs::remove_file(&path).map_err(|e| eyre!("{e:?}")).log_error();
+
+    let lines: Vec<String> = result?.iter().map(|text| text.trim().to_string()).collect();
+    let mut text = lines.join("\n");
+    if let Some(config) = settings.llm.as_ref().filter(|_| !text.trim().is_empty()) {
+        // The phrase as transcribed beats no phrase at all.
+        match llm::rewrite(config, &text).await {
+            Ok(rewritten) => text = rewritten,
+            Err(error) => tracing::error!("dictation LLM rewrite failed: {:?}", error),
+        }
+    }
+    Ok(if settings.normalize_output {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text.trim().to_string()
+    })
+}
+
+/// The text of each segment Sona finds in the file.
+async fn transcribe_file(app_handle: &AppHandle, settings: &Settings, model_path: &str, path: &Path) -> Result<Vec<String>> {
+    let (client, base_url) = if settings.dedicated_model {
+        let state = app_handle.state::<DictationState>();
+        let mut sona = state.sona.lock().await;
+        load_sona(app_handle, settings, &mut sona, model_path).await?
+    } else {
+        // A process left from an earlier dictation model would only hold memory now; dropping it
+        // kills it.
+        app_handle.state::<DictationState>().sona.lock().await.take();
+        let state = app_handle.state::<tokio::sync::Mutex<SonaState>>();
+        let mut shared = state.lock().await;
+        load_sona(app_handle, settings, &mut shared.process, model_path).await?
+    }; // lock released here, so a transcription can share the process meanwhile
+
+    let mut options = settings.transcribe_options(path)?;
+    let requires_vad = SonaProcess::model_metadata_with(&client, &base_url, model_path)
+        .await
+        .log_error()
+        .is_some_and(|metadata| metadata.capabilities.requires_vad);
+    if requires_vad {
+        let models_folder = crate::cmd::app::get_models_folder(app_handle.clone())?;
+        options.vad_model = Some(models_folder.join(VAD_MODEL_FILENAME).to_string_lossy().to_string());
+    }
+
+    let stream = SonaProcess::transcribe_stream(&client, &base_url, &options).await?;
+    tokio::pin!(stream);
+    let mut texts = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            SonaEvent::Segment { text, .. } => texts.push(text),
+            SonaEvent::Error { message, .. } => bail!("sona: {}", message),
+            SonaEvent::Progress { .. } | SonaEvent::Result { .. } => {}
+        }
+    }
+    Ok(texts)
+}
+
+/// Load `model_path` into the Sona process in `slot` and return where to reach it.
+async fn load_sona(
+    app_handle: &AppHandle,
+    settings: &Settings,
+    slot: &mut Option<SonaProcess>,
+    model_path: &str,
+) -> Result<(reqwest::Client, String)> {
+    crate::cmd::sona_cmd::load_model_into(
+        app_handle,
+        slot,
+        model_path,
+        settings.gpu_device,
+        settings.unload_timeout_minutes,
+    )
+    .await?;
+    let process = slot.as_ref().context("Sona is not running")?;
+    Ok((process.client(), process.base_url()))
+}
+
+/// Post-process `text` against the previous dictation, then type or copy it.
+async fn deliver(app_handle: &AppHandle, settings: &Settings, text: &str) -> Result<()> {
+    let state = app_handle.state::<DictationState>();
+    // Copied text is pasted wherever the user likes, so only typed text continues the last one.
+    let previous = match settings.output_mode {
+        OutputMode::Type => state.previous.lock().ok().and_then(|previous| previous.clone()),
+        OutputMode::Clipboard => None,
+    };
+    let processed = postprocess::process(text, &settings.post_process, previous.as_ref());
+
+    match settings.output_mode {
+        OutputMode::Type => {
+            let typed = processed.text.clone();
+            let erase = processed.erase_previous;
+            let insertion = settings.insertion.clone();
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                text_insertion::erase_text(erase)?;
+                text_insertion::insert_text(&app_handle, &typed, &insertion)
+            })
+            .await??;
+        }
+        OutputMode::Clipboard => {
+            // What was copied cannot be taken back, so "delete that" alone only copies nothing.
+            if !processed.text.is_empty() {
+                app_handle
+                    .clipboard()
+                    .write_text(processed.text.clone())
+                    .map_err(|e| eyre!("{e:?}"))?;
+                notify(app_handle, "Transcription copied to clipboard");
+            }
+        }
+    }
+
+    if let Ok(mut previous) = state.previous.lock() {
+        if !processed.text.is_empty() {
+            *previous = Some(PreviousDictation::new(&processed.text));
+        } else if processed.erase_previous > 0 {
+            // What came before the erased dictation is unknown.
+            *previous = None;
+        }
+    }
+    Ok(())
+}
+
+fn notify(app_handle: &AppHandle, body: &str) {
+    app_handle
+        .notification()
+        .builder()
+        .title("Vibe")
+        .body(body)
+        .show()
+        .map_err(|e| eyre!("{e:?}"))
+        .log_error();
+}
+
+fn show_status(app_handle: &AppHandle, id: u64, status: &str, output: Option<OutputMode>, message: Option<String>) {
+    let payload = DictationIndicatorPayload {
+        session_id: id,
+        status: status.to_string(),
+        output: output.map(|output| output.as_str().to_string()),
+        message,
+    };
+    app_handle.emit("dictation_state", payload.clone()).log_error();
+    if let Err(error) = crate::dictation_indicator::show_dictation_indicator(app_handle.clone(), payload) {
+        tracing::error!("Could not show dictation indicator: {error}");
+    }
+}
+
+/// Show how the session ended, then hide the indicator unless a newer session took it over.
+fn finish(app_handle: &AppHandle, id: u64, result: Result<OutputMode>) {
+    let delay = match result {
+        Ok(output) => {
+            show_status(app_handle, id, "completed", Some(output), None);
+            COMPLETED_INDICATOR_DELAY
+        }
+        Err(error) => {
+            tracing::error!("dictation failed: {:?}", error);
+            let message = format!("{error:#}");
+            show_status(app_handle, id, "error", None, Some(message.clone()));
+            notify(app_handle, &message);
+            ERROR_INDICATOR_DELAY
+        }
+    };
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        crate::dictation_indicator::hide_dictation_indicator(app_handle, id).log_error();
+    });
+}
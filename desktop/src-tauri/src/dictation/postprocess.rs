@@ -0,0 +1,365 @@
+//! Turns what the model heard into what should be typed.
+//!
+//! Spoken punctuation and commands ("comma", "new line", "delete that") become the characters or
+//! edits they name, user snippets expand, and capitalization follows on from the previous
+//! dictation: a phrase that continues a sentence starts in lowercase, one after a full stop or a
+//! new line starts capitalized. Words are matched case-insensitively, ignoring the punctuation the
+//! model put around them, so "Hello, period." still ends a sentence once.
+
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// A previous dictation older than this no longer decides how the next one starts; the cursor has
+/// probably moved on.
+const CONTEXT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Punctuation that replaces whatever the model put at the end of the previous word.
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?'];
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '\n'];
+
+/// User-defined expansion: saying `phrase` types `text`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snippet {
+    pub phrase: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Punctuation(char),
+    NewLine,
+    NewParagraph,
+    DeleteThat,
+}
+
+struct LanguageRules {
+    commands: &'static [(&'static str, Command)],
+    /// Punctuation written with a space before it, as in French.
+    spaced_punctuation: &'static [char],
+    /// Whether a phrase that continues a sentence may start in lowercase. Off where nouns are
+    /// always capitalized.
+    lowercase_continuation: bool,
+}
+
+const ENGLISH: LanguageRules = LanguageRules {
+    commands: &[
+        ("period", Command::Punctuation('.')),
+        ("full stop", Command::Punctuation('.')),
+        ("comma", Command::Punctuation(',')),
+        ("question mark", Command::Punctuation('?')),
+        ("exclamation mark", Command::Punctuation('!')),
+        ("exclamation point", Command::Punctuation('!')),
+        ("colon", Command::Punctuation(':')),
+        ("semicolon", Command::Punctuation(';')),
+        ("new line", Command::NewLine),
+        ("new paragraph", Command::NewParagraph),
+        ("delete that", Command::DeleteThat),
+        ("scratch that", Command::DeleteThat),
+    ],
+    spaced_punctuation: &[],
+    lowercase_continuation: true,
+};
+
+const GERMAN: LanguageRules = LanguageRules {
+    commands: &[
+        ("punkt", Command::Punctuation('.')),
+        ("komma", Command::Punctuation(',')),
+        ("fragezeichen", Command::Punctuation('?')),
+        ("ausrufezeichen", Command::Punctuation('!')),
+        ("doppelpunkt", Command::Punctuation(':')),
+        ("semikolon", Command::Punctuation(';')),
+        ("neue zeile", Command::NewLine),
+        ("neuer absatz", Command::NewParagraph),
+        ("lösch das", Command::DeleteThat),
+    ],
+    spaced_punctuation: &[],
+    lowercase_continuation: false,
+};
+
+const FRENCH: LanguageRules = LanguageRules {
+    commands: &[
+        ("point", Command::Punctuation('.')),
+        ("virgule", Command::Punctuation(',')),
+        ("point d'interrogation", Command::Punctuation('?')),
+        ("point d'exclamation", Command::Punctuation('!')),
+        ("deux points", Command::Punctuation(':')),
+        ("point-virgule", Command::Punctuation(';')),
+        ("à la ligne", Command::NewLine),
+        ("nouvelle ligne", Command::NewLine),
+        ("nouveau paragraphe", Command::NewParagraph),
+        ("efface ça", Command::DeleteThat),
+    ],
+    spaced_punctuation: &['?', '!', ':', ';'],
+    lowercase_continuation: true,
+};
+
+const SPANISH: LanguageRules = LanguageRules {
+    commands: &[
+        ("punto", Command::Punctuation('.')),
+        ("coma", Command::Punctuation(',')),
+        ("signo de interrogación", Command::Punctuation('?')),
+        ("signo de exclamación", Command::Punctuation('!')),
+        ("dos puntos", Command::Punctuation(':')),
+        ("punto y coma", Command::Punctuation(';')),
+        ("nueva línea", Command::NewLine),
+        ("nuevo párrafo", Command::NewParagraph),
+        ("borra eso", Command::DeleteThat),
+    ],
+    spaced_punctuation: &[],
+    lowercase_continuation: true,
+};
+
+/// Rules for a transcription language code, English when unknown or auto-detected.
+fn rules(lang: Option<&str>) -> &'static LanguageRules {
+    match lang.map(|lang| lang.split(['-', '_']).next().unwrap_or(lang)) {
+        Some("de") => &GERMAN,
+        Some("fr") => &FRENCH,
+        Some("es") => &SPANISH,
+        _ => &ENGLISH,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessOptions {
+    pub lang: Option<String>,
+    pub spoken_commands: bool,
+    pub trailing_space: bool,
+    pub snippets: Vec<Snippet>,
+}
+
+/// What the last dictation left behind at the cursor.
+#[derive(Debug, Clone)]
+pub struct PreviousDictation {
+    /// Characters it typed.
+    pub len: usize,
+    ends_sentence: bool,
+    /// Whether the next dictation has to start with a space to not run into it.
+    needs_space: bool,
+    at: Instant,
+}
+
+impl PreviousDictation {
+    pub fn new(text: &str) -> Self {
+        Self {
+            len: text.chars().count(),
+            ends_sentence: text.trim_end_matches(' ').ends_with(SENTENCE_ENDS),
+            needs_space: !text.is_empty() && !text.ends_with([' ', '\n']),
+            at: Instant::now(),
+        }
+    }
+
+    fn is_recent(&self) -> bool {
+        self.at.elapsed() < CONTEXT_TIMEOUT
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Processed {
+    pub text: String,
+    /// Characters of the previous dictation to erase before typing, when "delete that" was said
+    /// with nothing before it.
+    pub erase_previous: usize,
+}
+
+enum Match<'a> {
+    Command(Command),
+    Snippet(&'a str),
+}
+
+/// A word as it is matched: lowercase, without the punctuation around it.
+fn key(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .replace('\u{2019}', "'")
+        .to_lowercase()
+}
+
+fn words_of(phrase: &str) -> Vec<String> {
+    phrase.split_whitespace().map(key).filter(|word| !word.is_empty()).collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Lowercase a capitalized word the model started a phrase with, leaving acronyms and "I" alone.
+fn decapitalize(word: &str) -> String {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    let is_pronoun_i = key(word).split('\'').next() == Some("i");
+    if is_pronoun_i || letters.iter().skip(1).any(|c| c.is_uppercase()) {
+        return word.to_string();
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn process(text: &str, options: &PostProcessOptions, previous: Option<&PreviousDictation>) -> Processed {
+    let rules = rules(options.lang.as_deref());
+    let previous = previous.filter(|previous| previous.is_recent());
+    let starts_sentence = previous.is_none_or(|previous| previous.ends_sentence);
+
+    let mut phrases: Vec<(Vec<String>, Match)> = options
+        .snippets
+        .iter()
+        .map(|snippet| (words_of(&snippet.phrase), Match::Snippet(snippet.text.as_str())))
+        .filter(|(words, _)| !words.is_empty())
+        .collect();
+    if options.spoken_commands {
+        phrases.extend(
+            rules
+                .commands
+                .iter()
+                .map(|(phrase, command)| (words_of(phrase), Match::Command(*command))),
+        );
+    }
+    // Longest first, so "point d'interrogation" wins over "point".
+    phrases.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let keys: Vec<String> = words.iter().map(|word| key(word)).collect();
+    let mut out = String::new();
+    let mut capitalize_next = starts_sentence;
+    let mut erase_previous = 0;
+
+    let mut index = 0;
+    while index < words.len() {
+        let found = phrases
+            .iter()
+            .find(|(phrase, _)| keys.get(index..index + phrase.len()) == Some(phrase.as_slice()));
+        let Some((phrase, found)) = found else {
+            let word = words[index];
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push(' ');
+            }
+            if capitalize_next {
+                out.push_str(&capitalize(word));
+            } else if out.is_empty() && rules.lowercase_continuation {
+                out.push_str(&decapitalize(word));
+            } else {
+                out.push_str(word);
+            }
+            capitalize_next = word.ends_with(SENTENCE_ENDS);
+            index += 1;
+            continue;
+        };
+        index += phrase.len();
+
+        match found {
+            Match::Snippet(expansion) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push(' ');
+                }
+                out.push_str(expansion);
+                capitalize_next = expansion.ends_with(SENTENCE_ENDS);
+            }
+            Match::Command(Command::Punctuation(mark)) => {
+                out.truncate(out.trim_end_matches(TRAILING_PUNCTUATION).trim_end_matches(' ').len());
+                if rules.spaced_punctuation.contains(mark) && !out.is_empty() {
+                    out.push(' ');
+                }
+                out.push(*mark);
+                capitalize_next = SENTENCE_ENDS.contains(mark);
+            }
+            Match::Command(Command::NewLine | Command::NewParagraph) => {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push('\n');
+                if matches!(found, Match::Command(Command::NewParagraph)) {
+                    out.push('\n');
+                }
+                capitalize_next = true;
+            }
+            Match::Command(Command::DeleteThat) => {
+                if out.is_empty() {
+                    erase_previous = previous.map_or(0, |previous| previous.len);
+                    capitalize_next = true;
+                    continue;
+                }
+                // Back to the end of the sentence before the last one.
+                let written = out.trim_end_matches(TRAILING_PUNCTUATION);
+                let keep = written.rfind(SENTENCE_ENDS).map_or(0, |end| end + 1);
+                out.truncate(keep);
+                out.truncate(out.trim_end_matches(' ').len());
+                capitalize_next = if out.is_empty() {
+                    starts_sentence
+                } else {
+                    out.ends_with(SENTENCE_ENDS)
+                };
+            }
+        }
+    }
+
+    if options.trailing_space && !out.is_empty() && !out.ends_with('\n') {
+        out.push(' ');
+    }
+    let follows_previous = erase_previous == 0 && previous.is_some_and(|previous| previous.needs_space);
+    if follows_previous && !out.is_empty() && !out.starts_with(TRAILING_PUNCTUATION) && !out.starts_with('\n') {
+        out.insert(0, ' ');
+    }
+    Processed {
+        text: out,
+        erase_previous,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(lang: &str) -> PostProcessOptions {
+        PostProcessOptions {
+            lang: Some(lang.to_string()),
+            spoken_commands: true,
+            trailing_space: false,
+            snippets: vec![Snippet {
+                phrase: "my address".to_string(),
+                text: "1 Main St, Springfield".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn applies_spoken_commands_and_snippets() {
+        let processed = process(
+            "Send it to my address, period. Thanks comma see you new line Bye",
+            &options("en"),
+            None,
+        );
+        assert_eq!(processed.text, "Send it to 1 Main St, Springfield. Thanks, see you\nBye");
+        assert_eq!(processed.erase_previous, 0);
+
+        let french = process("Tu viens point d'interrogation", &options("fr"), None);
+        assert_eq!(french.text, "Tu viens ?");
+    }
+
+    #[test]
+    fn continues_or_erases_the_previous_dictation() {
+        let mid_sentence = PreviousDictation::new("I think ");
+        let processed = process("That works. Delete that", &options("en"), Some(&mid_sentence));
+        assert_eq!(processed.text, "");
+
+        let processed = process("It works and I agree", &options("en"), Some(&mid_sentence));
+        assert_eq!(processed.text, "it works and I agree");
+
+        let finished = PreviousDictation::new("Done.");
+        assert_eq!(process("next one", &options("en"), Some(&finished)).text, " Next one");
+        let processed = process("delete that", &options("en"), Some(&finished));
+        assert_eq!(
+            processed,
+            Processed {
+                text: String::new(),
+                erase_previous: 5
+            }
+        );
+
+        let trailing = PostProcessOptions {
+            trailing_space: true,
+            ..options("de")
+        };
+        assert_eq!(process("Das Haus punkt", &trailing, Some(&mid_sentence)).text, "Das Haus. ");
+    }
+}
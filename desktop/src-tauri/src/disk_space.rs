@@ -0,0 +1,151 @@
+//! Free-space checks before writing large files.
+//!
+//! Running out of space halfway through a model download or a recording leaves a truncated file
+//! and an IO error that says nothing useful, so writers ask here first with the size they expect
+//! to need and fail early with `InsufficientSpace` instead.
+
+use eyre::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Kept free on top of what the file itself needs, so filling the disk to the last byte does not
+/// break the OS or the app's own logs and config.
+const RESERVE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Sample rate and format of the WAV that `recording::normalize` produces.
+const NORMALIZED_SAMPLE_RATE: u32 = 16_000;
+const NORMALIZED_CHANNELS: u16 = 1;
+const NORMALIZED_BITS_PER_SAMPLE: u16 = 16;
+
+/// The target volume cannot hold what is about to be written.
+#[derive(Debug, Clone)]
+pub struct InsufficientSpace {
+    pub path: PathBuf,
+    pub required: u64,
+    pub available: u64,
+}
+
+impl std::fmt::Display for InsufficientSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not enough disk space for {}: {} MB needed, {} MB available",
+            self.path.display(),
+            self.required.div_ceil(1024 * 1024),
+            self.available / (1024 * 1024)
+        )
+    }
+}
+
+impl std::error::Error for InsufficientSpace {}
+
+/// Finds `InsufficientSpace` anywhere in a report, including under added context.
+pub fn find(error: &eyre::Report) -> Option<&InsufficientSpace> {
+    error.chain().find_map(|cause| cause.downcast_ref::<InsufficientSpace>())
+}
+
+/// The closest ancestor that exists, since the file (and often its folder) is not created yet.
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.exists())
+}
+
+/// Bytes available to this user on the volume holding `path`.
+pub fn available_space(path: &Path) -> Result<u64> {
+    let existing = existing_ancestor(path).unwrap_or(Path::new("."));
+    volume_available_space(existing).context(format!("Failed to read free space for {}", existing.display()))
+}
+
+#[cfg(unix)]
+fn volume_available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is a plain C struct.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The field widths differ between Linux and macOS.
+    #[allow(clippy::useless_conversion)]
+    let available = u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize));
+    Ok(available)
+}
+
+#[cfg(windows)]
+fn volume_available_space(path: &Path) -> std::io::Result<u64> {
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let mut available = 0u64;
+    // SAFETY: the out pointer lives for the duration of the call.
+    unsafe { GetDiskFreeSpaceExW(&HSTRING::from(path), Some(&mut available), None, None) }
+        .map_err(|error| std::io::Error::from_raw_os_error(error.code().0))?;
+    Ok(available)
+}
+
+/// Fails with `InsufficientSpace` when the volume holding `path` cannot take `required` more bytes.
+/// A volume whose free space cannot be read is let through: the write itself will still report it.
+pub fn ensure_space(path: &Path, required: u64) -> Result<()> {
+    let available = match available_space(path) {
+        Ok(available) => available,
+        Err(error) => {
+            tracing::warn!("skipping disk space check: {:?}", error);
+            return Ok(());
+        }
+    };
+    if available < required.saturating_add(RESERVE_BYTES) {
+        return Err(InsufficientSpace {
+            path: path.to_path_buf(),
+            required,
+            available,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Size of a PCM WAV file of `duration` in the given format, header included.
+pub fn wav_size(duration: Duration, sample_rate: u32, channels: u16, bits_per_sample: u16) -> u64 {
+    let bytes_per_second = u64::from(sample_rate) * u64::from(channels) * u64::from(bits_per_sample).div_ceil(8);
+    (duration.as_secs_f64() * bytes_per_second as f64).ceil() as u64 + 44
+}
+
+/// Size of the WAV `recording::normalize` writes for `duration` of audio.
+pub fn normalized_wav_size(duration: Duration) -> u64 {
+    wav_size(
+        duration,
+        NORMALIZED_SAMPLE_RATE,
+        NORMALIZED_CHANNELS,
+        NORMALIZED_BITS_PER_SAMPLE,
+    )
+}
+
+/// Duration of a finished WAV file, read from its header.
+pub fn wav_duration(path: &Path) -> Result<Duration> {
+    let reader = hound::WavReader::open(path).context(format!("Failed to open {}", path.display()))?;
+    let spec = reader.spec();
+    Ok(Duration::from_secs_f64(
+        reader.duration() as f64 / f64::from(spec.sample_rate),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_size_counts_every_channel_and_the_header() {
+        assert_eq!(normalized_wav_size(Duration::from_secs(60)), 60 * 16_000 * 2 + 44);
+        assert_eq!(wav_size(Duration::from_secs(1), 48_000, 2, 32), 48_000 * 2 * 4 + 44);
+    }
+
+    #[test]
+    fn impossible_requests_report_what_was_needed() {
+        let dir = std::env::temp_dir();
+        ensure_space(&dir, 0).unwrap();
+        let error = ensure_space(&dir.join("missing/child.wav"), u64::MAX / 2).unwrap_err();
+        let insufficient = find(&error).unwrap();
+        assert_eq!(insufficient.required, u64::MAX / 2);
+        assert!(insufficient.available < insufficient.required);
+    }
+}
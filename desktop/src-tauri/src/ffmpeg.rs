@@ -1,10 +1,15 @@
+use crate::error::LogError;
 use chrono::Local;
-use eyre::{bail, ContextCompat, Result};
+use eyre::{bail, eyre, Context, ContextCompat, Result};
 use rand::distr::Alphanumeric;
 use rand::Rng;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
 use which::which;
 
 pub fn get_local_time() -> String {
@@ -69,12 +74,168 @@ pub fn find_ffmpeg_path() -> Option<PathBuf> {
     None
 }
 
-pub fn normalize(input: PathBuf, output: PathBuf, additional_ffmpeg_args: Option<Vec<String>>) -> Result<()> {
-    let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
+/// How often a running conversion looks for a cancel.
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress and cancellation of one ffmpeg conversion. Clones share the same conversion, so one
+/// can be kept to cancel it while another is handed to `normalize` or `encode`.
+#[derive(Clone, Default)]
+pub struct ConversionHandle {
+    cancelled: Arc<AtomicBool>,
+    on_progress: Option<Arc<dyn Fn(f64) + Send + Sync>>,
+}
+
+impl ConversionHandle {
+    /// A handle whose conversion reports its progress, in percent, to `on_progress`.
+    pub fn with_progress(on_progress: impl Fn(f64) + Send + Sync + 'static) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            on_progress: Some(Arc::new(on_progress)),
+        }
+    }
+
+    /// Stop the conversion within `CANCEL_CHECK_INTERVAL`.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Report progress of work made of several ffmpeg runs, which report nothing themselves.
+    pub fn report_progress(&self, percent: f64) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(percent);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// A conversion stopped through `ConversionHandle::cancel`.
+#[derive(Debug, Clone)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The conversion was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Whether a report comes from a cancelled conversion, including under added context.
+pub fn is_cancelled(error: &eyre::Report) -> bool {
+    error.chain().any(|cause| cause.is::<Cancelled>())
+}
+
+/// Seconds of output written so far, from one line of `-progress` output.
+fn parse_progress_line(line: &str) -> Option<f64> {
+    // Both are in microseconds; `out_time_ms` is the older name.
+    let (key, value) = line.split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => value.trim().parse::<f64>().ok().map(|us| us / 1_000_000.0),
+        _ => None,
+    }
+}
+
+/// Run an ffmpeg command that writes `output`, reporting progress against the input's `duration`.
+/// The partial output is deleted when the conversion is cancelled.
+fn run(mut cmd: Command, output: &Path, duration: Option<f64>, conversion: &ConversionHandle) -> Result<()> {
+    cmd.args(["-progress", "pipe:1", "-nostats"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    tracing::debug!("cmd: {:?}", cmd);
+    let mut child = cmd.spawn()?;
+    // Drained on its own thread, so ffmpeg never blocks on a full stderr pipe.
+    let mut stderr = child.stderr.take().context("ffmpeg stderr")?;
+    let stderr_reader = std::thread::spawn(move || {
+        let mut stderr_output = String::new();
+        stderr.read_to_string(&mut stderr_output).ok();
+        stderr_output
+    });
+
+    // Read on its own thread too, so a cancel is noticed even while ffmpeg prints nothing.
+    let stdout = BufReader::new(child.stdout.take().context("ffmpeg stdout")?);
+    let (lines_tx, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(std::io::Result::ok) {
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let mut reported = None;
+    loop {
+        if conversion.is_cancelled() {
+            child.kill().ok();
+            child.wait().ok();
+            if output.exists() {
+                std::fs::remove_file(output).ok();
+            }
+            return Err(Cancelled.into());
+        }
+        let line = match lines.recv_timeout(CANCEL_CHECK_INTERVAL) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => continue,
+            // ffmpeg closed its output, so it is done.
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let (Some(on_progress), Some(duration)) = (&conversion.on_progress, duration) else {
+            continue;
+        };
+        let Some(seconds) = parse_progress_line(&line) else {
+            continue;
+        };
+        let percent = (seconds / duration * 100.0).clamp(0.0, 100.0).floor();
+        if reported != Some(percent) {
+            reported = Some(percent);
+            on_progress(percent);
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr_output = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        let stderr_output: String = stderr_output.chars().take(1000).collect();
+        bail!("ffmpeg failed: {:?} args: {:?}", stderr_output, cmd.get_args());
+    }
+    if !output.exists() {
+        bail!("seems like ffmpeg failed for some reason. output not exists")
+    }
+    Ok(())
+}
+
+/// Length of `input` in seconds, when someone is waiting for progress against it.
+fn progress_duration(input: &Path, conversion: &ConversionHandle) -> Option<f64> {
+    conversion.on_progress.as_ref()?;
+    crate::probe::probe(input)
+        .map_err(|e| eyre!("{e:?}"))
+        .log_error()?
+        .duration_seconds
+}
+
+/// Convert `input` into the 16 kHz mono WAV Sona reads, decoding natively when ffmpeg is missing.
+pub fn normalize(
+    input: PathBuf,
+    output: PathBuf,
+    additional_ffmpeg_args: Option<Vec<String>>,
+    conversion: &ConversionHandle,
+) -> Result<()> {
+    let Some(ffmpeg_path) = find_ffmpeg_path() else {
+        if additional_ffmpeg_args.is_some_and(|args| !args.is_empty()) {
+            tracing::warn!("ffmpeg not found, decoding without the additional ffmpeg arguments");
+        }
+        return crate::decode::decode_to_wav(&input, &output);
+    };
     tracing::debug!("ffmpeg path is {}", ffmpeg_path.display());
 
     let mut cmd = Command::new(ffmpeg_path);
-    let cmd = cmd.stderr(Stdio::piped()).args([
+    cmd.args([
         "-i",
         input.to_str().context("tostr")?,
         "-ar",
@@ -84,61 +245,181 @@ pub fn normalize(input: PathBuf, output: PathBuf, additional_ffmpeg_args: Option
         "-c:a",
         "pcm_s16le",
     ]);
-
     cmd.args(additional_ffmpeg_args.unwrap_or_default());
-
     cmd.args([output.to_str().context("tostr")?, "-hide_banner", "-y", "-loglevel", "error"]);
 
-    tracing::debug!("cmd: {:?}", cmd);
+    let duration = progress_duration(&input, conversion);
+    run(cmd, &output, duration, conversion).context("unable to convert file")
+}
 
-    let cmd = cmd.stdin(Stdio::null());
+/// Re-encode `input` into `output` with the given codec arguments, keeping its sample rate and channels.
+pub fn encode(input: PathBuf, output: PathBuf, codec_args: &[&str], conversion: &ConversionHandle) -> Result<()> {
+    let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
+
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.args(["-i", input.to_str().context("tostr")?]).args(codec_args).args([
+        output.to_str().context("tostr")?,
+        "-hide_banner",
+        "-y",
+        "-loglevel",
+        "error",
+    ]);
+
+    let duration = progress_duration(&input, conversion);
+    run(cmd, &output, duration, conversion).context("unable to encode file")
+}
+
+/// Decode the first audio stream of `input`, handing its samples as mono at `sample_rate` to
+/// `on_samples` block by block, without writing a file.
+pub fn decode_samples(input: &Path, sample_rate: u32, mut on_samples: impl FnMut(&[f32]) -> Result<()>) -> Result<()> {
+    let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
+
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.args(["-hide_banner", "-loglevel", "error", "-i", input.to_str().context("tostr")?])
+        .args(["-vn", "-ac", "1", "-ar", &sample_rate.to_string(), "-f", "f32le", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
 
     #[cfg(windows)]
-    let cmd = cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd.creation_flags(CREATE_NO_WINDOW);
 
-    let mut pid = cmd.spawn()?;
-    if !pid.wait()?.success() {
+    let mut child = cmd.spawn()?;
+    let mut stderr = child.stderr.take().context("ffmpeg stderr")?;
+    let stderr_reader = std::thread::spawn(move || {
         let mut stderr_output = String::new();
-        if let Some(ref mut stderr) = pid.stderr {
-            stderr.take(1000).read_to_string(&mut stderr_output)?;
+        stderr.read_to_string(&mut stderr_output).ok();
+        stderr_output
+    });
+
+    let mut stdout = child.stdout.take().context("ffmpeg stdout")?;
+    let mut bytes = vec![0u8; 64 * 1024];
+    // A read can end in the middle of a sample; its first bytes wait here for the next read.
+    let mut filled = 0;
+    let mut samples = Vec::with_capacity(bytes.len() / 4);
+    loop {
+        let read = stdout.read(&mut bytes[filled..])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+        let whole = filled - filled % 4;
+        samples.clear();
+        samples.extend(
+            bytes[..whole]
+                .chunks_exact(4)
+                .map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]])),
+        );
+        if let Err(error) = on_samples(&samples) {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(error);
         }
-        bail!("unable to convert file: {:?} args: {:?}", stderr_output, cmd.get_args());
+        bytes.copy_within(whole..filled, 0);
+        filled -= whole;
     }
 
-    if !output.exists() {
-        bail!("seems like ffmpeg failed for some reason. output not exists")
+    let status = child.wait()?;
+    if !status.success() {
+        let stderr_output: String = stderr_reader.join().unwrap_or_default().chars().take(1000).collect();
+        bail!("ffmpeg failed to decode {}: {:?}", input.display(), stderr_output);
     }
     Ok(())
 }
 
-pub fn merge_wav_files(a: PathBuf, b: PathBuf, dst: PathBuf) -> Result<()> {
+/// Whether ffmpeg accepts `filter_chain` as an audio filter, by running it over a moment of silence.
+pub fn check_filter(filter_chain: &str) -> Result<()> {
     let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
-    let output = dst.to_str().context("tostr")?;
 
     let mut cmd = Command::new(ffmpeg_path);
     cmd.args([
-        "-i",
-        a.to_str().context("tostr")?,
-        "-i",
-        b.to_str().context("tostr")?,
-        "-filter_complex",
-        "amix=inputs=2:duration=shortest",
-        "-ac",
-        "2",
-        output,
         "-hide_banner",
-        "-y",
         "-loglevel",
         "error",
+        "-f",
+        "lavfi",
+        "-i",
+        "anullsrc=r=16000:cl=mono",
+        "-t",
+        "0.1",
     ])
-    .stdin(Stdio::null());
+    .args(["-af", filter_chain, "-f", "null", "-"])
+    .stdin(Stdio::null())
+    .stdout(Stdio::null());
 
     #[cfg(windows)]
     cmd.creation_flags(CREATE_NO_WINDOW);
 
-    let mut pid = cmd.spawn()?;
-    if !pid.wait()?.success() {
-        bail!("unable to merge files");
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr_output = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "{}",
+            stderr_output.lines().last().unwrap_or("ffmpeg rejected the filter").trim()
+        );
     }
     Ok(())
 }
+
+/// Run ffmpeg with `args` (inputs, filters and codec) to write `output`, reporting progress against
+/// `duration` seconds of output.
+pub fn convert(args: &[String], output: &Path, duration: Option<f64>, conversion: &ConversionHandle) -> Result<()> {
+    let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
+
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.args(args)
+        .args([output.to_str().context("tostr")?, "-hide_banner", "-y", "-loglevel", "error"]);
+
+    run(cmd, output, duration, conversion).context("unable to convert file")
+}
+
+/// What `ffmpeg -i` prints about `input`: container, duration, streams and metadata.
+pub fn info(input: &Path) -> Result<String> {
+    let ffmpeg_path = find_ffmpeg_path().context("ffmpeg not found")?;
+
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.args(["-hide_banner", "-i", input.to_str().context("tostr")?])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    // Without an output file ffmpeg exits with an error, after printing the input's details.
+    let output = cmd.output()?;
+    Ok(String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_output_time_of_progress_lines() {
+        assert_eq!(parse_progress_line("out_time_us=12500000"), Some(12.5));
+        assert_eq!(parse_progress_line("out_time_ms=1000000"), Some(1.0));
+        assert_eq!(parse_progress_line("out_time=00:00:12.500000"), None);
+        assert_eq!(parse_progress_line("out_time_us=N/A"), None);
+        assert_eq!(parse_progress_line("progress=continue"), None);
+    }
+
+    /// A conversion that prints nothing must still stop soon after it is cancelled.
+    #[cfg(unix)]
+    #[test]
+    fn cancels_a_silent_conversion() {
+        let mut cmd = Command::new("sh");
+        // `run` appends its progress flags, which `sh -c` takes as positional parameters.
+        cmd.args(["-c", "sleep 30"]);
+        let conversion = ConversionHandle::default();
+        let canceller = conversion.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            canceller.cancel();
+        });
+        let started = std::time::Instant::now();
+        let output = std::env::temp_dir().join(format!("vibe-cancel-{}.wav", std::process::id()));
+        let error = run(cmd, &output, None, &conversion).unwrap_err();
+        assert!(is_cancelled(&error));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -2,21 +2,35 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod analytics;
+mod audio_devices;
+mod audio_quality;
+mod captions;
 mod cleaner;
 mod cli;
+mod clips;
 mod cmd;
 mod config;
 mod config_watcher;
+mod decode;
 mod diagnostics;
+mod dictation;
 mod dictation_indicator;
+mod disk_space;
 mod error;
 mod ffmpeg;
 mod handoff;
 mod logging;
+mod model_inventory;
+mod model_manifest;
+mod preprocess;
+mod probe;
+mod recording;
 mod setup;
 mod sona;
+mod text_insertion;
 mod transcript;
 mod tray;
+mod waveform;
 use tauri::Emitter;
 
 #[cfg(target_os = "macos")]
@@ -40,6 +54,9 @@ async fn main() -> Result<()> {
     #[allow(unused_mut)]
     let mut builder = tauri::Builder::default()
         .manage(tray::TrayState::default())
+        .manage(cmd::download::DownloadManager::default())
+        .manage(cmd::conversion::ConversionManager::default())
+        .manage(cmd::audio::RecordingState::default())
         .manage(tokio::sync::Mutex::<Option<handoff::HandoffState>>::new(None))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_http::init())
@@ -103,9 +120,17 @@ async fn main() -> Result<()> {
             cmd::config::get_config_path,
             tray::set_tray,
             cmd::transcribe::transcribe,
+            cmd::transcribe::validate_audio_filter,
+            cmd::transcribe::transcribe_tracks,
             cmd::files::glob_files,
             cmd::files::pick_media_paths,
             cmd::download::download_model,
+            cmd::download::cancel_download,
+            cmd::models::get_model_manifest,
+            cmd::models::list_installed_models,
+            cmd::models::delete_model,
+            cmd::models::rename_model,
+            cmd::models::move_model,
             cmd::sona_cmd::load_model,
             cmd::sona_cmd::get_gpu_devices,
             cmd::sona_cmd::get_model_metadata,
@@ -124,12 +149,24 @@ async fn main() -> Result<()> {
             cmd::files::get_default_recording_path,
             cmd::audio::get_audio_devices,
             cmd::audio::start_record,
+            cmd::audio::pause_record,
+            cmd::audio::resume_record,
+            cmd::audio::list_orphaned_recordings,
+            cmd::audio::recover_recording,
+            cmd::audio::discard_recording,
             cmd::app::get_models_folder,
             cmd::app::get_logs_folder,
             cmd::app::show_log_path,
             cmd::app::show_temp_path,
             cmd::files::get_ffmpeg_path,
+            cmd::files::probe_media,
+            cmd::files::analyze_audio,
+            cmd::files::load_waveform,
+            cmd::files::get_waveform_peaks,
+            cmd::files::export_clips,
+            cmd::conversion::cancel_conversion,
             cmd::ytdlp::download_audio,
+            cmd::ytdlp::list_playlist,
             cmd::ytdlp::get_temp_path,
             cmd::ytdlp::get_latest_ytdlp_version,
             cmd::app::is_crashed_recently,
@@ -140,6 +177,7 @@ async fn main() -> Result<()> {
             dictation_indicator::get_dictation_indicator_enabled,
             dictation_indicator::set_dictation_indicator_enabled,
             dictation_indicator::show_dictation_indicator,
+            dictation::set_dictation_shortcut,
             dictation_indicator::get_dictation_indicator_state,
             dictation_indicator::dictation_indicator_ready,
             dictation_indicator::hide_dictation_indicator
@@ -163,6 +201,7 @@ async fn main() -> Result<()> {
                     process.kill();
                 }
             };
+            app.state::<dictation::DictationState>().kill_sona();
             // Drop the handoff router so the iroh endpoint closes cleanly.
             let handoff = app.state::<tokio::sync::Mutex<Option<handoff::HandoffState>>>();
             if let Ok(mut guard) = handoff.try_lock() {
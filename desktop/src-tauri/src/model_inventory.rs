@@ -0,0 +1,446 @@
+//! What is installed in the models folder, and the bookkeeping Vibe keeps about each file.
+//!
+//! Asking Sona for a model's engine means loading its header, so the answer is cached in the app
+//! data folder together with when the model was last loaded. An entry is trusted only while the
+//! file's size and modification time still match what was recorded. Models moved to another folder
+//! are recorded there too, so they stay listed and manageable.
+
+use crate::config::STORE_FILENAME;
+use crate::model_manifest::ModelManifest;
+use crate::sona::ModelMetadata;
+use eyre::{bail, Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+
+const CACHE_FILENAME: &str = "model-inventory.json";
+/// Anything else in the folder (partial downloads, manifests, the quarantine) is not a model.
+const MODEL_EXTENSIONS: &[&str] = &["bin", "gguf", "onnx"];
+
+/// Keys in `app_config.json` that point at model files (`lib/config-keys.ts`).
+const CONFIG_KEY_MODEL_PATH: &str = "model.path";
+const CONFIG_KEY_MODEL_DISPLAY_NAMES: &str = "model.displayNames";
+const CONFIG_KEY_DICTATION_MODEL_PATH: &str = "dictation.modelPath";
+/// Keys holding the path of a single model, as opposed to maps keyed by path.
+const CONFIG_PATH_KEYS: &[&str] = &[CONFIG_KEY_MODEL_PATH, CONFIG_KEY_DICTATION_MODEL_PATH];
+
+/// Serializes read-modify-write of the cache file between concurrent commands.
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CachedModel {
+    size: u64,
+    modified: Option<u64>,
+    metadata: Option<ModelMetadata>,
+    last_used: Option<u64>,
+    /// Moved out of the models folder by `move_to`.
+    #[serde(default)]
+    moved: bool,
+}
+
+type Cache = BTreeMap<String, CachedModel>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledModel {
+    pub path: String,
+    pub filename: String,
+    pub size: u64,
+    /// Unix seconds.
+    pub modified: Option<u64>,
+    pub last_used: Option<u64>,
+    pub engine: Option<String>,
+    pub format: Option<String>,
+    /// The manifest entry this file was downloaded from, when its name matches one.
+    pub manifest_id: Option<String>,
+    pub is_selected: bool,
+}
+
+fn unix_seconds(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|duration| duration.as_secs())
+}
+
+fn is_model_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| MODEL_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(extension)))
+}
+
+fn is_model_file(path: &Path) -> bool {
+    path.is_file() && is_model_extension(path)
+}
+
+fn cache_path(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?.join(CACHE_FILENAME))
+}
+
+fn read_cache(app_handle: &tauri::AppHandle) -> Cache {
+    cache_path(app_handle)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn update_cache(app_handle: &tauri::AppHandle, update: impl FnOnce(&mut Cache)) -> Result<()> {
+    let _guard = CACHE_LOCK
+        .lock()
+        .map_err(|error| eyre::eyre!("model cache lock poisoned: {error}"))?;
+    let mut cache = read_cache(app_handle);
+    update(&mut cache);
+    let path = cache_path(app_handle)?;
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder).context(format!("Failed to create {}", folder.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&cache)?).context(format!("Failed to write {}", path.display()))
+}
+
+/// The cache entry for `path`, reset when the file on disk changed since it was recorded.
+fn fresh_entry<'a>(cache: &'a mut Cache, path: &Path) -> Result<&'a mut CachedModel> {
+    let metadata = std::fs::metadata(path).context(format!("Failed to read {}", path.display()))?;
+    let size = metadata.len();
+    let modified = metadata.modified().ok().and_then(unix_seconds);
+    let entry = cache.entry(path.to_string_lossy().to_string()).or_default();
+    if entry.size != size || entry.modified != modified {
+        *entry = CachedModel {
+            size,
+            modified,
+            metadata: None,
+            last_used: entry.last_used,
+            moved: entry.moved,
+        };
+    }
+    Ok(entry)
+}
+
+/// Remember what Sona reported for a model, so the inventory can show it without asking again.
+pub fn record_metadata(app_handle: &tauri::AppHandle, path: &Path, metadata: &ModelMetadata) -> Result<()> {
+    let mut result = Ok(());
+    update_cache(app_handle, |cache| match fresh_entry(cache, path) {
+        Ok(entry) => entry.metadata = Some(metadata.clone()),
+        Err(error) => result = Err(error),
+    })?;
+    result
+}
+
+pub fn record_used(app_handle: &tauri::AppHandle, path: &Path) -> Result<()> {
+    let mut result = Ok(());
+    update_cache(app_handle, |cache| match fresh_entry(cache, path) {
+        Ok(entry) => entry.last_used = unix_seconds(SystemTime::now()),
+        Err(error) => result = Err(error),
+    })?;
+    result
+}
+
+fn selected_model(app_handle: &tauri::AppHandle) -> Option<String> {
+    let store = app_handle.store(STORE_FILENAME).ok()?;
+    store.get(CONFIG_KEY_MODEL_PATH)?.as_str().map(str::to_string)
+}
+
+/// Models outside the models folder that `move_to` put there.
+fn moved_paths(cache: &Cache) -> Vec<PathBuf> {
+    cache
+        .iter()
+        .filter(|(_, cached)| cached.moved)
+        .map(|(path, _)| PathBuf::from(path))
+        .collect()
+}
+
+pub fn scan(app_handle: &tauri::AppHandle, folder: &Path) -> Result<Vec<InstalledModel>> {
+    let manifest = crate::model_manifest::load(app_handle);
+    let cache = read_cache(app_handle);
+    let selected = selected_model(app_handle);
+
+    let mut paths = Vec::new();
+    match std::fs::read_dir(folder) {
+        Ok(entries) => {
+            for entry in entries {
+                paths.push(entry?.path());
+            }
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error).context(format!("Failed to read {}", folder.display())),
+    };
+    // A moved model that was deleted outside Vibe simply stops being listed.
+    paths.extend(moved_paths(&cache).into_iter().filter(|path| !paths.contains(path)));
+
+    let mut models = Vec::new();
+    for path in paths {
+        if !is_model_file(&path) {
+            continue;
+        }
+        let metadata = std::fs::metadata(&path)?;
+        let size = metadata.len();
+        let modified = metadata.modified().ok().and_then(unix_seconds);
+        let path_str = path.to_string_lossy().to_string();
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let cached = cache.get(&path_str);
+        let cached_metadata = cached
+            .filter(|cached| cached.size == size && cached.modified == modified)
+            .and_then(|cached| cached.metadata.as_ref());
+        let manifest_entry = find_in_manifest(&manifest, &filename);
+
+        models.push(InstalledModel {
+            is_selected: selected.as_deref() == Some(path_str.as_str()),
+            path: path_str,
+            filename,
+            size,
+            modified,
+            last_used: cached.and_then(|cached| cached.last_used),
+            engine: cached_metadata
+                .map(|metadata| metadata.capabilities.engine.clone())
+                .or_else(|| manifest_entry.map(|entry| entry.engine.clone())),
+            format: cached_metadata.map(|metadata| metadata.format.clone()),
+            manifest_id: manifest_entry.map(|entry| entry.id.clone()),
+        });
+    }
+    models.sort_by(|a, b| a.filename.to_lowercase().cmp(&b.filename.to_lowercase()));
+    Ok(models)
+}
+
+fn find_in_manifest<'a>(manifest: &'a ModelManifest, filename: &str) -> Option<&'a crate::model_manifest::ModelEntry> {
+    manifest.models.iter().find(|entry| entry.filename == filename)
+}
+
+fn resolve(path: &Path) -> Result<PathBuf> {
+    std::fs::canonicalize(path).context(format!("Failed to resolve {}", path.display()))
+}
+
+/// Only model files directly inside the models folder, or moved out of it by `move_to`, can be
+/// touched, so a command can never be pointed at an arbitrary file on disk. Paths are compared
+/// resolved, but callers keep using the path as given: that is the form stored in `model.path`.
+pub fn ensure_managed(app_handle: &tauri::AppHandle, folder: &Path, path: &Path) -> Result<()> {
+    check_managed(folder, &moved_paths(&read_cache(app_handle)), path)
+}
+
+fn check_managed(folder: &Path, moved: &[PathBuf], path: &Path) -> Result<()> {
+    let resolved = resolve(path)?;
+    let in_folder = resolved.parent() == Some(resolve(folder)?.as_path());
+    let was_moved = moved.iter().any(|moved| resolve(moved).is_ok_and(|moved| moved == resolved));
+    if !(in_folder || was_moved) || !is_model_file(&resolved) {
+        bail!("{} is not a model in {}", path.display(), folder.display());
+    }
+    Ok(())
+}
+
+/// The new value of config `key` once the model at `from` moved to `to` (or was deleted, with
+/// `None`): `Some(None)` deletes the key, `None` means it never referenced the model.
+fn rewrite_reference(key: &str, value: &serde_json::Value, from: &str, to: Option<&str>) -> Option<Option<serde_json::Value>> {
+    if CONFIG_PATH_KEYS.contains(&key) {
+        return (value.as_str() == Some(from)).then(|| to.map(|to| serde_json::Value::String(to.to_string())));
+    }
+    if key == CONFIG_KEY_MODEL_DISPLAY_NAMES {
+        let mut names = value.as_object()?.clone();
+        let name = names.remove(from)?;
+        if let Some(to) = to {
+            names.insert(to.to_string(), name);
+        }
+        return Some(Some(serde_json::Value::Object(names)));
+    }
+    None
+}
+
+/// Point every config key that referenced `from` at `to`, or drop it when `to` is `None`.
+fn update_config_references(app_handle: &tauri::AppHandle, from: &Path, to: Option<&Path>) -> Result<()> {
+    let store = app_handle.store(STORE_FILENAME).map_err(|error| eyre::eyre!("{error:?}"))?;
+    let from = from.to_string_lossy().to_string();
+    let to = to.map(|path| path.to_string_lossy().to_string());
+    let mut changed = false;
+
+    for key in CONFIG_PATH_KEYS.iter().chain([&CONFIG_KEY_MODEL_DISPLAY_NAMES]) {
+        let Some(value) = store.get(key) else {
+            continue;
+        };
+        match rewrite_reference(key, &value, &from, to.as_deref()) {
+            Some(Some(value)) => store.set(*key, value),
+            Some(None) => {
+                store.delete(key);
+            }
+            None => continue,
+        }
+        changed = true;
+    }
+
+    if changed {
+        store.save().map_err(|error| eyre::eyre!("{error:?}"))?;
+    }
+    Ok(())
+}
+
+fn move_cached(cache: &mut Cache, from: &Path, to: Option<&Path>) {
+    if let Some(entry) = cache.remove(&from.to_string_lossy().to_string()) {
+        if let Some(to) = to {
+            cache.insert(to.to_string_lossy().to_string(), entry);
+        }
+    }
+}
+
+fn move_cache_entry(app_handle: &tauri::AppHandle, from: &Path, to: Option<&Path>) -> Result<()> {
+    update_cache(app_handle, |cache| move_cached(cache, from, to))
+}
+
+pub fn delete(app_handle: &tauri::AppHandle, path: &Path) -> Result<()> {
+    std::fs::remove_file(path).context(format!("Failed to delete {}", path.display()))?;
+    tracing::debug!("deleted model {}", path.display());
+    move_cache_entry(app_handle, path, None)?;
+    update_config_references(app_handle, path, None)
+}
+
+/// Move a file, falling back to copy + delete when the target is on another volume. A failed copy
+/// leaves nothing behind at the target.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        bail!("{} already exists", to.display());
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(error) = std::fs::copy(from, to) {
+        std::fs::remove_file(to).ok();
+        return Err(error).context(format!("Failed to copy {} to {}", from.display(), to.display()));
+    }
+    std::fs::remove_file(from).context(format!("Failed to remove {}", from.display()))
+}
+
+fn relocate(app_handle: &tauri::AppHandle, from: &Path, to: &Path) -> Result<()> {
+    move_file(from, to)?;
+    tracing::debug!("moved model {} to {}", from.display(), to.display());
+    move_cache_entry(app_handle, from, Some(to))?;
+    update_config_references(app_handle, from, Some(to))
+}
+
+/// Rename within the folder. The extension is kept unless `new_name` brings a model extension of its
+/// own, since Sona picks the loader by it.
+pub fn rename(app_handle: &tauri::AppHandle, path: &Path, new_name: &str) -> Result<PathBuf> {
+    let stem = crate::cmd::files::sanitize_filename_stem(new_name);
+    if stem.is_empty() {
+        bail!("'{}' is not a valid file name", new_name);
+    }
+    let file_name = if is_model_extension(Path::new(&stem)) {
+        stem
+    } else {
+        let extension = path.extension().context("model has no extension")?.to_string_lossy();
+        format!("{stem}.{extension}")
+    };
+    let target = path.with_file_name(file_name);
+    relocate(app_handle, path, &target)?;
+    Ok(target)
+}
+
+/// Move to `folder`, which may be the models folder itself to bring a moved model back.
+pub fn move_to(app_handle: &tauri::AppHandle, path: &Path, folder: &Path, models_folder: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(folder).context(format!("Failed to create {}", folder.display()))?;
+    let target = folder.join(path.file_name().context("model has no file name")?);
+    relocate(app_handle, path, &target)?;
+    let moved = resolve(folder)? != resolve(models_folder)?;
+    let mut result = Ok(());
+    update_cache(app_handle, |cache| match fresh_entry(cache, &target) {
+        Ok(entry) => entry.moved = moved,
+        Err(error) => result = Err(error),
+    })?;
+    result.map(|()| target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let unique = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("vibe-models-{name}-{}-{unique}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn moves_a_model_without_overwriting() {
+        let dir = test_dir("move");
+        let from = dir.join("a.bin");
+        let to = dir.join("b.bin");
+        std::fs::write(&from, b"weights").unwrap();
+
+        move_file(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), b"weights");
+
+        std::fs::write(&from, b"other").unwrap();
+        assert!(move_file(&from, &to).is_err());
+        assert_eq!(std::fs::read(&from).unwrap(), b"other");
+        assert_eq!(std::fs::read(&to).unwrap(), b"weights");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_manages_models_in_the_folder_or_moved_out_of_it() {
+        let dir = test_dir("managed");
+        let (folder, elsewhere) = (dir.join("models"), dir.join("elsewhere"));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::create_dir_all(&elsewhere).unwrap();
+        let inside = folder.join("a.bin");
+        let outside = elsewhere.join("b.bin");
+        let not_a_model = folder.join("notes.txt");
+        for path in [&inside, &outside, &not_a_model] {
+            std::fs::write(path, b"weights").unwrap();
+        }
+
+        assert!(check_managed(&folder, &[], &inside).is_ok());
+        assert!(check_managed(&folder, &[], &not_a_model).is_err());
+        assert!(check_managed(&folder, &[], &outside).is_err());
+        assert!(check_managed(&folder, &[outside.clone()], &outside).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cache_entries_follow_a_move_and_go_with_a_delete() {
+        let mut cache = Cache::new();
+        cache.insert(
+            "/models/a.bin".into(),
+            CachedModel {
+                size: 7,
+                last_used: Some(1),
+                ..Default::default()
+            },
+        );
+
+        move_cached(&mut cache, Path::new("/models/a.bin"), Some(Path::new("/big/a.bin")));
+        assert_eq!(cache.keys().collect::<Vec<_>>(), ["/big/a.bin"]);
+        assert_eq!(cache["/big/a.bin"].last_used, Some(1));
+
+        move_cached(&mut cache, Path::new("/big/a.bin"), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn rewrites_every_key_that_points_at_the_model() {
+        let from = "/models/a.bin";
+        let path = json!(from);
+        let names = json!({ from: "Turbo", "/models/b.bin": "Small" });
+
+        for key in [CONFIG_KEY_MODEL_PATH, CONFIG_KEY_DICTATION_MODEL_PATH] {
+            assert_eq!(
+                rewrite_reference(key, &path, from, Some("/big/a.bin")),
+                Some(Some(json!("/big/a.bin")))
+            );
+            assert_eq!(rewrite_reference(key, &path, from, None), Some(None));
+            assert_eq!(rewrite_reference(key, &json!("/models/b.bin"), from, None), None);
+        }
+        assert_eq!(
+            rewrite_reference(CONFIG_KEY_MODEL_DISPLAY_NAMES, &names, from, Some("/big/a.bin")),
+            Some(Some(json!({ "/big/a.bin": "Turbo", "/models/b.bin": "Small" })))
+        );
+        assert_eq!(
+            rewrite_reference(CONFIG_KEY_MODEL_DISPLAY_NAMES, &names, from, None),
+            Some(Some(json!({ "/models/b.bin": "Small" })))
+        );
+        assert_eq!(rewrite_reference("model.other", &path, from, None), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! The models Vibe knows how to download, and what each download must hash to.
+//!
+//! A copy ships inside the binary so a fresh install works offline. `refresh` pulls the latest
+//! revision from the repository and caches it in the app data folder; the cache wins only while its
+//! revision is newer than the bundled one, so an app update never regresses to a stale download.
+
+use eyre::{bail, Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+const BUNDLED_MANIFEST: &str = include_str!("../model-manifest.json");
+const MANIFEST_URL: &str = "https://raw.githubusercontent.com/thewh1teagle/vibe/main/desktop/src-tauri/model-manifest.json";
+const CACHE_FILENAME: &str = "model-manifest.json";
+/// Mismatched downloads are moved here rather than deleted, so a bad mirror can be investigated.
+const QUARANTINE_FOLDER: &str = "quarantine";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub revision: u32,
+    pub models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+    pub filename: String,
+    pub name: String,
+    /// The Sona engine that runs it (`whisper`, `silero-vad`, ...).
+    pub engine: String,
+    /// ISO 639-1 codes; empty means multilingual.
+    pub languages: Vec<String>,
+    /// Tried in order; the first is the preferred mirror.
+    pub urls: Vec<String>,
+    pub size: Option<u64>,
+    /// Lowercase hex.
+    pub sha256: Option<String>,
+}
+
+/// What a finished download has to match before it is published.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedDigest {
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+}
+
+impl From<&ModelEntry> for ExpectedDigest {
+    fn from(entry: &ModelEntry) -> Self {
+        Self {
+            size: entry.size,
+            sha256: entry.sha256.clone(),
+        }
+    }
+}
+
+/// The downloaded bytes are not the ones the manifest promised.
+#[derive(Debug)]
+pub struct ChecksumMismatch {
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checksum mismatch: expected {}, got {}", self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+impl ModelManifest {
+    pub fn bundled() -> Self {
+        serde_json::from_str(BUNDLED_MANIFEST).expect("bundled model manifest is valid JSON")
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        let manifest: Self = serde_json::from_str(contents).context("model manifest is not valid JSON")?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for model in &self.models {
+            if !ids.insert(model.id.as_str()) {
+                bail!("model manifest lists '{}' twice", model.id);
+            }
+            if model.urls.is_empty() {
+                bail!("model '{}' has no download URL", model.id);
+            }
+            if model.filename.is_empty() || model.filename.contains(['/', '\\']) {
+                bail!("model '{}' has an invalid file name", model.id);
+            }
+            if let Some(ref sha256) = model.sha256 {
+                if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) {
+                    bail!("model '{}' has a malformed sha256", model.id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The entry a URL downloads, so callers that only know the URL still get verified.
+    pub fn find_by_url(&self, url: &str) -> Option<&ModelEntry> {
+        self.models
+            .iter()
+            .find(|model| model.urls.iter().any(|candidate| candidate == url))
+    }
+}
+
+fn cache_path(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?.join(CACHE_FILENAME))
+}
+
+/// The freshest manifest available without touching the network.
+pub fn load(app_handle: &tauri::AppHandle) -> ModelManifest {
+    let bundled = ModelManifest::bundled();
+    let cached = cache_path(app_handle)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| ModelManifest::parse(&contents).ok());
+    match cached {
+        Some(cached) if cached.revision > bundled.revision => cached,
+        _ => bundled,
+    }
+}
+
+/// Fetch the published manifest and cache it. Falls back to what `load` returns when the published
+/// one is older than the bundled copy.
+pub async fn refresh(app_handle: &tauri::AppHandle) -> Result<ModelManifest> {
+    let contents = crate::cmd::download::http_client(app_handle)?
+        .get(MANIFEST_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+        .context("failed to read model manifest")?;
+    let manifest = ModelManifest::parse(&contents)?;
+
+    let path = cache_path(app_handle)?;
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder).context(format!("Failed to create {}", folder.display()))?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, &contents).context(format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path).context(format!("Failed to write {}", path.display()))?;
+    tracing::debug!("model manifest refreshed to revision {}", manifest.revision);
+
+    Ok(load(app_handle))
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).context(format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Check a finished download against the manifest. Size first: it is free and catches truncation
+/// without reading gigabytes. Fails when there is no sha256 to check.
+pub fn verify(path: &Path, expected: &ExpectedDigest) -> Result<()> {
+    if let Some(size) = expected.size {
+        let actual = std::fs::metadata(path)?.len();
+        if actual != size {
+            return Err(ChecksumMismatch {
+                expected: format!("{size} bytes"),
+                actual: format!("{actual} bytes"),
+            }
+            .into());
+        }
+    }
+    // A manifest model without a hash would be installed unchecked; refuse it instead.
+    let Some(ref sha256) = expected.sha256 else {
+        bail!("No checksum to verify {} against", path.display());
+    };
+    let actual = sha256_file(path)?;
+    if !actual.eq_ignore_ascii_case(sha256) {
+        return Err(ChecksumMismatch {
+            expected: sha256.clone(),
+            actual,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Move a rejected download out of the way, keeping only the latest bad copy per model.
+pub fn quarantine(file: &Path, destination: &Path) -> Result<PathBuf> {
+    let folder = destination
+        .parent()
+        .map(|parent| parent.join(QUARANTINE_FOLDER))
+        .context("download destination has no parent folder")?;
+    std::fs::create_dir_all(&folder).context(format!("Failed to create {}", folder.display()))?;
+    let target = folder.join(destination.file_name().context("download destination has no file name")?);
+    if target.exists() {
+        std::fs::remove_file(&target).context(format!("Failed to remove {}", target.display()))?;
+    }
+    std::fs::rename(file, &target).context(format!("Failed to quarantine {}", file.display()))?;
+    tracing::warn!("quarantined {} at {}", file.display(), target.display());
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_manifest_is_valid_and_covers_the_default_model() {
+        let manifest = ModelManifest::parse(BUNDLED_MANIFEST).unwrap();
+        let default = manifest.models.iter().find(|model| model.id == "default").unwrap();
+        assert_eq!(
+            manifest.find_by_url(&default.urls[1]).map(|model| model.id.as_str()),
+            Some("default")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_hashes_and_path_like_file_names() {
+        let entry = |filename: &str, sha256: &str| {
+            format!(
+                r#"{{"revision":1,"models":[{{"id":"x","filename":"{filename}","name":"x","engine":"whisper","languages":[],"urls":["https://example.com/x"],"size":null,"sha256":"{sha256}"}}]}}"#
+            )
+        };
+        let good = "a".repeat(64);
+        assert!(ModelManifest::parse(&entry("x.bin", &good)).is_ok());
+        assert!(ModelManifest::parse(&entry("x.bin", &"A".repeat(64))).is_err());
+        assert!(ModelManifest::parse(&entry("x.bin", "abc")).is_err());
+        assert!(ModelManifest::parse(&entry("../x.bin", &good)).is_err());
+    }
+
+    #[test]
+    fn verify_catches_a_tampered_file() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("vibe-manifest-{}-{unique}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.bin");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let expected = ExpectedDigest {
+            size: Some(3),
+            sha256: Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into()),
+        };
+        verify(&path, &expected).unwrap();
+
+        std::fs::write(&path, b"abd").unwrap();
+        let error = verify(&path, &expected).unwrap_err();
+        assert!(error.downcast_ref::<ChecksumMismatch>().is_some());
+
+        let unhashed = ExpectedDigest {
+            size: Some(3),
+            sha256: None,
+        };
+        let error = verify(&path, &unhashed).unwrap_err();
+        assert!(error.downcast_ref::<ChecksumMismatch>().is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
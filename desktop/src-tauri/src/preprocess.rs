@@ -0,0 +1,351 @@
+//! Filtering audio before transcription, for noisy field recordings and quiet lectures.
+//!
+//! The presets and the custom filter chain from the FFmpeg settings become one `-af` chain, and
+//! the input is converted through it into the WAV handed to Sona. Without ffmpeg nothing is
+//! filtered.
+
+use crate::cmd::conversion::{ConversionKind, ConversionManager};
+use crate::config::STORE_FILENAME;
+use crate::decode::DecodedAudio;
+use eyre::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Listener};
+use tauri_plugin_store::StoreExt;
+
+const CONFIG_KEY: &str = "transcription.ffmpegOptions";
+/// Longer chains are a pasted mistake, not a filter.
+const MAX_CUSTOM_FILTER_LEN: usize = 2000;
+/// Filters that read, write or run files named in their arguments.
+const FORBIDDEN_FILTERS: &[&str] = &[
+    "amovie",
+    "movie",
+    "asendcmd",
+    "sendcmd",
+    "ladspa",
+    "lv2",
+    "ametadata",
+    "metadata",
+];
+/// Options through which other filters write files, such as `ebur128`'s metadata log.
+const FORBIDDEN_OPTIONS: &[&str] = &["file"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioPreset {
+    /// Cut rumble, handling noise and wind below speech.
+    HighPass,
+    /// Cut hiss above speech.
+    LowPass,
+    /// Reduce steady background noise.
+    Denoise,
+    /// Even out speech that drifts between loud and quiet.
+    SpeechEnhance,
+    /// Raise quiet recordings, such as a lecture from the back of the room.
+    VolumeBoost,
+}
+
+impl AudioPreset {
+    /// Presets go into the chain in this order, whatever order they were picked in.
+    const ORDER: [AudioPreset; 5] = [
+        AudioPreset::HighPass,
+        AudioPreset::LowPass,
+        AudioPreset::Denoise,
+        AudioPreset::SpeechEnhance,
+        AudioPreset::VolumeBoost,
+    ];
+
+    fn filter(self, denoise_model: Option<&str>) -> String {
+        match self {
+            AudioPreset::HighPass => "highpass=f=80".to_string(),
+            AudioPreset::LowPass => "lowpass=f=7000".to_string(),
+            AudioPreset::Denoise => match denoise_model {
+                Some(model) => format!("arnndn=m={}", escape_filter_value(model)),
+                None => "afftdn=nf=-25".to_string(),
+            },
+            AudioPreset::SpeechEnhance => "speechnorm=e=12.5:r=0.0001:l=1".to_string(),
+            // The limiter keeps the peaks that were already loud from clipping.
+            AudioPreset::VolumeBoost => "volume=12dB,alimiter=limit=0.9".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct FfmpegOptions {
+    pub normalize_loudness: bool,
+    pub presets: Vec<AudioPreset>,
+    /// RNNoise model (`.rnnn`) for `Denoise`; ffmpeg's FFT denoiser is used without one.
+    pub denoise_model: Option<String>,
+    /// A custom filter chain. The `-af` that settings from older versions start with is accepted.
+    pub custom_command: Option<String>,
+}
+
+impl FfmpegOptions {
+    pub fn from_config(app_handle: &AppHandle) -> Self {
+        app_handle
+            .store(STORE_FILENAME)
+            .ok()
+            .and_then(|store| store.get(CONFIG_KEY))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    /// The `-af` chain for these options, or `None` when nothing is filtered.
+    pub fn filter_chain(&self) -> Option<String> {
+        let denoise_model = self.denoise_model.as_deref().filter(|model| !model.trim().is_empty());
+        let mut filters: Vec<String> = AudioPreset::ORDER
+            .iter()
+            .filter(|preset| self.presets.contains(preset))
+            .map(|preset| preset.filter(denoise_model))
+            .collect();
+        if let Some(custom) = self.custom_command.as_deref().filter(|custom| !custom.trim().is_empty()) {
+            match validate_custom_filter(custom) {
+                Ok(custom) => filters.push(custom),
+                // Saved by a version that checked less; the settings page asks for a valid one.
+                Err(error) => tracing::warn!("skipping the custom audio filter: {:#}", error),
+            }
+        }
+        // Last, so it measures what the other filters left.
+        if self.normalize_loudness {
+            filters.push("loudnorm=I=-16:TP=-1.5:LRA=11".to_string());
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+}
+
+/// Check a custom filter chain and return it without a leading `-af`. Only a single linear chain
+/// of audio filters is accepted; `ffmpeg::check_filter` tells whether ffmpeg understands it.
+pub fn validate_custom_filter(filter: &str) -> Result<String> {
+    let filter = filter.trim();
+    let filter = ["-af", "-filter:a"]
+        .iter()
+        .find_map(|flag| filter.strip_prefix(flag).filter(|rest| rest.starts_with(char::is_whitespace)))
+        .unwrap_or(filter)
+        .trim();
+    let filter = unquote(filter);
+    if filter.is_empty() {
+        bail!("The custom filter is empty");
+    }
+    if filter.len() > MAX_CUSTOM_FILTER_LEN {
+        bail!("The custom filter is longer than {} characters", MAX_CUSTOM_FILTER_LEN);
+    }
+    if filter.starts_with('-') {
+        bail!("The custom filter must be a filter chain such as \"highpass=f=200,afftdn\", not ffmpeg arguments");
+    }
+    if filter.contains(['[', ']', ';']) {
+        bail!("The custom filter must be a single chain, without labels or \";\"");
+    }
+    for part in split_unquoted(filter, ',') {
+        let (name, options) = split_once_unquoted(part, '=').unwrap_or((part, ""));
+        // `afftdn@voice` is an instance of `afftdn` named `voice`.
+        let (name, instance) = match name.trim().split_once('@') {
+            Some((name, instance)) => (name, Some(instance)),
+            None => (name.trim(), None),
+        };
+        if name.is_empty() {
+            bail!("The custom filter has an empty filter");
+        }
+        if !is_identifier(name) || instance.is_some_and(|instance| !is_identifier(instance)) {
+            bail!("\"{}\" is not a filter name", name);
+        }
+        if FORBIDDEN_FILTERS.contains(&name) {
+            bail!("The {} filter can't be used in a custom filter", name);
+        }
+        // Options without a key are values by position, which can't name a file option.
+        for (key, _) in split_unquoted(options, ':')
+            .into_iter()
+            .filter_map(|option| split_once_unquoted(option, '='))
+        {
+            let key = key.trim();
+            if !is_identifier(key) {
+                bail!("\"{}\" is not an option name of {}", key, name);
+            }
+            if FORBIDDEN_OPTIONS.contains(&key) {
+                bail!("The {} option of {} can't be used in a custom filter", key, name);
+            }
+        }
+    }
+    Ok(filter.to_string())
+}
+
+/// Filter, instance and option names are plain words. Quotes or escapes in them could spell a
+/// forbidden name that only ffmpeg would see once it unescapes them.
+fn is_identifier(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `value` split at each `delimiter` that is neither quoted nor escaped, as ffmpeg reads a
+/// filtergraph.
+fn split_unquoted(value: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (index, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '\'' {
+            quoted = !quoted;
+        } else if c == delimiter && !quoted {
+            parts.push(&value[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// `value` split at the first `delimiter` that is neither quoted nor escaped.
+fn split_once_unquoted(value: &str, delimiter: char) -> Option<(&str, &str)> {
+    let first = split_unquoted(value, delimiter)[0];
+    value.get(first.len() + delimiter.len_utf8()..).map(|rest| (first, rest))
+}
+
+/// `value` without one pair of matching quotes around all of it, as a shell would pass it. Quotes
+/// inside, such as around an expression, belong to the filter.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+/// Escape a filter option value, and then the filter for the filtergraph (see "Notes on
+/// filtergraph escaping" in the ffmpeg docs).
+fn escape_filter_value(value: &str) -> String {
+    let mut option = String::new();
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+    let mut graph = String::new();
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            graph.push('\\');
+        }
+        graph.push(c);
+    }
+    graph
+}
+
+/// Convert `input` through `filter_chain` into a temporary WAV for Sona, or `None` when there is
+/// no ffmpeg to apply it with. Stops on `abort_transcribe`.
+pub async fn preprocess_for_sona(app_handle: &AppHandle, input: &Path, filter_chain: String) -> Result<Option<DecodedAudio>> {
+    if crate::decode::needs_native_decoding(app_handle) {
+        tracing::warn!("ffmpeg not found, transcribing without the audio filters");
+        return Ok(None);
+    }
+    tracing::debug!("preprocessing {} with {}", input.display(), filter_chain);
+
+    let preprocessed = DecodedAudio::in_temp_folder();
+    let conversion = ConversionManager::start(app_handle, ConversionKind::Preprocess);
+    let handle = conversion.handle.clone();
+    let listener = app_handle.listen("abort_transcribe", move |_| handle.cancel());
+    let (input, output) = (input.to_path_buf(), preprocessed.path().to_path_buf());
+    let args = vec!["-af".to_string(), filter_chain];
+    let result =
+        tauri::async_runtime::spawn_blocking(move || crate::ffmpeg::normalize(input, output, Some(args), &conversion.handle))
+            .await;
+    app_handle.unlisten(listener);
+    result??;
+    Ok(Some(preprocessed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_chained_in_a_fixed_order() {
+        let options = FfmpegOptions {
+            normalize_loudness: true,
+            presets: vec![AudioPreset::VolumeBoost, AudioPreset::HighPass],
+            denoise_model: None,
+            custom_command: Some("-af afftdn".to_string()),
+        };
+        assert_eq!(
+            options.filter_chain().unwrap(),
+            "highpass=f=80,volume=12dB,alimiter=limit=0.9,afftdn,loudnorm=I=-16:TP=-1.5:LRA=11"
+        );
+        assert_eq!(FfmpegOptions::default().filter_chain(), None);
+    }
+
+    #[test]
+    fn settings_from_older_versions_still_load() {
+        let options: FfmpegOptions = serde_json::from_str(r#"{"normalize_loudness":false,"custom_command":null}"#).unwrap();
+        assert!(options.presets.is_empty());
+        assert_eq!(options.filter_chain(), None);
+    }
+
+    #[test]
+    fn an_invalid_saved_filter_is_skipped() {
+        let options = FfmpegOptions {
+            normalize_loudness: true,
+            custom_command: Some("amovie=secret.wav".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(options.filter_chain().unwrap(), "loudnorm=I=-16:TP=-1.5:LRA=11");
+    }
+
+    #[test]
+    fn custom_filters_must_be_a_single_audio_chain() {
+        assert_eq!(
+            validate_custom_filter(" highpass=f=200,afftdn ").unwrap(),
+            "highpass=f=200,afftdn"
+        );
+        assert_eq!(validate_custom_filter("-af 'loudnorm=I=-16'").unwrap(), "loudnorm=I=-16");
+        assert!(validate_custom_filter("-ss 10").is_err());
+        assert!(validate_custom_filter("[0:a]afftdn[out]").is_err());
+        assert!(validate_custom_filter("afftdn;amovie=secret.wav").is_err());
+        assert!(validate_custom_filter("amovie=secret.wav,amix").is_err());
+        assert!(validate_custom_filter("afftdn,,volume=2").is_err());
+    }
+
+    #[test]
+    fn custom_filters_cannot_reach_files() {
+        assert!(validate_custom_filter("amovie@in=secret.wav").is_err());
+        assert!(validate_custom_filter("ametadata=mode=print:file=out.txt").is_err());
+        assert!(validate_custom_filter("ebur128=metadata=1:file=/tmp/log").is_err());
+        assert_eq!(
+            validate_custom_filter("afftdn@voice=nr=20,volume=2").unwrap(),
+            "afftdn@voice=nr=20,volume=2"
+        );
+    }
+
+    #[test]
+    fn custom_filter_names_cannot_be_quoted_or_escaped() {
+        assert!(validate_custom_filter(r"a\movie=secret.wav").is_err());
+        assert!(validate_custom_filter("'amovie'=secret.wav").is_err());
+        assert!(validate_custom_filter("\"amovie\"=secret.wav,volume=2").is_err());
+        assert!(validate_custom_filter("afftdn@'x'=nr=20").is_err());
+        assert!(validate_custom_filter(r"ebur128=metadata=1:fi\le=/tmp/log").is_err());
+        assert!(validate_custom_filter("ebur128=metadata=1:'file'=/tmp/log").is_err());
+        // Values may still be quoted or escaped.
+        assert_eq!(
+            validate_custom_filter(r"arnndn=m=C\:/models/bd.rnnn").unwrap(),
+            r"arnndn=m=C\:/models/bd.rnnn"
+        );
+    }
+
+    #[test]
+    fn only_quotes_around_the_whole_filter_are_removed() {
+        assert_eq!(validate_custom_filter("\"volume=2\"").unwrap(), "volume=2");
+        assert_eq!(
+            validate_custom_filter("volume='if(lt(t,10),1,2)':eval=frame").unwrap(),
+            "volume='if(lt(t,10),1,2)':eval=frame"
+        );
+        assert_eq!(
+            validate_custom_filter("'volume=2,aecho=0.8:0.9:'1000':0.3'").unwrap(),
+            "volume=2,aecho=0.8:0.9:'1000':0.3"
+        );
+    }
+
+    #[test]
+    fn escapes_model_paths_for_the_filtergraph() {
+        assert_eq!(escape_filter_value("C:/models/bd.rnnn"), r"C\\:/models/bd.rnnn");
+        assert_eq!(escape_filter_value("it's,here"), r"it\\\'s\,here");
+    }
+}
@@ -0,0 +1,465 @@
+//! What a media file holds, asked before transcribing it (`probe_media`).
+//!
+//! ffmpeg reads everything, so its `-i` report is parsed when it is installed; without it
+//! Symphonia probes the audio formats `decode` can read, which cannot see video streams.
+//!
+//! Before transcribing, the audio is also checked for silence (`is_silent`), as a muted recording
+//! otherwise comes back as an empty transcript, or one the model made up.
+
+use eyre::{bail, eyre, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// About -60 dBFS: a muted input or digital silence. Quiet speech still peaks well above it.
+const SILENT_PEAK: f32 = 0.001;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    /// Demuxer name, such as `mov` or `matroska`.
+    pub container: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub audio_streams: Vec<AudioStream>,
+    pub video_streams: Vec<VideoStream>,
+    pub metadata: MediaMetadata,
+    /// No sample rises above `SILENT_PEAK`. Only checked by `probe_in_background`.
+    pub silent: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStream {
+    /// Index among all the streams of the file.
+    pub index: u32,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    /// As ffmpeg names it: `mono`, `stereo`, `5.1(side)`...
+    pub channel_layout: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoStream {
+    pub index: u32,
+    pub codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMetadata {
+    pub title: Option<String>,
+    pub creation_time: Option<String>,
+    /// Every container-level tag, including the two above.
+    pub tags: BTreeMap<String, String>,
+}
+
+impl MediaInfo {
+    /// Why the file cannot be transcribed, when that is already clear from probing it.
+    pub fn unusable_reason(&self) -> Option<String> {
+        if self.audio_streams.is_empty() {
+            return Some(if self.video_streams.is_empty() {
+                "The file has no audio".to_string()
+            } else {
+                "The video has no audio track".to_string()
+            });
+        }
+        if self.duration_seconds.is_some_and(|duration| duration <= 0.0) {
+            return Some("The audio is empty".to_string());
+        }
+        if self.silent {
+            return Some("The audio is silent".to_string());
+        }
+        None
+    }
+}
+
+/// Probe `path` with ffmpeg, or with Symphonia when ffmpeg is missing.
+pub fn probe(path: &Path) -> Result<MediaInfo> {
+    if crate::ffmpeg::find_ffmpeg_path().is_none() {
+        return probe_native(path);
+    }
+    let report = crate::ffmpeg::info(path)?;
+    parse_ffmpeg_info(&report).ok_or_else(|| {
+        let reason = report.lines().last().unwrap_or_default().trim().to_string();
+        eyre!("Unable to read {}: {}", path.display(), reason)
+    })
+}
+
+/// `probe` and the silence check on a blocking thread, as ffmpeg takes a moment to start.
+pub async fn probe_in_background(path: PathBuf) -> Result<MediaInfo> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut info = probe(&path)?;
+        if info.unusable_reason().is_none() {
+            match is_silent(&path) {
+                Ok(silent) => info.silent = silent,
+                Err(error) => tracing::warn!("could not check {} for silence: {:?}", path.display(), error),
+            }
+        }
+        Ok(info)
+    })
+    .await?
+}
+
+/// Whether no sample of `path` rises above `SILENT_PEAK`. Decoding stops at the first one that
+/// does, so only a silent file is read to the end.
+pub fn is_silent(path: &Path) -> Result<bool> {
+    let mut sounding = false;
+    let on_samples = |samples: &[f32]| {
+        if samples.iter().any(|sample| sample.abs() > SILENT_PEAK) {
+            sounding = true;
+            bail!("sound found");
+        }
+        Ok(())
+    };
+    let decoded = if crate::ffmpeg::find_ffmpeg_path().is_some() {
+        crate::ffmpeg::decode_samples(path, crate::recording::chunker::SAMPLE_RATE, on_samples)
+    } else {
+        crate::decode::decode_samples(path, on_samples)
+    };
+    match decoded {
+        Err(_) if sounding => Ok(false),
+        decoded => decoded.map(|()| true),
+    }
+}
+
+fn probe_native(path: &Path) -> Result<MediaInfo> {
+    let file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| eyre!("Unsupported media format without ffmpeg: {}", e))?;
+
+    let mut info = MediaInfo {
+        container: extension.map(str::to_lowercase),
+        ..Default::default()
+    };
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|metadata| metadata.current()) {
+        add_tags(&mut info.metadata, revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        add_tags(&mut info.metadata, revision);
+    }
+
+    let codecs = symphonia::default::get_codecs();
+    for (index, track) in probed.format.tracks().iter().enumerate() {
+        let params = &track.codec_params;
+        let channels = params.channels.map(|channels| channels.count() as u32);
+        let duration = params
+            .n_frames
+            .and_then(|frames| match (params.time_base, params.sample_rate) {
+                (Some(time_base), _) => {
+                    let time = time_base.calc_time(frames);
+                    Some(time.seconds as f64 + time.frac)
+                }
+                (None, Some(sample_rate)) => Some(frames as f64 / f64::from(sample_rate)),
+                (None, None) => None,
+            });
+        info.duration_seconds = match (info.duration_seconds, duration) {
+            (Some(longest), Some(duration)) => Some(longest.max(duration)),
+            (longest, duration) => longest.or(duration),
+        };
+        info.audio_streams.push(AudioStream {
+            index: index as u32,
+            codec: codecs
+                .get_codec(params.codec)
+                .map(|descriptor| descriptor.short_name.to_string()),
+            sample_rate: params.sample_rate,
+            channels,
+            channel_layout: channels.map(|channels| match channels {
+                1 => "mono".to_string(),
+                2 => "stereo".to_string(),
+                channels => format!("{channels} channels"),
+            }),
+        });
+    }
+    Ok(info)
+}
+
+fn add_tags(metadata: &mut MediaMetadata, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => metadata.title = Some(value.clone()),
+            Some(StandardTagKey::Date) => metadata.creation_time = Some(value.clone()),
+            _ => {}
+        }
+        metadata.tags.insert(tag.key.clone(), value);
+    }
+}
+
+/// Parse the report `ffmpeg -i` prints, or `None` when ffmpeg could not open the input.
+fn parse_ffmpeg_info(report: &str) -> Option<MediaInfo> {
+    let mut info = MediaInfo::default();
+    let mut opened = false;
+    // Only the input's own metadata block is kept, not the ones under each stream.
+    let mut in_metadata = false;
+    for line in report.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("Input #0, ") {
+            opened = true;
+            info.container = rest.split([',', ' ']).next().map(str::to_string);
+            continue;
+        }
+        if !opened {
+            continue;
+        }
+        if in_metadata && indent > 2 {
+            if let Some((key, value)) = line.split_once(':') {
+                let (key, value) = (key.trim().to_string(), value.trim().to_string());
+                match key.as_str() {
+                    "title" => info.metadata.title = Some(value.clone()),
+                    "creation_time" => info.metadata.creation_time = Some(value.clone()),
+                    _ => {}
+                }
+                info.metadata.tags.insert(key, value);
+            }
+            continue;
+        }
+        in_metadata = indent == 2 && line == "Metadata:";
+        if let Some(rest) = line.strip_prefix("Duration: ") {
+            info.duration_seconds = rest.split(',').next().and_then(parse_timestamp);
+        } else if let Some(rest) = line.strip_prefix("Stream #0:") {
+            parse_stream(rest, &mut info);
+        }
+    }
+    opened.then_some(info)
+}
+
+/// `01:02:03.45` as seconds; `N/A` for streams of unknown length.
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let mut parts = timestamp.trim().splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// One `Stream #0:1[0x2](und): Audio: aac (LC), 44100 Hz, stereo, fltp` line, after `Stream #0:`.
+fn parse_stream(line: &str, info: &mut MediaInfo) {
+    let index = line
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or_default();
+    let (kind, details) = if let Some((_, details)) = line.split_once(": Audio: ") {
+        ("audio", details)
+    } else if let Some((_, details)) = line.split_once(": Video: ") {
+        ("video", details)
+    } else {
+        return;
+    };
+    let fields = split_fields(details);
+    let codec = fields
+        .first()
+        .and_then(|codec| codec.split_whitespace().next())
+        .map(str::to_string);
+
+    if kind == "audio" {
+        let rate_position = fields.iter().position(|field| field.ends_with(" Hz"));
+        let channel_layout = rate_position
+            .and_then(|position| fields.get(position + 1))
+            .map(|layout| layout.to_string());
+        info.audio_streams.push(AudioStream {
+            index,
+            codec,
+            sample_rate: rate_position.and_then(|position| fields[position].trim_end_matches(" Hz").parse().ok()),
+            channels: channel_layout.as_deref().and_then(layout_channels),
+            channel_layout,
+        });
+    } else {
+        let size = fields.iter().find_map(|field| {
+            let (width, height) = field.split_whitespace().next()?.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        });
+        info.video_streams.push(VideoStream {
+            index,
+            codec,
+            width: size.map(|(width, _)| width),
+            height: size.map(|(_, height)| height),
+        });
+    }
+}
+
+/// Split on the commas that are not inside parentheses, as in `yuv420p(tv, bt709), 1920x1080`.
+fn split_fields(details: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (position, c) in details.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                fields.push(details[start..position].trim());
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(details[start..].trim());
+    fields
+}
+
+/// Channel count of an ffmpeg layout name.
+fn layout_channels(layout: &str) -> Option<u32> {
+    let layout = layout.split('(').next()?;
+    match layout {
+        "mono" => Some(1),
+        "stereo" | "downmix" => Some(2),
+        "quad" => Some(4),
+        "hexagonal" => Some(6),
+        "octagonal" | "cube" => Some(8),
+        _ => {
+            if let Some(count) = layout.strip_suffix(" channels") {
+                return count.parse().ok();
+            }
+            // `5.1` is five full-range channels and one LFE; `3.1.2` adds two height channels.
+            layout.split('.').map(|part| part.parse::<u32>().ok()).sum()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIDEO_REPORT: &str = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'meeting.mp4':
+  Metadata:
+    major_brand     : isom
+    title           : Weekly sync
+    creation_time   : 2024-05-01T09:30:00.000000Z
+  Duration: 01:02:03.50, start: 0.000000, bitrate: 1205 kb/s
+  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(tv, bt709, progressive), 1920x1080 [SAR 1:1 DAR 16:9], 1074 kb/s, 30 fps (default)
+    Metadata:
+      handler_name    : VideoHandler
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, 5.1(side), fltp, 127 kb/s (default)
+    Metadata:
+      handler_name    : SoundHandler
+At least one output file must be specified
+";
+
+    #[test]
+    fn parses_streams_duration_and_metadata() {
+        let info = parse_ffmpeg_info(VIDEO_REPORT).unwrap();
+        assert_eq!(info.container.as_deref(), Some("mov"));
+        assert_eq!(info.duration_seconds, Some(3723.5));
+        assert_eq!(
+            info.video_streams,
+            [VideoStream {
+                index: 0,
+                codec: Some("h264".to_string()),
+                width: Some(1920),
+                height: Some(1080),
+            }]
+        );
+        assert_eq!(
+            info.audio_streams,
+            [AudioStream {
+                index: 1,
+                codec: Some("aac".to_string()),
+                sample_rate: Some(48_000),
+                channels: Some(6),
+                channel_layout: Some("5.1(side)".to_string()),
+            }]
+        );
+        assert_eq!(info.metadata.title.as_deref(), Some("Weekly sync"));
+        assert_eq!(info.metadata.creation_time.as_deref(), Some("2024-05-01T09:30:00.000000Z"));
+        assert!(!info.metadata.tags.contains_key("handler_name"));
+        assert_eq!(info.unusable_reason(), None);
+    }
+
+    #[test]
+    fn reports_what_cannot_be_transcribed() {
+        let silent_video = "Input #0, matroska,webm, from 'screen.webm':
+  Duration: N/A, start: 0.000000, bitrate: N/A
+  Stream #0:0: Video: vp9 (Profile 0), yuv420p(tv), 1280x720, SAR 1:1 DAR 16:9, 30 fps
+";
+        let info = parse_ffmpeg_info(silent_video).unwrap();
+        assert_eq!(info.container.as_deref(), Some("matroska"));
+        assert_eq!(info.duration_seconds, None);
+        assert_eq!(info.unusable_reason().as_deref(), Some("The video has no audio track"));
+
+        assert_eq!(
+            parse_ffmpeg_info("notes.txt: Invalid data found when processing input\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn probes_audio_without_ffmpeg() {
+        let path = std::env::temp_dir().join(format!("vibe-probe-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22_050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..22_050 * 2 * 2 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let info = probe_native(&path).unwrap();
+        assert_eq!(info.container.as_deref(), Some("wav"));
+        assert_eq!(info.duration_seconds, Some(2.0));
+        assert_eq!(info.audio_streams.len(), 1);
+        assert_eq!(info.audio_streams[0].sample_rate, Some(22_050));
+        assert_eq!(info.audio_streams[0].channel_layout.as_deref(), Some("stereo"));
+        assert!(is_silent(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tells_silence_from_quiet_audio() {
+        let path = std::env::temp_dir().join(format!("vibe-probe-quiet-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        // A second of a tone at -70 dBFS, then a moment of one at -40 dBFS.
+        for frame in 0..20_000 {
+            let level = if frame < 16_000 { 10.0 } else { 300.0 };
+            let phase = frame as f32 * 2.0 * std::f32::consts::PI * 440.0 / 16_000.0;
+            writer.write_sample((phase.sin() * level) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert!(!is_silent(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+
+        let silent = MediaInfo {
+            duration_seconds: Some(1.0),
+            audio_streams: vec![AudioStream::default()],
+            silent: true,
+            ..Default::default()
+        };
+        assert_eq!(silent.unusable_reason().as_deref(), Some("The audio is silent"));
+    }
+
+    #[test]
+    fn counts_channels_of_layouts() {
+        assert_eq!(layout_channels("mono"), Some(1));
+        assert_eq!(layout_channels("stereo"), Some(2));
+        assert_eq!(layout_channels("7.1(wide)"), Some(8));
+        assert_eq!(layout_channels("3 channels"), Some(3));
+        assert_eq!(layout_channels("unknown"), None);
+    }
+}
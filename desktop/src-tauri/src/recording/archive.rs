@@ -0,0 +1,60 @@
+//! Compressed formats for the copy of a recording kept in the recordings folder.
+//!
+//! Sona always gets the normalized 16 kHz WAV from the temp folder; only the archived copy is
+//! re-encoded, so the format never affects transcription.
+
+use crate::config::STORE_FILENAME;
+use crate::ffmpeg::ConversionHandle;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri_plugin_store::StoreExt;
+
+pub const CONFIG_KEY_ARCHIVE_FORMAT: &str = "recording.archiveFormat";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    #[default]
+    Wav,
+    Flac,
+    /// Opus in an Ogg container.
+    Opus,
+    /// AAC in an MP4 container.
+    M4a,
+}
+
+impl ArchiveFormat {
+    /// The format chosen in settings, WAV when unset or unknown.
+    pub fn from_config(app_handle: &tauri::AppHandle) -> Self {
+        app_handle
+            .store(STORE_FILENAME)
+            .ok()
+            .and_then(|store| store.get(CONFIG_KEY_ARCHIVE_FORMAT))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Opus => "ogg",
+            Self::M4a => "m4a",
+        }
+    }
+
+    /// Bitrates are for speech: 16 kHz mono is transparent well below music settings.
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Wav => &["-c:a", "pcm_s16le"],
+            Self::Flac => &["-c:a", "flac"],
+            Self::Opus => &["-c:a", "libopus", "-b:a", "32k", "-application", "voip"],
+            Self::M4a => &["-c:a", "aac", "-b:a", "64k"],
+        }
+    }
+}
+
+pub fn encode(input: &Path, output: &Path, format: ArchiveFormat, conversion: &ConversionHandle) -> Result<()> {
+    crate::ffmpeg::encode(input.to_path_buf(), output.to_path_buf(), format.codec_args(), conversion)
+}
@@ -0,0 +1,178 @@
+//! Splits a live 16 kHz mono stream into speech chunks at pauses, so each chunk can be
+//! transcribed on its own while the recording keeps going.
+//!
+//! This is a plain energy gate rather than a VAD model: it only has to find the gaps between
+//! sentences, and it runs on the capture thread's data without loading anything into Sona.
+
+/// Sample rate every chunk is produced at, and the one Sona expects.
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// 30 ms analysis frames.
+const FRAME_LEN: usize = SAMPLE_RATE as usize * 30 / 1000;
+/// RMS above which a frame counts as speech (about -40 dBFS).
+const SPEECH_RMS: f32 = 0.01;
+/// Silence that closes a chunk.
+const MIN_SILENCE_FRAMES: usize = 600 / 30;
+/// Speech shorter than this is a click or a cough, not worth a request.
+const MIN_SPEECH_FRAMES: usize = 300 / 30;
+/// Audio kept before the first speech frame, so the first syllable is not clipped.
+const PRE_ROLL_FRAMES: usize = 300 / 30;
+/// Someone talking without a pause still gets a transcript every so often.
+const MAX_CHUNK_SAMPLES: usize = SAMPLE_RATE as usize * 30;
+
+/// A stretch of speech, positioned by its first sample on the track's clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub start_sample: u64,
+    pub samples: Vec<f32>,
+}
+
+impl Chunk {
+    pub fn start_seconds(&self) -> f64 {
+        self.start_sample as f64 / f64::from(SAMPLE_RATE)
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.samples.len() as f64 / f64::from(SAMPLE_RATE)
+    }
+}
+
+#[derive(Default)]
+pub struct SpeechChunker {
+    /// Samples not yet making up a whole frame.
+    pending: Vec<f32>,
+    /// Samples consumed into frames so far.
+    position: u64,
+    /// Recent silent frames, kept as pre-roll while no chunk is open.
+    pre_roll: Vec<f32>,
+    current: Option<Chunk>,
+    speech_frames: usize,
+    trailing_silence: usize,
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    (frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+impl SpeechChunker {
+    /// Feed samples; returns the chunks that closed.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Chunk> {
+        self.pending.extend_from_slice(samples);
+        let mut closed = Vec::new();
+        let frames = self.pending.len() / FRAME_LEN;
+        let pending = std::mem::take(&mut self.pending);
+        for frame in pending.chunks(FRAME_LEN).take(frames) {
+            if let Some(chunk) = self.push_frame(frame) {
+                closed.push(chunk);
+            }
+        }
+        self.pending = pending[frames * FRAME_LEN..].to_vec();
+        closed
+    }
+
+    fn push_frame(&mut self, frame: &[f32]) -> Option<Chunk> {
+        let frame_start = self.position;
+        self.position += frame.len() as u64;
+        let is_speech = rms(frame) >= SPEECH_RMS;
+
+        let Some(chunk) = self.current.as_mut() else {
+            if is_speech {
+                let pre_roll = std::mem::take(&mut self.pre_roll);
+                let mut samples = pre_roll;
+                let start_sample = frame_start - samples.len() as u64;
+                samples.extend_from_slice(frame);
+                self.current = Some(Chunk { start_sample, samples });
+                self.speech_frames = 1;
+                self.trailing_silence = 0;
+            } else {
+                self.pre_roll.extend_from_slice(frame);
+                let excess = self.pre_roll.len().saturating_sub(PRE_ROLL_FRAMES * FRAME_LEN);
+                self.pre_roll.drain(..excess);
+            }
+            return None;
+        };
+
+        chunk.samples.extend_from_slice(frame);
+        if is_speech {
+            self.speech_frames += 1;
+            self.trailing_silence = 0;
+        } else {
+            self.trailing_silence += 1;
+        }
+        if self.trailing_silence >= MIN_SILENCE_FRAMES || chunk.samples.len() >= MAX_CHUNK_SAMPLES {
+            return self.close();
+        }
+        None
+    }
+
+    /// Close the open chunk, dropping it when it held too little speech.
+    fn close(&mut self) -> Option<Chunk> {
+        let mut chunk = self.current.take()?;
+        let speech_frames = std::mem::take(&mut self.speech_frames);
+        // Keep a little of the trailing silence, drop the rest.
+        let trailing = self.trailing_silence.saturating_sub(PRE_ROLL_FRAMES) * FRAME_LEN;
+        chunk.samples.truncate(chunk.samples.len().saturating_sub(trailing));
+        self.trailing_silence = 0;
+        (speech_frames >= MIN_SPEECH_FRAMES).then_some(chunk)
+    }
+
+    /// The chunk still being spoken, for a provisional transcript.
+    pub fn open_chunk(&self) -> Option<&Chunk> {
+        self.current.as_ref()
+    }
+
+    /// End of stream: whatever is open becomes the last chunk.
+    pub fn finish(&mut self) -> Option<Chunk> {
+        let tail = std::mem::take(&mut self.pending);
+        if let Some(chunk) = self.current.as_mut() {
+            chunk.samples.extend_from_slice(&tail);
+        }
+        self.close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: f32) -> Vec<f32> {
+        let len = (seconds * SAMPLE_RATE as f32) as usize;
+        (0..len).map(|i| (i as f32 * 0.05).sin() * 0.3).collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<f32> {
+        vec![0.0; (seconds * SAMPLE_RATE as f32) as usize]
+    }
+
+    #[test]
+    fn splits_speech_at_pauses_and_positions_each_chunk() {
+        let mut chunker = SpeechChunker::default();
+        let mut chunks = Vec::new();
+        for part in [silence(1.0), tone(2.0), silence(1.0), tone(1.0), silence(1.0)] {
+            chunks.extend(chunker.push(&part));
+        }
+        assert!(chunker.finish().is_none());
+        assert_eq!(chunks.len(), 2);
+        // The pre-roll starts the first chunk a little before the speech does.
+        let first = chunks[0].start_seconds();
+        assert!((0.65..1.0).contains(&first), "{first}");
+        assert!((3.65..4.0).contains(&chunks[1].start_seconds()));
+        assert!(chunks[0].duration_seconds() < 3.0);
+    }
+
+    #[test]
+    fn clicks_are_dropped_and_monologues_are_cut() {
+        let mut chunker = SpeechChunker::default();
+        assert!(chunker.push(&tone(0.1)).is_empty());
+        assert!(chunker.push(&silence(1.0)).is_empty());
+
+        let chunks = chunker.push(&tone(65.0));
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.samples.len() <= MAX_CHUNK_SAMPLES));
+        assert!(chunker.open_chunk().is_some());
+        assert!(chunker.finish().is_some());
+    }
+}
@@ -0,0 +1,429 @@
+//! Live transcription of a recording in progress.
+//!
+//! The capture callbacks hand their buffers to a chunker thread, which resamples each track to
+//! 16 kHz mono and cuts it into speech chunks at pauses. Unless the tracks are told apart, they are
+//! mixed first, so a voice both the microphone and the loopback pick up is transcribed once.
+//! Chunks are transcribed one at a time by Sona, in order, and every result is emitted as
+//! `live_segment`. While someone is still talking, the open chunk is transcribed now and then as a
+//! provisional result that the final one for the same `chunkId` replaces. When the recording stops,
+//! the last chunk is flushed and the whole transcript is emitted as `live_transcript_finished`.
+
+use super::chunker::{Chunk, SpeechChunker, SAMPLE_RATE};
+use super::resample::{downmix, LinearResampler};
+use crate::error::LogError;
+use crate::ffmpeg::{get_vibe_temp_folder, random_string};
+use crate::sona::SonaEvent;
+use crate::transcript::{Segment, Transcript};
+use cpal::{FromSample, Sample};
+use eyre::{bail, Result};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+/// Buffers queued between the audio callbacks and the chunker before new ones are dropped.
+const FEED_CAPACITY: usize = 256;
+/// How much more of an open chunk has to be spoken before it is transcribed provisionally again.
+const PROVISIONAL_EVERY_SAMPLES: usize = SAMPLE_RATE as usize * 3;
+/// How far one track may run ahead of the others before they are taken as silent. WASAPI loopback
+/// delivers nothing while the system is quiet, and must not hold the microphone back.
+const MAX_MIX_LAG_SAMPLES: usize = SAMPLE_RATE as usize / 2;
+
+/// The subset of transcription options that make sense for short live chunks.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LiveTranscriptionOptions {
+    pub lang: Option<String>,
+    pub translate: Option<bool>,
+    pub init_prompt: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveSegmentPayload {
+    chunk_id: u64,
+    /// Index of the device in the `devices` passed to `start_record`; always 0 when they are mixed.
+    track: usize,
+    is_final: bool,
+    segments: Vec<Segment>,
+}
+
+struct LiveAudio {
+    track: usize,
+    speaker: Option<i32>,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+struct Job {
+    chunk_id: u64,
+    track: usize,
+    speaker: Option<i32>,
+    chunk: Chunk,
+    is_final: bool,
+}
+
+/// Handed to one capture stream; cheap to call from the audio callback.
+#[derive(Clone)]
+pub struct LiveTap {
+    track: usize,
+    speaker: Option<i32>,
+    sample_rate: u32,
+    channels: usize,
+    sender: SyncSender<LiveAudio>,
+}
+
+impl LiveTap {
+    pub fn feed<T>(&self, data: &[T])
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let samples = downmix(data.iter().map(|&sample| f32::from_sample(sample)), self.channels);
+        // Never block the audio thread: if transcription fell that far behind, lose a buffer.
+        let _ = self.sender.try_send(LiveAudio {
+            track: self.track,
+            speaker: self.speaker,
+            sample_rate: self.sample_rate,
+            samples,
+        });
+    }
+}
+
+pub struct LiveTranscriber {
+    sender: SyncSender<LiveAudio>,
+    /// Taps handed out so far, which the mixer waits for.
+    track_count: Arc<AtomicUsize>,
+}
+
+impl LiveTranscriber {
+    /// Start the chunker thread and the transcription task. They run until every tap is dropped,
+    /// which happens when the recording's streams are. With `mix_tracks`, all taps are transcribed
+    /// as one track.
+    pub fn start(
+        app_handle: AppHandle,
+        client: reqwest::Client,
+        base_url: String,
+        options: LiveTranscriptionOptions,
+        mix_tracks: bool,
+    ) -> Self {
+        let (sender, receiver) = sync_channel(FEED_CAPACITY);
+        let (job_sender, job_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let busy = Arc::new(AtomicBool::new(false));
+
+        let track_count = Arc::new(AtomicUsize::new(0));
+
+        let busy_c = busy.clone();
+        let mixer = mix_tracks.then(|| TrackMixer::new(track_count.clone()));
+        std::thread::spawn(move || run_chunker(receiver, job_sender, busy_c, mixer));
+        tauri::async_runtime::spawn(run_transcriber(app_handle, client, base_url, options, job_receiver, busy));
+
+        Self { sender, track_count }
+    }
+
+    /// A tap for the next capture stream, in the order the devices were given. Segments from it
+    /// are labelled with `speaker` when the tracks are told apart.
+    pub fn tap(&mut self, sample_rate: u32, channels: u16, speaker: Option<i32>) -> LiveTap {
+        let track = self.track_count.fetch_add(1, Ordering::Relaxed);
+        LiveTap {
+            track,
+            speaker,
+            sample_rate,
+            channels: channels.into(),
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+/// Lines the tracks up at 16 kHz and averages them, the way the recorded file is mixed.
+struct TrackMixer {
+    track_count: Arc<AtomicUsize>,
+    pending: Vec<VecDeque<f32>>,
+}
+
+impl TrackMixer {
+    fn new(track_count: Arc<AtomicUsize>) -> Self {
+        Self {
+            track_count,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Mixed audio that every track has reached, or that one track is too far ahead to wait for.
+    fn push(&mut self, track: usize, samples: &[f32]) -> Vec<f32> {
+        let tracks = self.track_count.load(Ordering::Relaxed).max(track + 1);
+        if self.pending.len() < tracks {
+            self.pending.resize_with(tracks, VecDeque::new);
+        }
+        self.pending[track].extend(samples);
+        let shortest = self.pending.iter().map(VecDeque::len).min().unwrap_or(0);
+        let longest = self.pending.iter().map(VecDeque::len).max().unwrap_or(0);
+        self.drain(shortest.max(longest.saturating_sub(MAX_MIX_LAG_SAMPLES)))
+    }
+
+    /// Whatever is left once the recording stopped.
+    fn finish(&mut self) -> Vec<f32> {
+        let longest = self.pending.iter().map(VecDeque::len).max().unwrap_or(0);
+        self.drain(longest)
+    }
+
+    fn drain(&mut self, count: usize) -> Vec<f32> {
+        let gain = 1.0 / self.pending.len().max(1) as f32;
+        (0..count)
+            .map(|_| {
+                let mixed: f32 = self.pending.iter_mut().map(|track| track.pop_front().unwrap_or(0.0)).sum();
+                mixed * gain
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct TrackState {
+    speaker: Option<i32>,
+    chunker: SpeechChunker,
+    open_id: Option<u64>,
+    provisional_len: usize,
+}
+
+fn run_chunker(
+    receiver: Receiver<LiveAudio>,
+    jobs: tokio::sync::mpsc::UnboundedSender<Job>,
+    busy: Arc<AtomicBool>,
+    mut mixer: Option<TrackMixer>,
+) {
+    let mut resamplers: HashMap<usize, LinearResampler> = HashMap::new();
+    let mut tracks: HashMap<usize, TrackState> = HashMap::new();
+    let mut next_id = 0u64;
+
+    // Ends when the last tap is dropped.
+    for audio in receiver {
+        let samples = resamplers
+            .entry(audio.track)
+            .or_insert_with(|| LinearResampler::new(audio.sample_rate, SAMPLE_RATE))
+            .process(&audio.samples);
+        let (track, samples) = match mixer.as_mut() {
+            Some(mixer) => (0, mixer.push(audio.track, &samples)),
+            None => (audio.track, samples),
+        };
+        let state = tracks.entry(track).or_insert_with(|| TrackState {
+            speaker: audio.speaker,
+            ..Default::default()
+        });
+        if !chunk_track(state, track, &samples, &jobs, &busy, &mut next_id) {
+            return;
+        }
+    }
+
+    if let Some(mut mixer) = mixer {
+        let state = tracks.entry(0).or_default();
+        if !chunk_track(state, 0, &mixer.finish(), &jobs, &busy, &mut next_id) {
+            return;
+        }
+    }
+    for (track, mut state) in tracks {
+        if let Some(chunk) = state.chunker.finish() {
+            let chunk_id = state.open_id.take().unwrap_or_else(|| {
+                next_id += 1;
+                next_id
+            });
+            jobs.send(Job {
+                chunk_id,
+                track,
+                speaker: state.speaker,
+                chunk,
+                is_final: true,
+            })
+            .log_error();
+        }
+    }
+}
+
+/// Cut one track's 16 kHz audio into chunks and queue them. False once the transcriber is gone.
+fn chunk_track(
+    state: &mut TrackState,
+    track: usize,
+    samples: &[f32],
+    jobs: &tokio::sync::mpsc::UnboundedSender<Job>,
+    busy: &AtomicBool,
+    next_id: &mut u64,
+) -> bool {
+    let mut new_id = || {
+        *next_id += 1;
+        *next_id
+    };
+
+    for chunk in state.chunker.push(samples) {
+        let chunk_id = state.open_id.take().unwrap_or_else(&mut new_id);
+        let job = Job {
+            chunk_id,
+            track,
+            speaker: state.speaker,
+            chunk,
+            is_final: true,
+        };
+        if jobs.send(job).is_err() {
+            return false;
+        }
+    }
+
+    if let Some(open) = state.chunker.open_chunk() {
+        let chunk_id = *state.open_id.get_or_insert_with(|| {
+            state.provisional_len = 0;
+            new_id()
+        });
+        // Only when Sona is idle: a provisional result that arrives after the final one is useless.
+        if open.samples.len() >= state.provisional_len + PROVISIONAL_EVERY_SAMPLES && !busy.load(Ordering::Relaxed) {
+            state.provisional_len = open.samples.len();
+            let job = Job {
+                chunk_id,
+                track,
+                speaker: state.speaker,
+                chunk: open.clone(),
+                is_final: false,
+            };
+            if jobs.send(job).is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+async fn run_transcriber(
+    app_handle: AppHandle,
+    client: reqwest::Client,
+    base_url: String,
+    options: LiveTranscriptionOptions,
+    mut jobs: tokio::sync::mpsc::UnboundedReceiver<Job>,
+    busy: Arc<AtomicBool>,
+) {
+    let started = Instant::now();
+    let mut transcript = Vec::new();
+
+    while let Some(job) = jobs.recv().await {
+        busy.store(true, Ordering::Relaxed);
+        let result = transcribe_chunk(&client, &base_url, &options, &job.chunk).await;
+        busy.store(false, Ordering::Relaxed);
+
+        match result {
+            Ok(mut segments) => {
+                if let Some(speaker) = job.speaker {
+                    for segment in &mut segments {
+                        segment.speaker = Some(speaker);
+                    }
+                }
+                if job.is_final {
+                    transcript.extend(segments.iter().cloned());
+                }
+                let payload = LiveSegmentPayload {
+                    chunk_id: job.chunk_id,
+                    track: job.track,
+                    is_final: job.is_final,
+                    segments,
+                };
+                app_handle.emit("live_segment", payload).log_error();
+            }
+            Err(error) => {
+                tracing::error!("live transcription of chunk {} failed: {:?}", job.chunk_id, error);
+                if job.is_final {
+                    app_handle.emit("live_transcription_error", format!("{error:#}")).log_error();
+                }
+            }
+        }
+    }
+
+    transcript.sort_by_key(|segment: &Segment| segment.start);
+    let transcript = Transcript {
+        processing_time_sec: started.elapsed().as_secs(),
+        segments: transcript,
+    };
+    app_handle.emit("live_transcript_finished", transcript).log_error();
+}
+
+/// Segments of one chunk, on the recording's clock.
+async fn transcribe_chunk(
+    client: &reqwest::Client,
+    base_url: &str,
+    options: &LiveTranscriptionOptions,
+    chunk: &Chunk,
+) -> Result<Vec<Segment>> {
+    let path = get_vibe_temp_folder().join(format!("live-{}.wav", random_string(10)));
+    super::normalize::write_wav(&path, &chunk.samples)?;
+
+    let options = crate::cmd::TranscribeOptions {
+        path: path.to_string_lossy().to_string(),
+        lang: options.lang.clone(),
+        verbose: None,
+        n_threads: None,
+        init_prompt: options.init_prompt.clone(),
+        temperature: None,
+        translate: options.translate,
+        max_text_ctx: None,
+        word_timestamps: None,
+        max_sentence_len: None,
+        sampling_strategy: None,
+        best_of: None,
+        beam_size: None,
+        diarize_model: None,
+        stable_timestamps: None,
+        vad_model: None,
+    };
+    let result = collect_segments(client, base_url, &options, chunk.start_seconds()).await;
+    std::fs::remove_file(&path).log_error();
+    result
+}
+
+async fn collect_segments(
+    client: &reqwest::Client,
+    base_url: &str,
+    options: &crate::cmd::TranscribeOptions,
+    offset_seconds: f64,
+) -> Result<Vec<Segment>> {
+    let stream = crate::sona::SonaProcess::transcribe_stream(client, base_url, options).await?;
+    tokio::pin!(stream);
+
+    let mut segments = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            SonaEvent::Segment {
+                start,
+                end,
+                text,
+                speaker,
+            } => segments.push(Segment {
+                start: ((offset_seconds + start) * 100.0) as i64,
+                stop: ((offset_seconds + end) * 100.0) as i64,
+                text,
+                speaker,
+            }),
+            SonaEvent::Error { message, .. } => bail!("sona: {}", message),
+            SonaEvent::Progress { .. } | SonaEvent::Result { .. } => {}
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixes_tracks_once_every_one_arrived() {
+        let mut mixer = TrackMixer::new(Arc::new(AtomicUsize::new(2)));
+        assert!(mixer.push(0, &[0.4, 0.4]).is_empty());
+        assert_eq!(mixer.push(1, &[0.2, 0.2, 0.2]), [0.3, 0.3]);
+        assert_eq!(mixer.finish(), [0.1]);
+    }
+
+    #[test]
+    fn does_not_wait_for_a_silent_track() {
+        let mut mixer = TrackMixer::new(Arc::new(AtomicUsize::new(2)));
+        let mixed = mixer.push(0, &vec![0.5; MAX_MIX_LAG_SAMPLES + 10]);
+        assert_eq!(mixed, [0.25; 10]);
+    }
+}
@@ -0,0 +1,9 @@
+//! Processing that runs alongside a recording started by `start_record`.
+
+pub mod archive;
+pub mod chunker;
+pub mod live;
+pub mod normalize;
+pub mod recovery;
+pub mod resample;
+pub mod voice_activity;
@@ -0,0 +1,201 @@
+//! Turns the WAVs `start_record` writes into the 16 kHz mono PCM Sona reads, without ffmpeg.
+//!
+//! Every track is downmixed, low-passed and resampled on its own, then the tracks are averaged
+//! into one file that ends with the shortest of them, as ffmpeg's `amix` did before. Tracks are
+//! streamed in blocks, so an hour-long meeting never sits in memory.
+
+use super::chunker::SAMPLE_RATE;
+use super::resample::{downmix, LinearResampler, LowPass};
+use cpal::Sample;
+use eyre::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// Frames read from a track at a time.
+const BLOCK_FRAMES: usize = 4096;
+
+enum Samples {
+    /// Integer PCM of any width, scaled to -1..1 by the factor.
+    Int(hound::WavIntoSamples<BufReader<File>, i32>, f32),
+    Float(hound::WavIntoSamples<BufReader<File>, f32>),
+}
+
+impl Samples {
+    fn next(&mut self) -> Option<Result<f32>> {
+        match self {
+            Self::Int(samples, scale) => samples.next().map(|sample| Ok(sample? as f32 * *scale)),
+            Self::Float(samples) => samples.next().map(|sample| Ok(sample?)),
+        }
+    }
+}
+
+/// One track, converted block by block into 16 kHz mono.
+struct Track {
+    samples: Samples,
+    channels: usize,
+    low_pass: Option<LowPass>,
+    resampler: LinearResampler,
+    buffer: VecDeque<f32>,
+    done: bool,
+}
+
+impl Track {
+    fn open(path: &Path) -> Result<Self> {
+        let reader = hound::WavReader::open(path).context(format!("Failed to open {}", path.display()))?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Int => Samples::Int(reader.into_samples(), 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32),
+            hound::SampleFormat::Float => Samples::Float(reader.into_samples()),
+        };
+        Ok(Self {
+            samples,
+            channels: spec.channels.into(),
+            low_pass: LowPass::for_resampling(spec.sample_rate, SAMPLE_RATE),
+            resampler: LinearResampler::new(spec.sample_rate, SAMPLE_RATE),
+            buffer: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Convert the next block into `buffer`.
+    fn fill(&mut self) -> Result<()> {
+        let mut block = Vec::with_capacity(BLOCK_FRAMES * self.channels);
+        while block.len() < BLOCK_FRAMES * self.channels {
+            match self.samples.next() {
+                Some(sample) => block.push(sample?),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        let mut mono = downmix(block, self.channels);
+        if let Some(low_pass) = self.low_pass.as_mut() {
+            mono = low_pass.process(&mono);
+        }
+        self.buffer.extend(self.resampler.process(&mono));
+        Ok(())
+    }
+}
+
+/// Create a 16 kHz mono 16-bit WAV, the format Sona reads.
+pub fn create_wav(path: &Path) -> Result<hound::WavWriter<BufWriter<File>>> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    hound::WavWriter::create(path, spec).context(format!("Failed to create {}", path.display()))
+}
+
+/// Whether `path` is already a WAV in the format `create_wav` writes.
+pub fn is_sona_wav(path: &Path) -> bool {
+    hound::WavReader::open(path).is_ok_and(|reader| {
+        let spec = reader.spec();
+        spec.channels == 1
+            && spec.sample_rate == SAMPLE_RATE
+            && spec.bits_per_sample == 16
+            && spec.sample_format == hound::SampleFormat::Int
+    })
+}
+
+/// Mix `inputs` into one 16 kHz mono 16-bit WAV at `output`.
+pub fn normalize(inputs: &[&Path], output: &Path) -> Result<()> {
+    if inputs.is_empty() {
+        bail!("Nothing to normalize");
+    }
+    let mut tracks = inputs.iter().map(|input| Track::open(input)).collect::<Result<Vec<_>>>()?;
+    let mut writer = create_wav(output)?;
+    let gain = 1.0 / tracks.len() as f32;
+
+    loop {
+        for track in &mut tracks {
+            while track.buffer.len() < BLOCK_FRAMES && !track.done {
+                track.fill()?;
+            }
+        }
+        // Zero once the shortest track is used up.
+        let ready = tracks.iter().map(|track| track.buffer.len()).min().unwrap_or(0);
+        if ready == 0 {
+            break;
+        }
+        for _ in 0..ready {
+            let mixed: f32 = tracks.iter_mut().filter_map(|track| track.buffer.pop_front()).sum();
+            writer.write_sample(i16::from_sample((mixed * gain).clamp(-1.0, 1.0)))?;
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Write 16 kHz mono samples as the 16-bit WAV Sona reads.
+pub fn write_wav(path: &Path, samples: &[f32]) -> Result<()> {
+    let mut writer = create_wav(path)?;
+    for &sample in samples {
+        writer.write_sample(i16::from_sample(sample.clamp(-1.0, 1.0)))?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tone<S: hound::Sample + Copy>(path: &Path, spec: hound::WavSpec, seconds: f32, sample: impl Fn(f32) -> S) {
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        let frames = (seconds * spec.sample_rate as f32) as usize;
+        for frame in 0..frames {
+            let value = (frame as f32 * 2.0 * std::f32::consts::PI * 440.0 / spec.sample_rate as f32).sin() * 0.5;
+            for _ in 0..spec.channels {
+                writer.write_sample(sample(value)).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn mixes_tracks_of_any_format_into_16khz_mono() {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let (microphone, system, output) = (
+            dir.join(format!("vibe-mic-{id}.wav")),
+            dir.join(format!("vibe-system-{id}.wav")),
+            dir.join(format!("vibe-normalized-{id}.wav")),
+        );
+        let stereo_int = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mono_float = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44_100,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        write_tone(&microphone, stereo_int, 2.0, |value| (value * i16::MAX as f32) as i16);
+        write_tone(&system, mono_float, 1.5, |value| value);
+
+        normalize(&[&microphone, &system], &output).unwrap();
+        let mut reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().sample_rate, SAMPLE_RATE);
+        assert_eq!(reader.spec().channels, 1);
+        // The shortest track decides the length.
+        assert!((23_900..=24_000).contains(&reader.len()), "{}", reader.len());
+        let peak = reader
+            .samples::<i16>()
+            .map(|sample| sample.unwrap().unsigned_abs())
+            .max()
+            .unwrap();
+        assert!((14_000..=17_000).contains(&peak), "{peak}");
+
+        for path in [microphone, system, output] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
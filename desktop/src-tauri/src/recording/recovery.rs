@@ -0,0 +1,198 @@
+//! Recovery of recordings cut short by a crash.
+//!
+//! `start_record` writes a manifest next to its raw WAV files and removes it once the recording is
+//! finished, so a manifest left from an earlier run marks a recording that never stopped. Its WAV
+//! headers only cover the audio up to their last flush; `repair_wav` rewrites them from the file
+//! sizes, and the tracks are then finished like a normal stop. The cleaner keeps any temp folder
+//! that still holds a manifest.
+
+use super::archive::ArchiveFormat;
+use crate::cmd::audio::TrackSource;
+use crate::ffmpeg::get_vibe_temp_folder;
+use eyre::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MANIFEST_SUFFIX: &str = ".recording.json";
+
+/// How to finish a recording, and which files hold it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingManifest {
+    pub id: String,
+    /// The process recording it; a manifest from any other process is orphaned.
+    pub pid: u32,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub recording_name: Option<String>,
+    pub store_in_documents: bool,
+    pub custom_path: Option<String>,
+    pub separate_tracks: bool,
+    /// Missing from manifests written before archive formats existed.
+    #[serde(default)]
+    pub archive_format: ArchiveFormat,
+    pub tracks: Vec<ManifestTrack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestTrack {
+    pub path: PathBuf,
+    pub source: TrackSource,
+}
+
+impl RecordingManifest {
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}{}", self.id, MANIFEST_SUFFIX))
+    }
+
+    /// Write the manifest into the folder of its first track and return its path.
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = self
+            .tracks
+            .first()
+            .and_then(|track| track.path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(get_vibe_temp_folder);
+        let path = self.path(&dir);
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Every `vibe_temp*` folder, including the current one.
+fn temp_folders() -> Result<Vec<PathBuf>> {
+    let pattern = std::env::temp_dir().join("vibe_temp*");
+    let pattern = pattern.to_str().unwrap_or_default();
+    Ok(glob::glob(pattern)?
+        .filter_map(|path| path.ok())
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+fn manifests_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(MANIFEST_SUFFIX))
+        .collect()
+}
+
+/// Whether `dir` still holds a recording in progress or waiting to be recovered.
+pub fn has_manifest(dir: &Path) -> bool {
+    !manifests_in(dir).is_empty()
+}
+
+/// Recordings left behind by earlier runs, oldest first, with the path of their manifest.
+pub fn find_orphans() -> Result<Vec<(PathBuf, RecordingManifest)>> {
+    let mut orphans = Vec::new();
+    for dir in temp_folders()? {
+        for path in manifests_in(&dir) {
+            let manifest = std::fs::read_to_string(&path)
+                .context(format!("Failed to read {}", path.display()))
+                .and_then(|json| serde_json::from_str::<RecordingManifest>(&json).context("Invalid recording manifest"));
+            match manifest {
+                Ok(manifest) if manifest.pid != std::process::id() => orphans.push((path, manifest)),
+                Ok(_) => {}
+                Err(error) => tracing::warn!("skipping {}: {:?}", path.display(), error),
+            }
+        }
+    }
+    orphans.sort_by_key(|(_, manifest)| manifest.started_at);
+    Ok(orphans)
+}
+
+/// Rewrite the RIFF and data sizes of a WAV file from its length on disk, dropping a trailing
+/// partial frame. Returns the number of samples it holds.
+pub fn repair_wav(path: &Path) -> Result<u32> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .context(format!("Failed to open {}", path.display()))?;
+    let len = file.metadata()?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        bail!("{} is not a WAV file", path.display());
+    }
+
+    let mut block_align = 0u64;
+    let mut bytes_per_sample = 0u64;
+    let mut offset = 12u64;
+    while offset + 8 <= len {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let size = u64::from(u32::from_le_bytes([header[4], header[5], header[6], header[7]]));
+
+        match &header[0..4] {
+            b"fmt " => {
+                let mut fmt = [0u8; 16];
+                file.read_exact(&mut fmt)?;
+                block_align = u64::from(u16::from_le_bytes([fmt[12], fmt[13]]));
+                bytes_per_sample = u64::from(u16::from_le_bytes([fmt[14], fmt[15]])).div_ceil(8);
+            }
+            b"data" => {
+                if block_align == 0 || bytes_per_sample == 0 {
+                    bail!("{} has no format before its data", path.display());
+                }
+                let data_start = offset + 8;
+                let data_len =
+                    ((len - data_start) / block_align * block_align).min(u64::from(u32::MAX) / block_align * block_align);
+                file.set_len(data_start + data_len)?;
+                file.seek(SeekFrom::Start(offset + 4))?;
+                file.write_all(&(data_len as u32).to_le_bytes())?;
+                file.seek(SeekFrom::Start(4))?;
+                file.write_all(&((data_start + data_len - 8) as u32).to_le_bytes())?;
+                file.flush()?;
+                return Ok((data_len / bytes_per_sample) as u32);
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        offset += 8 + size + (size & 1);
+    }
+    bail!("{} has no data chunk", path.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repairs_a_header_that_was_never_finalized() {
+        let path = std::env::temp_dir().join(format!("vibe-repair-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for sample in 0..1_000i16 {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        // What a crash leaves: sizes from before any audio, and half a frame at the end.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let data = bytes.windows(4).position(|window| window == b"data").unwrap();
+        bytes[4..8].copy_from_slice(&36u32.to_le_bytes());
+        bytes[data + 4..data + 8].copy_from_slice(&0u32.to_le_bytes());
+        bytes.push(0x7f);
+        std::fs::write(&path, bytes).unwrap();
+
+        assert_eq!(repair_wav(&path).unwrap(), 1_000);
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 1_000);
+        assert_eq!(reader.samples::<i16>().last().unwrap().unwrap(), 999);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+//! Streaming sample-rate conversion for audio that arrives in callback-sized buffers.
+
+/// Linear interpolation between neighbouring samples. Speech models do not need anything finer,
+/// and it has no latency and no state beyond one sample.
+pub struct LinearResampler {
+    /// Input samples per output sample.
+    step: f64,
+    /// Position of the next output sample, relative to `carry`.
+    position: f64,
+    /// Last input sample of the previous buffer, so interpolation continues across buffers.
+    carry: Option<f32>,
+}
+
+impl LinearResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: f64::from(from_rate) / f64::from(to_rate),
+            position: 0.0,
+            carry: None,
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if input.is_empty() {
+            return Vec::new();
+        }
+        if self.step == 1.0 {
+            return input.to_vec();
+        }
+        let buffer: Vec<f32> = self.carry.iter().copied().chain(input.iter().copied()).collect();
+        let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+        while self.position + 1.0 < buffer.len() as f64 {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            output.push(buffer[index] + (buffer[index + 1] - buffer[index]) * fraction);
+            self.position += self.step;
+        }
+        self.position -= (buffer.len() - 1) as f64;
+        self.carry = buffer.last().copied();
+        output
+    }
+}
+
+/// Taps of the anti-aliasing filter. Enough for speech; the transition band sits between 90% and
+/// 100% of the target Nyquist frequency.
+const LOW_PASS_TAPS: usize = 31;
+
+/// Windowed-sinc low-pass that removes what would fold back into the band when downsampling.
+/// Linear interpolation alone is fine for the live chunks, but aliasing is audible in a saved file.
+pub struct LowPass {
+    taps: Vec<f32>,
+    /// The last `taps - 1` input samples, so filtering continues across buffers.
+    history: Vec<f32>,
+}
+
+impl LowPass {
+    /// A filter for converting `from_rate` to `to_rate`, or None when that is not downsampling.
+    pub fn for_resampling(from_rate: u32, to_rate: u32) -> Option<Self> {
+        if to_rate >= from_rate {
+            return None;
+        }
+        // Cutoff in cycles per input sample.
+        let cutoff = 0.45 * f64::from(to_rate) / f64::from(from_rate);
+        let middle = (LOW_PASS_TAPS - 1) as f64 / 2.0;
+        let mut taps: Vec<f64> = (0..LOW_PASS_TAPS)
+            .map(|n| {
+                let x = n as f64 - middle;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * std::f64::consts::PI * cutoff * x).sin() / (std::f64::consts::PI * x)
+                };
+                let hamming = 0.54 - 0.46 * (2.0 * std::f64::consts::PI * n as f64 / (LOW_PASS_TAPS - 1) as f64).cos();
+                sinc * hamming
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        taps.iter_mut().for_each(|tap| *tap /= sum);
+        Some(Self {
+            taps: taps.into_iter().map(|tap| tap as f32).collect(),
+            history: vec![0.0; LOW_PASS_TAPS - 1],
+        })
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let buffer: Vec<f32> = self.history.iter().chain(input).copied().collect();
+        let output = buffer
+            .windows(self.taps.len())
+            .map(|window| window.iter().zip(&self.taps).map(|(sample, tap)| sample * tap).sum())
+            .collect();
+        self.history = buffer[buffer.len() - (self.taps.len() - 1)..].to_vec();
+        output
+    }
+}
+
+/// Average interleaved frames down to one channel.
+pub fn downmix(interleaved: impl IntoIterator<Item = f32>, channels: usize) -> Vec<f32> {
+    let channels = channels.max(1);
+    let mut output = Vec::new();
+    let mut sum = 0.0;
+    let mut count = 0;
+    for sample in interleaved {
+        sum += sample;
+        count += 1;
+        if count == channels {
+            output.push(sum / channels as f32);
+            sum = 0.0;
+            count = 0;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_length_follows_the_ratio_across_buffers() {
+        let mut resampler = LinearResampler::new(48_000, 16_000);
+        let mut total = 0;
+        for _ in 0..10 {
+            total += resampler.process(&[0.5; 480]).len();
+        }
+        assert!((1599..=1600).contains(&total), "{total}");
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        let mut resampler = LinearResampler::new(1, 2);
+        assert_eq!(resampler.process(&[0.0, 1.0]), [0.0, 0.5]);
+        assert_eq!(resampler.process(&[0.0]), [1.0, 0.5]);
+    }
+
+    #[test]
+    fn low_pass_keeps_speech_and_removes_what_would_alias() {
+        let mut low_pass = LowPass::for_resampling(48_000, 16_000).unwrap();
+        let level = |output: &[f32]| output[100..].iter().fold(0.0f32, |max, sample| max.max(sample.abs()));
+        let voice: Vec<f32> = (0..4_800)
+            .map(|i| (i as f32 * 2.0 * std::f32::consts::PI * 1_000.0 / 48_000.0).sin())
+            .collect();
+        assert!(level(&low_pass.process(&voice)) > 0.95);
+        let hiss: Vec<f32> = (0..4_800).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        assert!(level(&low_pass.process(&hiss)) < 0.01);
+        assert!(LowPass::for_resampling(16_000, 16_000).is_none());
+    }
+
+    #[test]
+    fn downmix_averages_each_frame() {
+        assert_eq!(downmix([1.0, 0.0, 0.5, 0.5], 2), [0.5, 0.5]);
+    }
+}
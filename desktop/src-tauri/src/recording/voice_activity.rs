@@ -0,0 +1,139 @@
+//! Auto-stop and silence trimming for `start_record`, decided on the windowed peak levels that
+//! `LevelMeter` emits as `record_level`.
+//!
+//! Times here are milliseconds of recorded audio: paused stretches are not written, so they do
+//! not count towards silence or the duration cap, and spans line up with the saved file.
+
+use serde::{Deserialize, Serialize};
+
+/// Peak level below which a meter window counts as silence (about -34 dBFS).
+pub(crate) const DEFAULT_SILENCE_THRESHOLD: f32 = 0.02;
+/// Silence kept around the speech when trimming, so the first and last words are not clipped.
+const TRIM_PADDING_MS: u64 = 300;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AutoStopOptions {
+    /// Stop after this much continuous silence, counted from the first speech.
+    pub silence_seconds: Option<f64>,
+    /// Stop after this much recorded audio.
+    pub max_duration_seconds: Option<f64>,
+    /// Cut the silence before the first and after the last speech from the saved recording.
+    pub trim_silence: Option<bool>,
+    /// Peak level (0..1) below which the input counts as silence.
+    pub silence_threshold: Option<f32>,
+}
+
+/// Why a recording stopped on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Silence,
+    MaxDuration,
+}
+
+pub struct VoiceActivity {
+    silence_ms: Option<u64>,
+    max_duration_ms: Option<u64>,
+    trim_silence: bool,
+    threshold: f32,
+    first_voice_ms: Option<u64>,
+    /// End of the last window with speech; meaningless until `first_voice_ms` is set.
+    last_voice_ms: u64,
+    stopped: bool,
+}
+
+impl VoiceActivity {
+    pub fn new(options: &AutoStopOptions) -> Self {
+        let to_ms = |seconds: f64| (seconds.max(0.0) * 1000.0) as u64;
+        Self {
+            silence_ms: options.silence_seconds.filter(|seconds| *seconds > 0.0).map(to_ms),
+            max_duration_ms: options.max_duration_seconds.filter(|seconds| *seconds > 0.0).map(to_ms),
+            trim_silence: options.trim_silence.unwrap_or(false),
+            threshold: options.silence_threshold.unwrap_or(DEFAULT_SILENCE_THRESHOLD),
+            first_voice_ms: None,
+            last_voice_ms: 0,
+            stopped: false,
+        }
+    }
+
+    /// Feed the peak of the meter window that ended at `recorded_ms`. Returns why the recording
+    /// should stop, once.
+    pub fn push(&mut self, recorded_ms: u64, level: f32) -> Option<StopReason> {
+        if level >= self.threshold {
+            self.first_voice_ms.get_or_insert(recorded_ms);
+            self.last_voice_ms = recorded_ms;
+        }
+        if self.stopped {
+            return None;
+        }
+        let reason = if self.max_duration_ms.is_some_and(|max| recorded_ms >= max) {
+            StopReason::MaxDuration
+        } else if self.first_voice_ms.is_some()
+            && self
+                .silence_ms
+                .is_some_and(|silence| recorded_ms.saturating_sub(self.last_voice_ms) >= silence)
+        {
+            StopReason::Silence
+        } else {
+            return None;
+        };
+        self.stopped = true;
+        Some(reason)
+    }
+
+    /// The part of the recording to keep when trimming is on, padded and clamped to `recorded_ms`.
+    /// None when trimming is off or nobody spoke, so the recording is kept whole.
+    pub fn trim_span(&self, recorded_ms: u64) -> Option<(u64, u64)> {
+        if !self.trim_silence {
+            return None;
+        }
+        let first = self.first_voice_ms?;
+        Some((
+            first.saturating_sub(TRIM_PADDING_MS),
+            (self.last_voice_ms + TRIM_PADDING_MS).min(recorded_ms),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(silence: f64, max: f64) -> AutoStopOptions {
+        AutoStopOptions {
+            silence_seconds: Some(silence),
+            max_duration_seconds: Some(max),
+            trim_silence: Some(true),
+            silence_threshold: None,
+        }
+    }
+
+    #[test]
+    fn stops_once_after_silence_following_speech() {
+        let mut activity = VoiceActivity::new(&options(2.0, 60.0));
+        assert_eq!(activity.push(1_000, 0.0), None);
+        assert_eq!(activity.push(1_500, 0.5), None);
+        assert_eq!(activity.push(3_000, 0.5), None);
+        assert_eq!(activity.push(4_900, 0.01), None);
+        assert_eq!(activity.push(5_000, 0.0), Some(StopReason::Silence));
+        assert_eq!(activity.push(8_000, 0.0), None);
+        assert_eq!(activity.trim_span(5_000), Some((1_200, 3_300)));
+    }
+
+    #[test]
+    fn waits_for_the_first_speech_before_counting_silence() {
+        let mut activity = VoiceActivity::new(&options(2.0, 60.0));
+        assert_eq!(activity.push(10_000, 0.0), None);
+        assert_eq!(activity.push(10_100, 0.5), None);
+        assert_eq!(activity.push(12_000, 0.0), None);
+        assert_eq!(activity.push(12_100, 0.0), Some(StopReason::Silence));
+    }
+
+    #[test]
+    fn caps_duration_and_keeps_silent_recordings_whole() {
+        let mut activity = VoiceActivity::new(&options(0.0, 10.0));
+        assert_eq!(activity.push(9_900, 0.0), None);
+        assert_eq!(activity.push(10_000, 0.0), Some(StopReason::MaxDuration));
+        assert_eq!(activity.trim_span(10_000), None);
+    }
+}
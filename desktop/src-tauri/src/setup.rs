@@ -31,6 +31,7 @@ pub fn setup(app: &App) -> Result<(), Box<dyn std::error::Error>> {
     // Manage sona state
     app.manage(Mutex::new(SonaState { process: None }));
     app.manage(crate::dictation_indicator::DictationIndicatorRuntime::default());
+    app.manage(crate::dictation::DictationState::default());
 
     let store = app.store(STORE_FILENAME)?;
 
@@ -43,6 +44,7 @@ pub fn setup(app: &App) -> Result<(), Box<dyn std::error::Error>> {
     crate::cleaner::clean_old_logs(app.handle()).log_error();
     crate::cleaner::clean_old_files().log_error();
     crate::cleaner::clean_updater_files().log_error();
+    crate::waveform::clean_cache().log_error();
     tracing::debug!("Vibe App Running");
 
     // Settings live in app_config.json so a person or an agent can edit it directly; the store
@@ -55,6 +57,12 @@ pub fn setup(app: &App) -> Result<(), Box<dyn std::error::Error>> {
         Err(error) => tracing::error!("could not start config watcher: {:?}", error),
     }
 
+    // Device ids are stable, so the UI can keep a selection across hot-plugs; tell it when to re-list.
+    crate::audio_devices::watch(app.handle().clone());
+
+    // Dictation runs from the backend, so the shortcut works before (and without) the webview.
+    crate::dictation::sync_shortcut(app.handle());
+
     // Crash handler
 
     let _handler = crash_handler::CrashHandler::attach(unsafe {
@@ -0,0 +1,237 @@
+//! Inserting text at the cursor of the focused app, for `type_text` and dictation.
+//!
+//! Keystrokes (`enigo.text`) work everywhere, but they are slow for long text, garble non-Latin
+//! text in several apps and mix with modifier keys the user is still holding. Pasting inserts the
+//! whole text at once: it goes on the clipboard, the paste shortcut is pressed, and the clipboard
+//! text from before is put back.
+
+use crate::config::STORE_FILENAME;
+use crate::error::LogError;
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_store::StoreExt;
+
+const CONFIG_KEY_MODE: &str = "dictation.insertionMode";
+/// `{ "<app name>": "paste" | "keystrokes" | "auto" }`, for apps that handle one method badly.
+const CONFIG_KEY_APP_MODES: &str = "dictation.insertionAppModes";
+
+/// `Auto` pastes text at least this long; typing it would take seconds.
+const AUTO_PASTE_MIN_CHARS: usize = 120;
+/// Small delay to let the user's key release propagate.
+const KEY_RELEASE_DELAY: Duration = Duration::from_millis(100);
+/// Apps read the clipboard a moment after the paste shortcut; restoring it sooner pastes the old text.
+const PASTE_SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+#[cfg(target_os = "macos")]
+const PASTE_MODIFIER: Key = Key::Meta;
+#[cfg(not(target_os = "macos"))]
+const PASTE_MODIFIER: Key = Key::Control;
+/// The V key by position rather than by the character it types, so the paste shortcut works with
+/// Hebrew, Arabic, Cyrillic and other layouts whose V key types something else.
+#[cfg(target_os = "macos")]
+const PASTE_KEY: Key = Key::Other(9); // kVK_ANSI_V
+#[cfg(windows)]
+const PASTE_KEY: Key = Key::Other(0x56); // VK_V
+/// X11 and Wayland apps match shortcuts by keysym, which is `v` on every layout.
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE_KEY: Key = Key::Unicode('v');
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InsertionMode {
+    /// Paste long or right-to-left text, type the rest.
+    #[default]
+    Auto,
+    Keystrokes,
+    Paste,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Keystrokes,
+    Paste,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InsertionOptions {
+    pub mode: InsertionMode,
+    /// Keyed by app name as `frontmost_app` reports it, compared case-insensitively.
+    pub app_modes: HashMap<String, InsertionMode>,
+}
+
+impl InsertionOptions {
+    pub fn from_config(app_handle: &AppHandle) -> Self {
+        let store = app_handle.store(STORE_FILENAME).ok();
+        let setting = |key: &str| store.as_ref().and_then(|store| store.get(key));
+        Self {
+            mode: setting(CONFIG_KEY_MODE)
+                .and_then(|value| serde_json::from_value(value).ok())
+                .unwrap_or_default(),
+            app_modes: setting(CONFIG_KEY_APP_MODES)
+                .and_then(|value| serde_json::from_value(value).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn method(&self, app: Option<&str>, text: &str) -> Method {
+        let mode = app
+            .and_then(|app| {
+                self.app_modes
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(app))
+                    .map(|(_, mode)| *mode)
+            })
+            .unwrap_or(self.mode);
+        match mode {
+            InsertionMode::Keystrokes => Method::Keystrokes,
+            InsertionMode::Paste => Method::Paste,
+            InsertionMode::Auto if text.chars().count() >= AUTO_PASTE_MIN_CHARS || text.chars().any(is_rtl) => Method::Paste,
+            InsertionMode::Auto => Method::Keystrokes,
+        }
+    }
+}
+
+/// Hebrew, Arabic and the other right-to-left blocks, with their presentation forms.
+fn is_rtl(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+fn new_enigo() -> Result<Enigo> {
+    Enigo::new(&Settings::default()).map_err(|e| eyre!("Failed to create enigo: {}", e))
+}
+
+/// Insert `text` at the cursor of the focused app, falling back to keystrokes when pasting fails.
+/// Blocks for a few hundred milliseconds.
+pub fn insert_text(app_handle: &AppHandle, text: &str, options: &InsertionOptions) -> Result<()> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let app = frontmost_app();
+    let method = options.method(app.as_deref(), text);
+    tracing::debug!("inserting {} chars into {:?} with {:?}", text.chars().count(), app, method);
+
+    let mut enigo = new_enigo()?;
+    std::thread::sleep(KEY_RELEASE_DELAY);
+    if method == Method::Paste {
+        match paste(app_handle, &mut enigo, text) {
+            Ok(()) => return Ok(()),
+            Err(error) => tracing::warn!("pasting failed, typing instead: {:?}", error),
+        }
+    }
+    enigo.text(text).map_err(|e| eyre!("Failed to type text: {}", e))
+}
+
+fn paste(app_handle: &AppHandle, enigo: &mut Enigo, text: &str) -> Result<()> {
+    let clipboard = app_handle.clipboard();
+    // Only text can be put back; images and files on the clipboard are replaced for good.
+    let previous = clipboard.read_text().ok();
+    clipboard.write_text(text).map_err(|e| eyre!("{e:?}"))?;
+
+    // Shift or Alt still held from the dictation shortcut would turn the paste into another shortcut.
+    for modifier in [Key::Shift, Key::Alt, Key::Control, Key::Meta] {
+        enigo.key(modifier, Direction::Release).log_error();
+    }
+    enigo
+        .key(PASTE_MODIFIER, Direction::Press)
+        .map_err(|e| eyre!("Failed to paste: {}", e))?;
+    let pressed = enigo.key(PASTE_KEY, Direction::Click);
+    enigo.key(PASTE_MODIFIER, Direction::Release).log_error();
+    pressed.map_err(|e| eyre!("Failed to paste: {}", e))?;
+
+    std::thread::sleep(PASTE_SETTLE_DELAY);
+    // Leave the clipboard alone if the user copied something in the meantime.
+    if let Some(previous) = previous {
+        if clipboard.read_text().ok().as_deref() == Some(text) {
+            clipboard.write_text(previous).map_err(|e| eyre!("{e:?}")).log_error();
+        }
+    }
+    Ok(())
+}
+
+/// Press Backspace `count` times, to take back inserted text.
+pub fn erase_text(count: usize) -> Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    let mut enigo = new_enigo()?;
+    std::thread::sleep(KEY_RELEASE_DELAY);
+    for _ in 0..count {
+        enigo
+            .key(Key::Backspace, Direction::Click)
+            .map_err(|e| eyre!("Failed to erase text: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Name of the app that will receive the text, to look up `app_modes`.
+#[cfg(target_os = "macos")]
+fn frontmost_app() -> Option<String> {
+    use objc2_app_kit::NSWorkspace;
+
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    Some(app.localizedName()?.to_string())
+}
+
+/// Executable name of the foreground window's process, without `.exe`.
+#[cfg(windows)]
+fn frontmost_app() -> Option<String> {
+    use std::path::Path;
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut process_id = 0u32;
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    // SAFETY: the out pointers live for the duration of each call, and the handle is closed here.
+    unsafe {
+        GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut process_id));
+        if process_id == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+        let queried = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len);
+        let _ = CloseHandle(process);
+        queried.ok()?;
+    }
+    let path = String::from_utf16_lossy(&buffer[..len as usize]);
+    Path::new(&path).file_stem().map(|stem| stem.to_string_lossy().to_string())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn frontmost_app() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InsertionMode, InsertionOptions, Method};
+
+    #[test]
+    fn auto_pastes_long_and_right_to_left_text() {
+        let options = InsertionOptions::default();
+        assert_eq!(options.method(None, "Hello world."), Method::Keystrokes);
+        assert_eq!(options.method(None, &"word ".repeat(40)), Method::Paste);
+        assert_eq!(options.method(None, "שלום עולם"), Method::Paste);
+        assert_eq!(options.method(None, "مرحبا"), Method::Paste);
+    }
+
+    #[test]
+    fn app_modes_override_the_mode() {
+        let options = InsertionOptions {
+            mode: InsertionMode::Keystrokes,
+            app_modes: [("Slack".to_string(), InsertionMode::Paste)].into(),
+        };
+        assert_eq!(options.method(Some("slack"), "Hi"), Method::Paste);
+        assert_eq!(options.method(Some("Notes"), "שלום"), Method::Keystrokes);
+        assert_eq!(options.method(None, "Hi"), Method::Keystrokes);
+    }
+}
@@ -0,0 +1,358 @@
+//! Waveform peaks of media files, so the player can draw a whole recording at once and zoom in.
+//!
+//! A file is decoded once into min/max pairs at several resolutions, cached on disk next to the
+//! temp folders. Each level halves the one before it, from a peak per 16 ms down to a level that
+//! fits the width of a window. The UI picks the level that matches its zoom and reads only the
+//! range it shows.
+//!
+//! The cache is `VWF1`, then the sample rate, the samples per peak of the first level, the total
+//! samples, the level count and each level's length (all little endian), then the levels one after
+//! another as `[min, max]` byte pairs scaled to ±127.
+
+use crate::ffmpeg::random_string;
+use crate::recording::chunker::SAMPLE_RATE;
+use eyre::{bail, Context, ContextCompat, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MAGIC: &[u8; 4] = b"VWF1";
+/// 16 ms at 16 kHz, finer than a word.
+const SAMPLES_PER_PEAK: u32 = 256;
+/// Levels stop halving once one fits in this many peaks.
+const COARSEST_MAX_PEAKS: usize = 2048;
+/// Most peaks one `read_peaks` returns.
+pub const MAX_CHUNK_PEAKS: u32 = 65_536;
+/// Waveforms of files not opened for this long are deleted by `clean_cache`.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformLevel {
+    pub samples_per_peak: u32,
+    pub peaks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformInfo {
+    pub sample_rate: u32,
+    pub duration_seconds: f64,
+    /// Finest first.
+    pub levels: Vec<WaveformLevel>,
+}
+
+/// Collects one min/max pair per `SAMPLES_PER_PEAK` samples.
+struct PeakBuilder {
+    peaks: Vec<[i8; 2]>,
+    min: f32,
+    max: f32,
+    count: u32,
+    total_samples: u64,
+}
+
+impl PeakBuilder {
+    fn new() -> Self {
+        Self {
+            peaks: Vec::new(),
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            count: 0,
+            total_samples: 0,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+            self.count += 1;
+            if self.count == SAMPLES_PER_PEAK {
+                self.flush();
+            }
+        }
+        self.total_samples += samples.len() as u64;
+    }
+
+    fn flush(&mut self) {
+        self.peaks.push([quantize(self.min), quantize(self.max)]);
+        (self.min, self.max, self.count) = (f32::INFINITY, f32::NEG_INFINITY, 0);
+    }
+
+    fn finish(mut self) -> Peaks {
+        if self.count > 0 {
+            self.flush();
+        }
+        let mut levels = vec![self.peaks];
+        while let Some(last) = levels.last().filter(|level| level.len() > COARSEST_MAX_PEAKS) {
+            levels.push(halve(last));
+        }
+        Peaks {
+            levels,
+            total_samples: self.total_samples,
+        }
+    }
+}
+
+fn quantize(sample: f32) -> i8 {
+    (sample.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
+
+/// The next coarser level: each pair of peaks merged into one.
+fn halve(level: &[[i8; 2]]) -> Vec<[i8; 2]> {
+    level
+        .chunks(2)
+        .map(|pair| {
+            let min = pair.iter().map(|peak| peak[0]).min().unwrap_or_default();
+            let max = pair.iter().map(|peak| peak[1]).max().unwrap_or_default();
+            [min, max]
+        })
+        .collect()
+}
+
+struct Peaks {
+    levels: Vec<Vec<[i8; 2]>>,
+    total_samples: u64,
+}
+
+impl Peaks {
+    fn write(&self, path: &Path) -> Result<()> {
+        // Written aside and renamed, so a reader never sees half a file.
+        let partial = path.with_extension(format!("{}.partial", random_string(6)));
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        writer.write_all(&SAMPLES_PER_PEAK.to_le_bytes())?;
+        writer.write_all(&self.total_samples.to_le_bytes())?;
+        writer.write_all(&(self.levels.len() as u32).to_le_bytes())?;
+        for level in &self.levels {
+            writer.write_all(&(level.len() as u32).to_le_bytes())?;
+        }
+        for level in &self.levels {
+            for peak in level {
+                writer.write_all(&peak.map(|value| value as u8))?;
+            }
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Everything in a cached waveform before its peaks.
+struct Header {
+    sample_rate: u32,
+    samples_per_peak: u32,
+    total_samples: u64,
+    lengths: Vec<u32>,
+}
+
+impl Header {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("Not a cached waveform");
+        }
+        let sample_rate = read_u32(reader)?;
+        let samples_per_peak = read_u32(reader)?;
+        let mut total_samples = [0u8; 8];
+        reader.read_exact(&mut total_samples)?;
+        let level_count = read_u32(reader)?;
+        // A level per halving of a u32 length at most.
+        if level_count == 0 || level_count > 32 {
+            bail!("Corrupt cached waveform");
+        }
+        let lengths = (0..level_count).map(|_| read_u32(reader)).collect::<Result<_>>()?;
+        Ok(Self {
+            sample_rate,
+            samples_per_peak,
+            total_samples: u64::from_le_bytes(total_samples),
+            lengths,
+        })
+    }
+
+    fn len(&self) -> u64 {
+        4 + 4 + 4 + 8 + 4 + 4 * self.lengths.len() as u64
+    }
+
+    fn info(&self) -> WaveformInfo {
+        WaveformInfo {
+            sample_rate: self.sample_rate,
+            duration_seconds: self.total_samples as f64 / f64::from(self.sample_rate),
+            levels: self
+                .lengths
+                .iter()
+                .enumerate()
+                .map(|(index, &peaks)| WaveformLevel {
+                    samples_per_peak: self.samples_per_peak << index,
+                    peaks,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn cache_folder() -> PathBuf {
+    std::env::temp_dir().join("vibe_waveforms")
+}
+
+/// Where the waveform of `input` is cached. Named after its path, size and modification time, so
+/// an edited file gets a new waveform.
+fn cache_path(input: &Path) -> Result<PathBuf> {
+    let metadata = std::fs::metadata(input).context(format!("Failed to read {}", input.display()))?;
+    let mut hasher = DefaultHasher::new();
+    std::fs::canonicalize(input)?.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+    Ok(cache_folder().join(format!("{:016x}.peaks", hasher.finish())))
+}
+
+fn open_cached(path: &Path) -> Result<(BufReader<File>, Header)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = Header::read(&mut reader)?;
+    Ok((reader, header))
+}
+
+/// Decode `input` into peaks, with ffmpeg when it is installed. `on_progress` gets the seconds
+/// decoded so far.
+fn compute(input: &Path, on_progress: &dyn Fn(f64)) -> Result<Peaks> {
+    let mut builder = PeakBuilder::new();
+    let mut reported = 0;
+    let mut on_samples = |samples: &[f32]| {
+        builder.push(samples);
+        let seconds = builder.total_samples / u64::from(SAMPLE_RATE);
+        if seconds > reported {
+            reported = seconds;
+            on_progress(seconds as f64);
+        }
+        Ok(())
+    };
+    if crate::ffmpeg::find_ffmpeg_path().is_some() {
+        crate::ffmpeg::decode_samples(input, SAMPLE_RATE, &mut on_samples)?;
+    } else {
+        crate::decode::decode_samples(input, &mut on_samples)?;
+    }
+    Ok(builder.finish())
+}
+
+/// The waveform of `input`, decoded now or read from the cache. Decoding a long file takes a
+/// while; `on_progress` gets the seconds decoded so far.
+pub fn load(input: &Path, on_progress: &dyn Fn(f64)) -> Result<WaveformInfo> {
+    let path = cache_path(input)?;
+    match open_cached(&path) {
+        Ok((_, header)) => return Ok(header.info()),
+        Err(error) if path.exists() => tracing::warn!("rebuilding the cached waveform {}: {:?}", path.display(), error),
+        Err(_) => {}
+    }
+    tracing::debug!("computing the waveform of {}", input.display());
+    let peaks = compute(input, on_progress)?;
+    std::fs::create_dir_all(cache_folder())?;
+    peaks.write(&path)?;
+    let (_, header) = open_cached(&path)?;
+    Ok(header.info())
+}
+
+/// Peaks `start..start + count` of one level of `input`'s cached waveform, as interleaved min and
+/// max values. Shorter at the end of the level.
+pub fn read_peaks(input: &Path, level: usize, start: u32, count: u32) -> Result<Vec<i8>> {
+    let path = cache_path(input)?;
+    let (mut reader, header) = open_cached(&path).context("The waveform is not loaded")?;
+    let length = *header.lengths.get(level).context("No such waveform level")?;
+    let start = start.min(length);
+    let count = count.min(MAX_CHUNK_PEAKS).min(length - start);
+    let offset: u64 = header.len() + header.lengths[..level].iter().map(|&peaks| u64::from(peaks) * 2).sum::<u64>();
+    reader.seek(SeekFrom::Start(offset + u64::from(start) * 2))?;
+    let mut bytes = vec![0u8; count as usize * 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes.into_iter().map(|byte| byte as i8).collect())
+}
+
+/// Delete waveforms whose files have not been opened for a month.
+pub fn clean_cache() -> Result<()> {
+    let Ok(entries) = std::fs::read_dir(cache_folder()) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let age = entry
+            .metadata()
+            .and_then(|metadata| metadata.accessed().or_else(|_| metadata.modified()))
+            .ok()
+            .and_then(|time| time.elapsed().ok());
+        if age.is_some_and(|age| age > CACHE_MAX_AGE) {
+            tracing::debug!("clean old waveform {}", entry.path().display());
+            std::fs::remove_file(entry.path()).ok();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_halve_until_they_fit_a_window() {
+        let mut builder = PeakBuilder::new();
+        // 100 seconds of a ramp from -1 to 1 each second.
+        let second: Vec<f32> = (0..SAMPLE_RATE).map(|i| i as f32 / SAMPLE_RATE as f32 * 2.0 - 1.0).collect();
+        for _ in 0..100 {
+            builder.push(&second);
+        }
+        let peaks = builder.finish();
+        let lengths: Vec<usize> = peaks.levels.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [6250, 3125, 1563]);
+        assert_eq!(peaks.levels[0][0], [-127, -123]);
+        assert_eq!(peaks.levels[2][0], [-127, -111]);
+        assert_eq!(peaks.total_samples, 1_600_000);
+    }
+
+    #[test]
+    fn reads_chunks_of_a_cached_waveform() {
+        let input = std::env::temp_dir().join(format!("vibe-waveform-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&input, spec).unwrap();
+        for i in 0..SAMPLE_RATE * 40 {
+            // Silent for the first half, at half scale for the rest.
+            writer
+                .write_sample(if i < SAMPLE_RATE * 20 { 0 } else { i16::MAX / 2 })
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let info = load(&input, &|_| {}).unwrap();
+        assert_eq!(info.duration_seconds, 40.0);
+        assert_eq!(
+            info.levels[0],
+            WaveformLevel {
+                samples_per_peak: 256,
+                peaks: 2500
+            }
+        );
+        assert_eq!(info.levels.len(), 2);
+        // Cached now.
+        assert_eq!(load(&input, &|_| panic!("decoded twice")).unwrap(), info);
+
+        assert_eq!(read_peaks(&input, 0, 1249, 2).unwrap(), [0, 0, 63, 63]);
+        assert_eq!(read_peaks(&input, 0, 2499, 10).unwrap(), [63, 63]);
+        assert_eq!(read_peaks(&input, 1, 1249, 10).unwrap(), [63, 63]);
+        assert!(read_peaks(&input, 2, 0, 1).is_err());
+
+        std::fs::remove_file(cache_path(&input).unwrap()).unwrap();
+        std::fs::remove_file(input).unwrap();
+    }
+}
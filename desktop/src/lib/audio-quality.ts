@@ -0,0 +1,37 @@
+import { invoke } from '@tauri-apps/api/core'
+import { m } from '~/paraglide/messages.js'
+
+export type QualityWarning = 'silent' | 'too_quiet' | 'clipping' | 'mostly_silent' | 'noisy' | 'little_speech'
+
+export interface AudioQuality {
+	durationSeconds: number
+	peakDb: number
+	rmsDb: number
+	clippingRatio: number
+	silenceRatio: number
+	snrDb: number | null
+	speechRatio: number
+	warnings: QualityWarning[]
+}
+
+/** Levels and quality warnings of the first ten minutes of a media file. */
+export function analyzeAudio(path: string) {
+	return invoke<AudioQuality>('analyze_audio', { path })
+}
+
+export function qualityWarningMessage(warning: QualityWarning) {
+	switch (warning) {
+		case 'silent':
+			return m.audioQualitySilent()
+		case 'too_quiet':
+			return m.audioQualityTooQuiet()
+		case 'clipping':
+			return m.audioQualityClipping()
+		case 'mostly_silent':
+			return m.audioQualityMostlySilent()
+		case 'noisy':
+			return m.audioQualityNoisy()
+		case 'little_speech':
+			return m.audioQualityLittleSpeech()
+	}
+}
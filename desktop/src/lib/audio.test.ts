@@ -0,0 +1,20 @@
+import { describe, expect, test } from 'vitest'
+import { mergeLiveSegment, type LiveSegment } from './audio'
+
+function chunk(chunkId: number, isFinal: boolean, text: string): LiveSegment {
+	return { chunkId, track: 0, isFinal, segments: [{ start: chunkId * 100, stop: chunkId * 100 + 50, text }] }
+}
+
+describe('mergeLiveSegment', () => {
+	test('replaces a provisional result and keeps chunks in order', () => {
+		let chunks = mergeLiveSegment([], chunk(2, false, 'hel'))
+		chunks = mergeLiveSegment(chunks, chunk(1, true, 'first'))
+		chunks = mergeLiveSegment(chunks, chunk(2, true, 'hello'))
+		expect(chunks.map((c) => c.segments[0].text)).toEqual(['first', 'hello'])
+	})
+
+	test('ignores a provisional result that arrives after the final one', () => {
+		const chunks = mergeLiveSegment([chunk(1, true, 'hello')], chunk(1, false, 'hel'))
+		expect(chunks.map((c) => c.segments[0].text)).toEqual(['hello'])
+	})
+})
@@ -1,3 +1,29 @@
+import type { Segment } from './transcript'
+
+/** A `live_segment` event: one chunk of a recording, transcribed while it is still recorded. */
+export interface LiveSegment {
+	chunkId: number
+	/** The device's index in `start_record`'s devices; 0 when the tracks are mixed. */
+	track: number
+	/** A provisional result is replaced by the next one for the same chunk. */
+	isFinal: boolean
+	segments: Segment[]
+}
+
+/** The chunks so far in recording order, with `next` replacing the earlier result for its chunk. */
+export function mergeLiveSegment(chunks: LiveSegment[], next: LiveSegment) {
+	// A provisional result that lands after the final one is stale.
+	if (!next.isFinal && chunks.some((chunk) => chunk.chunkId === next.chunkId && chunk.isFinal)) return chunks
+	return [...chunks.filter((chunk) => chunk.chunkId !== next.chunkId), next].sort((a, b) => a.chunkId - b.chunkId)
+}
+
+/** One device of a recording saved on its own, from `record_finish` with `separateTracks`. */
+export interface RecordedTrack {
+	path: string
+	/** The microphone is the local speaker; the system audio everyone else. */
+	source: 'microphone' | 'system'
+}
+
 export interface AudioDevice {
 	isDefault: boolean
 	isInput: boolean
@@ -0,0 +1,22 @@
+import { describe, expect, it } from 'vitest'
+import { wordAgreement } from './captions'
+import type { Segment } from './transcript'
+
+function segments(...texts: string[]): Segment[] {
+	return texts.map((text, index) => ({ start: index * 100, stop: index * 100 + 100, text }))
+}
+
+describe('wordAgreement', () => {
+	it('ignores case, punctuation and how the text is split', () => {
+		expect(wordAgreement(segments('Hello, world.', 'How are you?'), segments('hello world how are you'))).toBe(1)
+	})
+
+	it('counts each word only as often as both have it', () => {
+		expect(wordAgreement(segments('the the cat'), segments('the cat sat'))).toBeCloseTo(2 / 3)
+		expect(wordAgreement(segments('one two'), segments('three four'))).toBe(0)
+	})
+
+	it('has nothing to say about empty transcripts', () => {
+		expect(wordAgreement([], segments(' '))).toBeNull()
+	})
+})
@@ -0,0 +1,31 @@
+import type { Segment } from './transcript'
+
+function words(segments: Segment[]) {
+	return segments
+		.map((segment) => segment.text)
+		.join(' ')
+		.toLowerCase()
+		.replace(/[^\p{L}\p{N}'\s]/gu, ' ')
+		.split(/\s+/)
+		.filter(Boolean)
+}
+
+/**
+ * How much of two transcripts of the same media say the same thing, from 0 to 1: the words they
+ * share, ignoring case, punctuation and order. Null when neither has any words.
+ */
+export function wordAgreement(ours: Segment[], theirs: Segment[]) {
+	const a = words(ours)
+	const b = words(theirs)
+	if (a.length + b.length === 0) return null
+	const counts = new Map<string, number>()
+	for (const word of a) counts.set(word, (counts.get(word) ?? 0) + 1)
+	let matched = 0
+	for (const word of b) {
+		const left = counts.get(word) ?? 0
+		if (left === 0) continue
+		counts.set(word, left - 1)
+		matched += 1
+	}
+	return (2 * matched) / (a.length + b.length)
+}
@@ -35,6 +35,9 @@ export const CONFIG_KEYS = {
 	// Recording
 	storeRecordInDocuments: 'recording.storeInDocuments',
 	customRecordingPath: 'recording.customPath',
+	recordingArchiveFormat: 'recording.archiveFormat',
+	recordingLiveTranscription: 'recording.liveTranscription',
+	recordingSeparateTracks: 'recording.separateTracks',
 	inputDeviceId: 'recording.inputDeviceId',
 	outputDeviceId: 'recording.outputDeviceId',
 
@@ -51,15 +54,28 @@ export const CONFIG_KEYS = {
 	hotkeyEnabled: 'dictation.enabled',
 	hotkeyShortcut: 'dictation.shortcut',
 	hotkeyOutputMode: 'dictation.outputMode',
+	hotkeyInsertionMode: 'dictation.insertionMode',
+	hotkeyInsertionAppModes: 'dictation.insertionAppModes',
 	hotkeyActivationMode: 'dictation.activationMode',
 	hotkeyNormalizeOutput: 'dictation.normalizeOutput',
+	hotkeyAutoStopSilenceSeconds: 'dictation.autoStopSilenceSeconds',
+	hotkeyMaxDurationSeconds: 'dictation.maxDurationSeconds',
+	hotkeyModelPath: 'dictation.modelPath',
+	hotkeySpokenCommands: 'dictation.spokenCommands',
+	hotkeyTrailingSpace: 'dictation.trailingSpace',
+	hotkeySnippets: 'dictation.snippets',
 
 	// AI summaries
 	llmConfig: 'summarize.llm',
 
+	// Downloads
+	downloadProxy: 'downloads.proxy',
+	downloadMaxBytesPerSecond: 'downloads.maxBytesPerSecond',
+
 	// Tools
 	ytDlpVersion: 'tools.ytDlpVersion',
 	shouldCheckYtDlpVersion: 'tools.checkYtDlpUpdates',
+	ytDlpCaptions: 'tools.ytDlpCaptions',
 
 	// UI state that is remembered but not really a setting
 	homeTab: 'ui.homeTab',
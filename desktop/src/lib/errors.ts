@@ -0,0 +1,29 @@
+/**
+ * Commands reject with either a plain string or a `CommandError` object (`{ code, message, details? }`).
+ */
+export interface CommandError {
+	code: string
+	message: string
+	details?: unknown
+}
+
+export interface InsufficientSpaceDetails {
+	path: string
+	required: number
+	available: number
+}
+
+export function isCommandError(error: unknown): error is CommandError {
+	return typeof error === 'object' && error !== null && 'code' in error && 'message' in error
+}
+
+export function errorMessage(error: unknown): string {
+	if (typeof error === 'string') return error
+	if (error && typeof error === 'object' && 'message' in error) return String((error as { message: unknown }).message)
+	return String(error)
+}
+
+/** Not enough free space on the target volume; retrying elsewhere will not help. */
+export function isInsufficientSpace(error: unknown): error is CommandError & { details: InsufficientSpaceDetails } {
+	return isCommandError(error) && error.code === 'insufficient_space'
+}
@@ -16,3 +16,11 @@ export function validPath(path: string) {
 
 	return mediaExtensions.includes(name.slice(dot + 1).toLowerCase())
 }
+
+/** A running ffmpeg conversion; its `id` is what `cancel_conversion` takes. */
+export interface ConversionProgress {
+	id: string
+	kind: 'normalize' | 'archive' | 'preprocess' | 'clips'
+	/** Percent of the input converted. */
+	progress: number
+}
@@ -0,0 +1,37 @@
+import { invoke } from '@tauri-apps/api/core'
+
+/** One downloadable model, as listed in `src-tauri/model-manifest.json`. */
+export interface ManifestModel {
+	id: string
+	filename: string
+	name: string
+	/** The Sona engine that runs it (`whisper`, `silero-vad`, ...). */
+	engine: string
+	/** ISO 639-1 codes; empty means multilingual. */
+	languages: string[]
+	/** Tried in order; the first is the preferred mirror. */
+	urls: string[]
+	size: number | null
+	sha256: string | null
+}
+
+export interface ModelManifest {
+	revision: number
+	models: ManifestModel[]
+}
+
+/**
+ * The models Vibe knows how to download. With `refresh`, the published manifest is fetched first;
+ * offline, the cached or bundled copy is returned instead.
+ */
+export function getModelManifest(refresh = false) {
+	return invoke<ModelManifest>('get_model_manifest', { refresh })
+}
+
+/** Download URLs of a manifest model, preferred mirror first. */
+export async function modelUrls(id: string, refresh = false) {
+	const manifest = await getModelManifest(refresh)
+	const model = manifest.models.find((candidate) => candidate.id === id)
+	if (!model) throw new Error(`model '${id}' is not in the model manifest`)
+	return model.urls
+}
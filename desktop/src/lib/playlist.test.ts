@@ -0,0 +1,39 @@
+import { describe, expect, it } from 'vitest'
+import { looksLikePlaylist, parseItemRange } from './playlist'
+
+describe('looksLikePlaylist', () => {
+	it('spots playlists and channels', () => {
+		expect(looksLikePlaylist('https://www.youtube.com/playlist?list=PL123')).toBe(true)
+		expect(looksLikePlaylist('https://www.youtube.com/watch?v=abc&list=PL123')).toBe(true)
+		expect(looksLikePlaylist('https://www.youtube.com/@vibe')).toBe(true)
+		expect(looksLikePlaylist('https://www.youtube.com/@vibe/videos')).toBe(true)
+		expect(looksLikePlaylist('https://www.youtube.com/channel/UC123')).toBe(true)
+	})
+
+	it('leaves single videos and garbage alone', () => {
+		expect(looksLikePlaylist('https://www.youtube.com/watch?v=abc')).toBe(false)
+		expect(looksLikePlaylist('https://youtu.be/abc')).toBe(false)
+		expect(looksLikePlaylist('not a link')).toBe(false)
+	})
+})
+
+describe('parseItemRange', () => {
+	it('reads single items, ranges and open ends', () => {
+		expect(parseItemRange('1-3, 5', 10)).toEqual([0, 1, 2, 4])
+		expect(parseItemRange('8-', 10)).toEqual([7, 8, 9])
+		expect(parseItemRange('-2', 10)).toEqual([0, 1])
+		expect(parseItemRange('3, 1-2, 3', 10)).toEqual([0, 1, 2])
+	})
+
+	it('stops at the last item', () => {
+		expect(parseItemRange('9-20', 10)).toEqual([8, 9])
+		expect(parseItemRange('12', 10)).toEqual([])
+	})
+
+	it('rejects what it cannot read', () => {
+		expect(parseItemRange('a-b', 10)).toBeNull()
+		expect(parseItemRange('5-2', 10)).toBeNull()
+		expect(parseItemRange('0', 10)).toBeNull()
+		expect(parseItemRange('-', 10)).toBeNull()
+	})
+})
@@ -0,0 +1,51 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export interface PlaylistEntry {
+	url: string
+	title: string | null
+	durationSeconds: number | null
+}
+
+export interface Playlist {
+	title: string | null
+	entries: PlaylistEntry[]
+}
+
+/** The videos of a playlist or channel link, listed by yt-dlp without downloading them. */
+export function listPlaylist(url: string) {
+	return invoke<Playlist>('list_playlist', { url })
+}
+
+/**
+ * Whether a link may point at more than one video, so it is worth listing before downloading.
+ * A video opened from a playlist (`watch?v=…&list=…`) counts: the dialog lets the user pick just it.
+ */
+export function looksLikePlaylist(url: string) {
+	try {
+		const parsed = new URL(url.trim())
+		if (parsed.searchParams.has('list')) return true
+		return /^\/(playlist\/?$|channel\/|c\/|user\/|@)/.test(parsed.pathname)
+	} catch {
+		return false
+	}
+}
+
+/**
+ * Zero-based indexes picked by a range such as `1-5, 8, 12-`, where items are numbered from 1 and
+ * an open end runs to the last of `count`. Returns null when the range can't be read.
+ */
+export function parseItemRange(spec: string, count: number): number[] | null {
+	const picked = new Set<number>()
+	for (const part of spec.split(',')) {
+		const item = part.trim()
+		if (!item) continue
+		const match = item.match(/^(\d*)\s*(-?)\s*(\d*)$/)
+		if (!match || (!match[1] && !match[3])) return null
+		const [, from, dash, to] = match
+		const start = from ? Number(from) : 1
+		const end = dash ? (to ? Number(to) : count) : start
+		if (start < 1 || end < start) return null
+		for (let index = start; index <= Math.min(end, count); index += 1) picked.add(index - 1)
+	}
+	return [...picked].sort((a, b) => a - b)
+}
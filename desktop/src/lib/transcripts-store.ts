@@ -1,6 +1,8 @@
 import * as pathApi from '@tauri-apps/api/path'
 import * as fs from '@tauri-apps/plugin-fs'
+import type { AudioQuality } from './audio-quality'
 import type { Segment } from './transcript'
+import type { MediaDetails } from './ytdlp'
 
 /**
  * Transcript persistence.
@@ -38,6 +40,10 @@ export interface TranscriptRecord {
 	segments: Segment[]
 	/** Last AI summary of this transcript, when one was made. */
 	summary?: string
+	/** Levels and quality warnings of the media, analyzed before it was transcribed. */
+	audioQuality?: AudioQuality
+	/** Title, chapters and captions the platform had, for transcripts of downloaded links. */
+	source?: MediaDetails
 }
 
 export interface TranscriptEntry {
@@ -56,6 +62,8 @@ export interface SaveTranscriptInput {
 	language?: string
 	modelPath?: string | null
 	createdAt?: Date
+	audioQuality?: AudioQuality
+	source?: MediaDetails
 }
 
 function pad(value: number, length = 2) {
@@ -133,6 +141,12 @@ function isRecord(value: unknown): value is TranscriptRecord {
 	return typeof candidate.name === 'string' && Array.isArray(candidate.segments)
 }
 
+function isMediaDetails(value: unknown): value is MediaDetails {
+	if (typeof value !== 'object' || value === null) return false
+	const candidate = value as Partial<MediaDetails>
+	return Array.isArray(candidate.chapters) && Array.isArray(candidate.captions)
+}
+
 function serialize(record: TranscriptRecord) {
 	return JSON.stringify(record, null, '\t')
 }
@@ -175,6 +189,8 @@ export async function saveTranscript(input: SaveTranscriptInput): Promise<string
 			language: input.language,
 			modelPath: input.modelPath ?? null,
 			segments: input.segments,
+			audioQuality: input.audioQuality,
+			source: input.source,
 		}
 		await fs.writeTextFile(target, serialize(record))
 		// The transcript itself is already safe on disk; the media copy may fail without losing it.
@@ -243,6 +259,9 @@ export async function readTranscript(path: string): Promise<TranscriptRecord | n
 			segments: parsed.segments.filter(
 				(segment): segment is Segment => typeof segment === 'object' && segment !== null && typeof (segment as Segment).text === 'string',
 			),
+			audioQuality:
+				typeof parsed.audioQuality === 'object' && parsed.audioQuality !== null && Array.isArray(parsed.audioQuality.warnings) ? parsed.audioQuality : undefined,
+			source: isMediaDetails(parsed.source) ? parsed.source : undefined,
 		}
 	} catch (error) {
 		console.warn('failed to read transcript:', path, error)
@@ -0,0 +1,47 @@
+import { m } from '~/paraglide/messages.js'
+import { invoke } from '@tauri-apps/api/core'
+import { ask } from '@tauri-apps/plugin-dialog'
+import { useEffect } from 'react'
+import { ModifyState, NamedPath } from './types'
+
+interface OrphanedRecording {
+	id: string
+	startedAt: number
+	recordingName: string | null
+	size: number
+}
+
+interface FinishedRecording {
+	path: string
+	name: string
+}
+
+interface UseRecoverRecordingsProps {
+	setFiles: ModifyState<NamedPath[]>
+}
+
+// Offer recordings interrupted by a crash in an earlier run.
+export function useRecoverRecordings({ setFiles }: UseRecoverRecordingsProps) {
+	async function recoverRecordings() {
+		const orphans = await invoke<OrphanedRecording[]>('list_orphaned_recordings')
+		for (const orphan of orphans) {
+			const name = orphan.recordingName || new Date(orphan.startedAt * 1000).toLocaleString()
+			const recover = await ask(m.recoverRecordingBody({ name }), {
+				title: m.recoverRecording(),
+				kind: 'warning',
+				okLabel: m.recover(),
+				cancelLabel: m.later(),
+			})
+			if (recover) {
+				const recovered = await invoke<FinishedRecording>('recover_recording', { id: orphan.id })
+				setFiles([{ name: recovered.name, path: recovered.path }])
+			} else if (await ask(m.discardRecordingBody({ name }), { title: m.recoverRecording(), kind: 'warning' })) {
+				await invoke('discard_recording', { id: orphan.id })
+			}
+		}
+	}
+
+	useEffect(() => {
+		recoverRecordings().catch(console.error)
+	}, [])
+}
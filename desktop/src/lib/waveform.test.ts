@@ -0,0 +1,20 @@
+import { describe, expect, it } from 'vitest'
+import { pickLevel, type WaveformInfo } from './waveform'
+
+// Two hours at 16 kHz
+const info: WaveformInfo = {
+	sampleRate: 16000,
+	durationSeconds: 7200,
+	levels: Array.from({ length: 9 }, (_, level) => ({ samplesPerPeak: 256 << level, peaks: Math.ceil(450000 / 2 ** level) })),
+}
+
+describe('pickLevel', () => {
+	it('reads a coarse level for the whole file', () => {
+		expect(pickLevel(info, 0, 7200, 800)).toBe(8)
+	})
+
+	it('switches to finer levels when zoomed in', () => {
+		expect(pickLevel(info, 0, 600, 800)).toBe(5)
+		expect(pickLevel(info, 100, 110, 800)).toBe(0)
+	})
+})
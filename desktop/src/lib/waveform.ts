@@ -0,0 +1,49 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export interface WaveformLevel {
+	samplesPerPeak: number
+	peaks: number
+}
+
+export interface WaveformInfo {
+	sampleRate: number
+	durationSeconds: number
+	// Finest first
+	levels: WaveformLevel[]
+}
+
+// Same as waveform::MAX_CHUNK_PEAKS in the backend
+const MAX_CHUNK_PEAKS = 65536
+
+/** Decode the waveform of a media file, or read it from the backend's cache. */
+export function loadWaveform(path: string) {
+	return invoke<WaveformInfo>('load_waveform', { path })
+}
+
+/**
+ * The coarsest level that still has `minPeaks` peaks between two times, so a wide view reads few
+ * peaks and zooming in switches to finer ones.
+ */
+export function pickLevel(info: WaveformInfo, startSeconds: number, endSeconds: number, minPeaks: number) {
+	const seconds = Math.max(0, endSeconds - startSeconds)
+	for (let level = info.levels.length - 1; level > 0; level--) {
+		if ((seconds * info.sampleRate) / info.levels[level].samplesPerPeak >= minPeaks) {
+			return level
+		}
+	}
+	return 0
+}
+
+/** Interleaved min and max peaks (-127 to 127) of one level between two times, read chunk by chunk. */
+export async function readPeaks(path: string, info: WaveformInfo, level: number, startSeconds: number, endSeconds: number) {
+	const { samplesPerPeak, peaks } = info.levels[level]
+	const start = Math.max(0, Math.floor((startSeconds * info.sampleRate) / samplesPerPeak))
+	const end = Math.min(peaks, Math.ceil((endSeconds * info.sampleRate) / samplesPerPeak))
+	const result = new Int8Array(Math.max(0, end - start) * 2)
+	for (let offset = start; offset < end; offset += MAX_CHUNK_PEAKS) {
+		const count = Math.min(MAX_CHUNK_PEAKS, end - offset)
+		const chunk = await invoke<number[]>('get_waveform_peaks', { path, level, start: offset, count })
+		result.set(chunk, (offset - start) * 2)
+	}
+	return result
+}
@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core'
 import * as fs from '@tauri-apps/plugin-fs'
 import * as path from '@tauri-apps/api/path'
 import { ytDlpAssetNames, ytDlpDownloadUrl } from './config'
+import type { Segment } from './transcript'
 
 const platformArch = `${platform()}-${arch()}`
 const assetName = ytDlpAssetNames[platformArch]
@@ -24,11 +25,40 @@ export async function getLatestVersion(): Promise<string> {
 export async function downloadYtDlp(version: string) {
 	const url = ytDlpDownloadUrl(version, platformArch)
 	const binaryPath = await getBinaryPath()
-	await invoke('download_file', { url, path: binaryPath })
+	await invoke('download_file', { url, path: binaryPath, id: 'yt-dlp' })
 }
 
-export async function downloadAudio(url: string, inDocuments?: boolean, customPath?: string | null) {
+/** A chapter of a video, in centiseconds like transcript segments. */
+export interface Chapter {
+	start: number
+	stop: number
+	title: string
+}
+
+export interface Captions {
+	language: string
+	segments: Segment[]
+}
+
+/** What the platform says about a downloaded video. */
+export interface MediaDetails {
+	title: string | null
+	uploader: string | null
+	/** `YYYY-MM-DD` */
+	uploadDate: string | null
+	description: string | null
+	durationSeconds: number | null
+	url: string | null
+	chapters: Chapter[]
+	captions: Captions[]
+}
+
+/**
+ * Download the audio of a link, with what the platform says about it. With `captions`, its own
+ * captions in that language (`auto` for the ones the author uploaded) are fetched too.
+ */
+export async function downloadAudio(url: string, inDocuments?: boolean, customPath?: string | null, captions?: { language: string }) {
 	const outPath = await invoke<string>('get_temp_path', { ext: 'm4a', inDocuments, customPath: customPath ?? null })
-	await invoke<string>('download_audio', { url, outPath })
-	return outPath
+	const details = await invoke<MediaDetails>('download_audio', { url, outPath, captions: Boolean(captions), language: captions?.language ?? null })
+	return { path: outPath, details }
 }
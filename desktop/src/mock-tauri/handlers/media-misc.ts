@@ -10,8 +10,8 @@ interface MockAudioDevice {
 }
 
 const MOCK_AUDIO_DEVICES: MockAudioDevice[] = [
-	{ isDefault: true, isInput: true, id: 'mock-mic', name: 'Mock Microphone' },
-	{ isDefault: true, isInput: false, id: 'mock-out', name: 'Mock Speakers' },
+	{ isDefault: true, isInput: true, id: 'Mock:input:Mock Microphone', name: 'Mock Microphone' },
+	{ isDefault: true, isInput: false, id: 'Mock:output:Mock Speakers', name: 'Mock Speakers' },
 ]
 
 const RECORD_FINISH_DELAY_MS = 200
@@ -58,6 +58,25 @@ function join(folder: string, name: string) {
 	return `${folder.replace(/\/+$/, '')}/${name}`
 }
 
+/** What yt-dlp would report for a downloaded link: a title and two chapters, without captions. */
+function mockMediaDetails(url: unknown) {
+	return {
+		title: 'Mock video',
+		uploader: 'Mock channel',
+		uploadDate: '2026-01-01',
+		description: null,
+		durationSeconds: 600,
+		url: url ? String(url) : null,
+		chapters: [
+			{ start: 0, stop: 30000, title: 'Introduction' },
+			{ start: 30000, stop: 60000, title: 'Wrapping up' },
+		],
+		captions: [],
+	}
+}
+
+let mockRecordPaused = false
+
 export const mediaMiscHandlers: CommandHandlerMap = {
 	// --- Audio devices / recording ---------------------------------------------
 
@@ -69,12 +88,14 @@ export const mediaMiscHandlers: CommandHandlerMap = {
 		// Stand in for the capture callbacks: a level event every 100ms, like the throttled Rust side.
 		let tick = 0
 		const levelTimer = window.setInterval(() => {
+			if (mockRecordPaused) return
 			tick += 1
 			emitMockEvent('record_level', mockSpeechLevel(tick))
 		}, RECORD_LEVEL_TICK_MS)
 		// The frontend stops recording by emitting `stop_record` on the event bus.
 		const unsub = onMockEvent('stop_record', () => {
 			unsub()
+			mockRecordPaused = false
 			window.clearInterval(levelTimer)
 			setTimeout(() => {
 				virtualFs.set(path, null)
@@ -85,6 +106,18 @@ export const mediaMiscHandlers: CommandHandlerMap = {
 		return undefined
 	},
 
+	pause_record: () => {
+		if (mockRecordPaused) return
+		mockRecordPaused = true
+		emitMockEvent('record_paused', null)
+	},
+
+	resume_record: () => {
+		if (!mockRecordPaused) return
+		mockRecordPaused = false
+		emitMockEvent('record_resumed', null)
+	},
+
 	// --- Text injection ---------------------------------------------------------
 
 	type_text: (args) => {
@@ -134,8 +167,69 @@ export const mediaMiscHandlers: CommandHandlerMap = {
 
 	get_temp_path: (args) => `${APP_LOCAL_DATA}/tmp.${String(args?.ext ?? 'tmp')}`,
 
+	// Mock recordings finish without ffmpeg, so there is never a conversion to cancel.
+	cancel_conversion: () => false,
+
+	// A minute of audio whose loudness swells and fades, so the player has a waveform to draw.
+	load_waveform: () => ({
+		sampleRate: 16000,
+		durationSeconds: 60,
+		levels: [
+			{ samplesPerPeak: 256, peaks: 3750 },
+			{ samplesPerPeak: 512, peaks: 1875 },
+		],
+	}),
+	get_waveform_peaks: (args) => {
+		const samplesPerPeak = Number(args?.level ?? 0) === 0 ? 256 : 512
+		const start = Number(args?.start ?? 0)
+		const peaks: number[] = []
+		for (let i = start; i < start + Number(args?.count ?? 0); i++) {
+			const seconds = (i * samplesPerPeak) / 16000
+			const value = Math.round(Math.abs(Math.sin(seconds / 3)) * (60 + 40 * Math.sin(seconds * 7)))
+			peaks.push(-value, value)
+		}
+		return peaks
+	},
+
+	export_clips: (args) => {
+		console.info('[mock] export_clips', args)
+		return [String(args?.output ?? '')]
+	},
+
+	// Accept any custom filter; there is no ffmpeg to check it against.
+	validate_audio_filter: () => null,
+
+	probe_media: () => ({
+		container: 'wav',
+		durationSeconds: 60,
+		audioStreams: [{ index: 0, codec: 'pcm_s16le', sampleRate: 16000, channels: 1, channelLayout: 'mono' }],
+		videoStreams: [],
+		metadata: { title: null, creationTime: null, tags: {} },
+		silent: false,
+	}),
+
+	analyze_audio: () => ({
+		durationSeconds: 60,
+		peakDb: -3,
+		rmsDb: -20,
+		clippingRatio: 0,
+		silenceRatio: 0.2,
+		snrDb: 35,
+		speechRatio: 0.9,
+		warnings: [],
+	}),
+
 	// --- yt-dlp -----------------------------------------------------------------
 
+	list_playlist: (args) => ({
+		title: 'Mock playlist',
+		entries: Array.from({ length: 5 }, (_, index) => ({
+			url: `${String(args?.url ?? '')}#${index + 1}`,
+			title: `Lesson ${index + 1}`,
+			durationSeconds: 300 + index * 60,
+		})),
+	}),
+
 	pick_media_paths: async () => null,
 
 	set_tray: async () => null,
@@ -152,13 +246,13 @@ export const mediaMiscHandlers: CommandHandlerMap = {
 					// The call site checks its own cancel ref after this resolves, so resolve
 					// quietly instead of rejecting (a rejection would open the error modal).
 					console.info('[mock] download_audio cancelled')
-					return outPath
+					return mockMediaDetails(args?.url)
 				}
 				emitMockEvent('ytdlp-progress', (tick / YTDLP_TICKS) * 100)
 				await sleep(YTDLP_TICK_MS)
 			}
 			virtualFs.set(outPath, null)
-			return outPath
+			return mockMediaDetails(args?.url)
 		} finally {
 			unsub()
 		}
@@ -196,4 +290,7 @@ export const mediaMiscHandlers: CommandHandlerMap = {
 	},
 	hide_dictation_indicator: () => undefined,
 	dictation_indicator_ready: () => undefined,
+	set_dictation_shortcut: (args) => {
+		console.info('[mock] set_dictation_shortcut', args?.shortcut)
+	},
 }
@@ -4,6 +4,7 @@
 import { emitMockEvent, onMockEvent } from '../event-bus'
 import { DEFAULT_MODEL_FILE, DOWNLOAD_TICKS, DOWNLOAD_TICK_MS, MODELS_FOLDER, TRANSCRIBE_SEGMENT_INTERVAL_MS, sampleSegments, virtualFs } from '../state'
 import type { CommandHandlerMap } from '../types'
+import bundledModelManifest from '../../../src-tauri/model-manifest.json'
 
 interface Segment {
 	start: number
@@ -171,6 +172,8 @@ function runTranscribe(
 export const sonaHandlers: CommandHandlerMap = {
 	get_models_folder: () => MODELS_FOLDER,
 
+	get_model_manifest: () => bundledModelManifest,
+
 	// ({ modelPath }) - every mock model reports the same whisper capabilities.
 	get_model_metadata: () => ({
 		format: 'gguf',
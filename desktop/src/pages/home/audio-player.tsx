@@ -3,14 +3,17 @@ import { useEffect, useRef, useState } from 'react'
 import { Music2, Pause, Play, SkipBack, SkipForward } from 'lucide-react'
 import { Button } from '~/components/ui/button'
 import { m } from '~/paraglide/messages.js'
+import Waveform from './waveform'
 
 interface AudioInputProps {
 	audio: HTMLAudioElement
+	// The file playing, to draw its waveform
+	path?: string
 	label: string
 	onLabelClick: () => void
 }
 
-export default function AudioPlayer({ audio, label, onLabelClick }: AudioInputProps) {
+export default function AudioPlayer({ audio, path, label, onLabelClick }: AudioInputProps) {
 	const [playing, setPlaying] = useState(false)
 	const [progress, setProgres] = useState(0)
 	const [currentDuration, setCurrentDuration] = useState<number>(0)
@@ -167,6 +170,7 @@ export default function AudioPlayer({ audio, label, onLabelClick }: AudioInputPr
 				</div>
 
 				<div className="mt-4 space-y-2">
+					{path && <Waveform path={path} progress={progress} />}
 					<div
 						className="relative h-2 w-full cursor-grab overflow-hidden rounded-full bg-muted touch-none active:cursor-grabbing"
 						onPointerDown={onSeekPointerDown}
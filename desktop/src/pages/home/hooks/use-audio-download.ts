@@ -3,13 +3,22 @@ import { listen } from '@tauri-apps/api/event'
 import * as dialog from '@tauri-apps/plugin-dialog'
 import { useContext, useEffect, useRef, useState } from 'react'
 import { m } from '~/paraglide/messages.js'
+import { errorMessage } from '~/lib/errors'
+import { listPlaylist, looksLikePlaylist, type Playlist, type PlaylistEntry } from '~/lib/playlist'
 import * as ytDlp from '~/lib/ytdlp'
 import { ErrorModalContext } from '~/providers/error-modal'
 import { useFilesContext } from '~/providers/files-provider'
 import { usePreferenceProvider } from '~/providers/preference'
 import { useToastProvider } from '~/providers/toast'
 
-export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
+/**
+ * @param queueLinks takes the picked videos of a playlist or channel link; without it, links are
+ * always downloaded as a single video.
+ */
+export function useAudioDownload(
+	transcribe: (path: string, details?: ytDlp.MediaDetails) => Promise<void>,
+	queueLinks?: (links: { name: string; url: string }[]) => void,
+) {
 	const preference = usePreferenceProvider()
 	const { setFiles } = useFilesContext()
 	const toast = useToastProvider()
@@ -17,6 +26,8 @@ export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
 	const [audioUrl, setAudioUrl] = useState('')
 	const [downloadingAudio, setDownloadingAudio] = useState(false)
 	const [ytdlpProgress, setYtDlpProgress] = useState<number | null>(null)
+	const [listingPlaylist, setListingPlaylist] = useState(false)
+	const [playlist, setPlaylist] = useState<Playlist | null>(null)
 	const cancelYtDlpRef = useRef(false)
 	const switchingToLinkRef = useRef(false)
 	const cachedYtDlpVersion = useRef<string | null | undefined>(undefined)
@@ -95,7 +106,7 @@ export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
 					preference.setHomeTab('link')
 				} catch (error) {
 					console.error(error)
-					setErrorModal?.({ log: String(error), open: true })
+					setErrorModal?.({ log: errorMessage(error), open: true })
 				}
 			} else if (binaryExists) {
 				if (needsUpdate) skippedUpdatePromptRef.current = true
@@ -107,18 +118,36 @@ export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
 	}
 
 	async function downloadAudio() {
-		if (!audioUrl) return
+		if (!audioUrl || listingPlaylist) return
+		if (queueLinks && looksLikePlaylist(audioUrl)) {
+			setListingPlaylist(true)
+			try {
+				const listed = await listPlaylist(audioUrl)
+				if (listed.entries.length > 1) {
+					setPlaylist(listed)
+					return
+				}
+			} catch (error) {
+				// Downloading the link as one video instead would hide why the playlist is missing.
+				console.error('failed to list playlist:', error)
+				setErrorModal?.({ log: String(error), open: true })
+				return
+			} finally {
+				setListingPlaylist(false)
+			}
+		}
 		setYtDlpProgress(0)
 		setDownloadingAudio(true)
 		try {
-			const outPath = await ytDlp.downloadAudio(audioUrl, preference.storeRecordInDocuments, preference.customRecordingPath)
+			const captions = preference.ytDlpCaptions ? { language: preference.modelOptions.lang } : undefined
+			const { path: outPath, details } = await ytDlp.downloadAudio(audioUrl, preference.storeRecordInDocuments, preference.customRecordingPath, captions)
 			if (cancelYtDlpRef.current) {
 				cancelYtDlpRef.current = false
 				return
 			}
 			preference.setHomeTab('file')
 			setFiles([{ name: 'audio.m4a', path: outPath }])
-			await transcribe(outPath)
+			await transcribe(outPath, details)
 		} catch (error) {
 			console.error(error)
 			setErrorModal?.({ log: String(error), open: true })
@@ -128,6 +157,11 @@ export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
 		}
 	}
 
+	function queuePlaylist(entries: PlaylistEntry[]) {
+		setPlaylist(null)
+		queueLinks?.(entries.map((entry) => ({ name: entry.title || entry.url, url: entry.url })))
+	}
+
 	return {
 		cancelYtDlpRef,
 		cancelYtDlpDownload,
@@ -139,5 +173,9 @@ export function useAudioDownload(transcribe: (path: string) => Promise<void>) {
 		downloadAudio,
 		downloadingAudio,
 		setDownloadingAudio,
+		listingPlaylist,
+		playlist,
+		setPlaylist,
+		queuePlaylist,
 	}
 }
@@ -1,14 +1,27 @@
-import { emit } from '@tauri-apps/api/event'
+import { emit, listen } from '@tauri-apps/api/event'
 import { invoke } from '@tauri-apps/api/core'
 import { type SetStateAction, useContext, useEffect, useState } from 'react'
-import type { AudioDevice } from '~/lib/audio'
+import { toast } from 'sonner'
+import { m } from '~/paraglide/messages.js'
+import { mergeLiveSegment, type AudioDevice, type LiveSegment } from '~/lib/audio'
 import { CONFIG_KEYS } from '~/lib/config-keys'
+import { errorMessage } from '~/lib/errors'
 import { usePersisted } from '~/lib/config-store'
 import { startKeepAwake, stopKeepAwake } from '~/lib/keep-awake'
+import type { ConversionProgress } from '~/lib/media'
 import { ensureSystemAudioPermission } from '~/lib/permissions'
 import { ErrorModalContext } from '~/providers/error-modal'
 import { usePreferenceProvider } from '~/providers/preference'
 
+/**
+ * The saved device, or the system default while it is unplugged or when nothing was saved.
+ * An empty saved id means the user turned that source off.
+ */
+export function pickDevice(devices: AudioDevice[], savedId: string | null) {
+	if (savedId === '') return null
+	return devices.find((device) => device.id === savedId) ?? devices.find((device) => device.isDefault) ?? null
+}
+
 export function useRecording(onBeforeStart: () => void) {
 	const preference = usePreferenceProvider()
 	const { setState: setErrorModal } = useContext(ErrorModalContext)
@@ -18,6 +31,8 @@ export function useRecording(onBeforeStart: () => void) {
 	const [inputDevice, setInputDevice] = useState<AudioDevice | null>(null)
 	const [outputDevice, setOutputDevice] = useState<AudioDevice | null>(null)
 	const [isRecording, setIsRecording] = useState(false)
+	const [isPaused, setIsPaused] = useState(false)
+	const [liveChunks, setLiveChunks] = useState<LiveSegment[]>([])
 	const [recordingName, setRecordingName] = useState('')
 
 	function setInputDeviceAndSave(value: SetStateAction<AudioDevice | null>) {
@@ -36,41 +51,104 @@ export function useRecording(onBeforeStart: () => void) {
 		const newDevices = await invoke<AudioDevice[]>('get_audio_devices')
 		const inputs = newDevices.filter((device) => device.isInput)
 		const outputs = newDevices.filter((device) => !device.isInput)
-		setInputDevice(
-			savedInputDeviceId === null
-				? (inputs.find((device) => device.isDefault) ?? null)
-				: (inputs.find((device) => device.id === savedInputDeviceId) ?? null),
-		)
-		setOutputDevice(
-			savedOutputDeviceId === null
-				? (outputs.find((device) => device.isDefault) ?? null)
-				: (outputs.find((device) => device.id === savedOutputDeviceId) ?? null),
-		)
+		setInputDevice(pickDevice(inputs, savedInputDeviceId))
+		setOutputDevice(pickDevice(outputs, savedOutputDeviceId))
 		setDevices(newDevices)
 	}
 
 	useEffect(() => {
-		if (preference.homeTab === 'record') loadAudioDevices()
-	}, [preference.homeTab])
+		if (preference.homeTab !== 'record') return
+		loadAudioDevices()
+		// Device ids are stable, so re-listing on hot-plug keeps the saved choice when it is still there.
+		const unlisteners = [listen('audio_devices_changed', loadAudioDevices), listen('default_audio_device_changed', loadAudioDevices)]
+		return () => {
+			unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()))
+		}
+	}, [preference.homeTab, savedInputDeviceId, savedOutputDeviceId])
+
+	// The backend confirms each pause, so the state stays right when the command races a stop.
+	useEffect(() => {
+		if (!isRecording) {
+			setIsPaused(false)
+			return
+		}
+		const unlisteners = [listen('record_paused', () => setIsPaused(true)), listen('record_resumed', () => setIsPaused(false))]
+		return () => {
+			unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()))
+		}
+	}, [isRecording])
+
+	/**
+	 * Live results keep arriving after the stop while the last chunk is transcribed, so these listen
+	 * for the hook's lifetime. The full transcription that follows `record_finish` replaces them.
+	 */
+	useEffect(() => {
+		const unlisteners = [
+			listen<LiveSegment>('live_segment', ({ payload }) => setLiveChunks((chunks) => mergeLiveSegment(chunks, payload))),
+			listen<string>('live_transcription_error', ({ payload }) => {
+				toast.error(m.liveTranscriptionFailed({ error: payload }), { id: 'live-transcription-error', position: 'bottom-center' })
+			}),
+			listen('live_transcript_finished', () => setLiveChunks([])),
+		]
+		return () => {
+			unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()))
+		}
+	}, [])
+
+	/**
+	 * Mixing the tracks and compressing the saved copy run between the stop and `record_finish`, and
+	 * take a while on long recordings. Cancelling the compression keeps the WAV instead.
+	 */
+	useEffect(() => {
+		if (!isRecording) return
+		let toastId: string | number | undefined
+		const unlisten = listen<ConversionProgress>('conversion_progress', ({ payload }) => {
+			if (payload.kind !== 'normalize' && payload.kind !== 'archive') return
+			const archive = payload.kind === 'archive'
+			toastId = toast.loading((archive ? m.compressingRecording : m.finishingRecordingProgress)({ progress: payload.progress }), {
+				id: toastId,
+				position: 'bottom-center',
+				action: archive ? { label: m.cancel(), onClick: () => void invoke('cancel_conversion', { id: payload.id }) } : undefined,
+			})
+		})
+		return () => {
+			unlisten.then((fn) => fn())
+			if (toastId !== undefined) toast.dismiss(toastId)
+		}
+	}, [isRecording])
 
 	async function startRecord() {
 		if (outputDevice && !(await ensureSystemAudioPermission())) return
 		startKeepAwake()
 		onBeforeStart()
 		setIsRecording(true)
+		setLiveChunks([])
 		const selectedDevices = [inputDevice, outputDevice].filter((device): device is AudioDevice => device !== null)
 		try {
+			let liveTranscription = null
+			if (preference.recordingLiveTranscription && preference.modelPath) {
+				// Live chunks go to the loaded model, so it has to be up before the first one is cut.
+				await invoke('load_model', {
+					modelPath: preference.modelPath,
+					gpuDevice: preference.gpuDevice,
+					unloadTimeoutMinutes: preference.unloadTimeoutMinutes,
+				})
+				const { lang, translate, init_prompt } = preference.modelOptions
+				liveTranscription = { lang, translate: translate ?? null, init_prompt: init_prompt || null }
+			}
 			await invoke('start_record', {
 				devices: selectedDevices,
 				storeInDocuments: preference.storeRecordInDocuments,
 				customPath: preference.customRecordingPath,
 				recordingName: recordingName.trim() || null,
+				liveTranscription,
+				separateTracks: preference.recordingSeparateTracks,
 			})
 		} catch (error) {
 			stopKeepAwake()
 			setIsRecording(false)
 			console.error('startRecord error: ', error)
-			setErrorModal?.({ log: String(error), open: true })
+			setErrorModal?.({ log: errorMessage(error), open: true })
 		}
 	}
 
@@ -85,6 +163,24 @@ export function useRecording(onBeforeStart: () => void) {
 		}
 	}
 
+	async function pauseRecord() {
+		try {
+			await invoke('pause_record')
+		} catch (error) {
+			console.error('pauseRecord error: ', error)
+			setErrorModal?.({ log: errorMessage(error), open: true })
+		}
+	}
+
+	async function resumeRecord() {
+		try {
+			await invoke('resume_record')
+		} catch (error) {
+			console.error('resumeRecord error: ', error)
+			setErrorModal?.({ log: errorMessage(error), open: true })
+		}
+	}
+
 	return {
 		devices,
 		setDevices,
@@ -92,11 +188,15 @@ export function useRecording(onBeforeStart: () => void) {
 		outputDevice,
 		isRecording,
 		setIsRecording,
+		isPaused,
+		liveChunks,
 		recordingName,
 		setRecordingName,
 		setInputDevice: setInputDeviceAndSave,
 		setOutputDevice: setOutputDeviceAndSave,
 		startRecord,
 		stopRecord,
+		pauseRecord,
+		resumeRecord,
 	}
 }
@@ -187,7 +187,7 @@ export default function Home() {
 							{vm.audio && (
 								<div>
 									{vm.files.length ? (
-										<AudioPlayer label={vm.files[0].name} onLabelClick={() => vm.openPath(vm.files[0])} audio={vm.audio} />
+										<AudioPlayer label={vm.files[0].name} path={vm.files[0].path} onLabelClick={() => vm.openPath(vm.files[0])} audio={vm.audio} />
 									) : null}
 									{!vm.loading && (
 										<Button variant="link" onMouseDown={vm.selectFiles} className="mb-2 mt-1 px-0 text-xs">
@@ -15,7 +15,6 @@ import { ls, pathToNamedPath } from '~/lib/fs'
 import { openPath } from '~/lib/app'
 import { ModelOptions, usePreferenceProvider } from '~/providers/preference'
 import { UpdaterContext } from '~/providers/updater'
-import { hotkeyRecordingActive } from '~/providers/hotkey'
 import { useSummarization } from './hooks/use-summarization'
 import { useRecording } from './hooks/use-recording'
 import { useAudioDownload } from './hooks/use-audio-download'
@@ -122,7 +121,6 @@ export function viewModel() {
 		)
 		unlisteners.push(
 			listen<{ path: string; name: string }>('record_finish', (event) => {
-				if (hotkeyRecordingActive) return
 				const { name, path } = event.payload
 				setSelectedFolder(null)
 				preference.setHomeTab('file')
@@ -0,0 +1,90 @@
+import { useCallback, useEffect, useRef, useState } from 'react'
+import { loadWaveform, pickLevel, readPeaks } from '~/lib/waveform'
+
+interface WaveformProps {
+	path: string
+	// Percent played, drawn in the primary color
+	progress: number
+}
+
+// CSS pixels per bar, gap included; the level read has at least a peak per bar
+const BAR_WIDTH = 3
+
+/** The waveform of a whole media file, from the peaks the backend decodes and caches. */
+export default function Waveform({ path, progress }: WaveformProps) {
+	const containerRef = useRef<HTMLDivElement>(null)
+	const canvasRef = useRef<HTMLCanvasElement>(null)
+	const [peaks, setPeaks] = useState<Int8Array | null>(null)
+
+	useEffect(() => {
+		let cancelled = false
+		setPeaks(null)
+		const load = async () => {
+			try {
+				const info = await loadWaveform(path)
+				const width = containerRef.current?.clientWidth || 600
+				const level = pickLevel(info, 0, info.durationSeconds, width / BAR_WIDTH)
+				const next = await readPeaks(path, info, level, 0, info.durationSeconds)
+				if (!cancelled) setPeaks(next)
+			} catch (error) {
+				console.error('failed to load the waveform', error)
+			}
+		}
+		load()
+		return () => {
+			cancelled = true
+		}
+	}, [path])
+
+	const draw = useCallback(() => {
+		const canvas = canvasRef.current
+		const container = containerRef.current
+		if (!canvas || !container || !peaks) return
+		const ctx = canvas.getContext('2d')
+		if (!ctx) return
+
+		const dpr = window.devicePixelRatio
+		const width = container.clientWidth
+		const height = container.clientHeight
+		if (canvas.width !== width * dpr || canvas.height !== height * dpr) {
+			canvas.width = width * dpr
+			canvas.height = height * dpr
+		}
+		ctx.setTransform(dpr, 0, 0, dpr, 0, 0)
+		ctx.clearRect(0, 0, width, height)
+
+		// The container is text-primary and the canvas text-muted-foreground, so both follow the theme
+		const playedColor = getComputedStyle(container).color
+		const restColor = getComputedStyle(canvas).color
+		const bars = Math.floor(width / BAR_WIDTH)
+		const peakCount = peaks.length / 2
+		const midY = height / 2
+		for (let bar = 0; bar < bars; bar++) {
+			// Each bar covers its share of the peaks; keep the loudest of them
+			const from = Math.floor((bar * peakCount) / bars)
+			const to = Math.max(from + 1, Math.floor(((bar + 1) * peakCount) / bars))
+			let min = 0
+			let max = 0
+			for (let i = from; i < to && i < peakCount; i++) {
+				min = Math.min(min, peaks[i * 2])
+				max = Math.max(max, peaks[i * 2 + 1])
+			}
+			const top = midY - (max / 127) * midY
+			const barHeight = Math.max(1, ((max - min) / 127) * midY)
+			ctx.fillStyle = (bar / bars) * 100 < progress ? playedColor : restColor
+			ctx.fillRect(bar * BAR_WIDTH, top, BAR_WIDTH - 1, barHeight)
+		}
+	}, [peaks, progress])
+
+	useEffect(() => {
+		draw()
+		window.addEventListener('resize', draw)
+		return () => window.removeEventListener('resize', draw)
+	}, [draw])
+
+	return (
+		<div ref={containerRef} className="relative h-10 w-full text-primary">
+			<canvas ref={canvasRef} className="absolute inset-0 h-full w-full text-muted-foreground/50" />
+		</div>
+	)
+}
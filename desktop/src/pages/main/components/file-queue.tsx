@@ -1,7 +1,8 @@
 import { motion } from 'framer-motion'
-import { AlertCircle, Check, CircleSlash, X } from 'lucide-react'
+import { AlertCircle, AlertTriangle, Check, CircleSlash, X } from 'lucide-react'
 import { m } from '~/paraglide/messages.js'
 import { Spinner } from '~/components/ui/spinner'
+import { qualityWarningMessage } from '~/lib/audio-quality'
 import { cn } from '~/lib/style'
 import type { Job } from '../hooks/use-transcribe-queue'
 import { useSession } from '../session'
@@ -24,7 +25,7 @@ function StatusIcon({ job }: { job: Job }) {
 function statusLabel(job: Job) {
 	switch (job.status) {
 		case 'running':
-			return `${Math.round(job.progress)}%`
+			return job.downloading ? m.downloading({ progress: String(Math.round(job.progress)) }) : `${Math.round(job.progress)}%`
 		case 'done':
 			return job.seconds != null ? m.transcribeTook({ total: String(job.seconds) }) : m.transcribed()
 		case 'error':
@@ -73,6 +74,11 @@ export default function FileQueue() {
 									<StatusIcon job={job} />
 								</span>
 								<span className="min-w-0 flex-1 truncate text-[13px] font-medium text-foreground">{job.name}</span>
+								{job.quality && job.quality.warnings.length > 0 && (
+									<span title={job.quality.warnings.map(qualityWarningMessage).join('\n')} className="shrink-0 text-amber-500">
+										<AlertTriangle className="h-3.5 w-3.5" />
+									</span>
+								)}
 								{job.status === 'running' && (
 									<span
 										role="button"
@@ -1,6 +1,6 @@
 import { listen } from '@tauri-apps/api/event'
 import { AnimatePresence, motion } from 'framer-motion'
-import { FolderOpen, Link2, Mic, Square, Upload } from 'lucide-react'
+import { FolderOpen, Link2, Mic, Pause, Play, Square, Upload } from 'lucide-react'
 import { siFacebook, siInstagram, siTiktok, siX, siYoutube } from 'simple-icons'
 import { useEffect, useRef, useState } from 'react'
 import { m } from '~/paraglide/messages.js'
@@ -9,8 +9,10 @@ import { Button } from '~/components/ui/button'
 import { Input } from '~/components/ui/input'
 import { Spinner } from '~/components/ui/spinner'
 import { Tooltip, TooltipContent, TooltipTrigger } from '~/components/ui/tooltip'
+import type { LiveSegment } from '~/lib/audio'
 import { cn } from '~/lib/style'
 import { useSession, type IdlePanel } from '../session'
+import PlaylistDialog from './playlist-dialog'
 import QuietRow from './quiet-row'
 
 function formatElapsed(seconds: number) {
@@ -101,6 +103,29 @@ function Segment({ active, label, onClick, children }: { active?: boolean; label
 	)
 }
 
+/** What live transcription has heard so far; provisional text is dimmed until its chunk is final. */
+function LiveTranscript({ chunks }: { chunks: LiveSegment[] }) {
+	const scrollRef = useRef<HTMLDivElement>(null)
+
+	useEffect(() => {
+		scrollRef.current?.scrollTo({ top: scrollRef.current.scrollHeight })
+	}, [chunks])
+
+	return (
+		<div ref={scrollRef} className="max-h-32 w-full overflow-y-auto rounded-xl border border-border/60 px-3 py-2 text-sm leading-relaxed">
+			{chunks.length === 0 ? (
+				<span className="text-muted-foreground">{m.liveTranscriptListening()}</span>
+			) : (
+				chunks.map((chunk) => (
+					<span key={chunk.chunkId} className={cn(!chunk.isFinal && 'text-muted-foreground')}>
+						{chunk.segments.map((segment) => segment.text.trim()).join(' ')}{' '}
+					</span>
+				))
+			)}
+		</div>
+	)
+}
+
 function RecordPanel() {
 	const { recording, recordElapsed, preference } = useSession()
 
@@ -114,12 +139,30 @@ function RecordPanel() {
 			<div className="flex flex-col items-center gap-4 py-2.5">
 				<div className="flex items-center gap-3">
 					<LevelMeter />
-					<span className="font-mono text-2xl tracking-tight tabular-nums">{formatElapsed(recordElapsed)}</span>
+					<span className={cn('font-mono text-2xl tracking-tight tabular-nums', recording.isPaused && 'text-muted-foreground')}>
+						{formatElapsed(recordElapsed)}
+					</span>
 				</div>
-				<Button onClick={() => recording.stopRecord()} className="h-10 w-full rounded-xl">
-					<Square className="h-3.5 w-3.5 fill-current" />
-					{m.stopAndTranscribe()}
-				</Button>
+				<div className="flex w-full gap-2">
+					<Tooltip>
+						<TooltipTrigger asChild>
+							<Button
+								variant="outline"
+								size="icon"
+								onClick={() => (recording.isPaused ? recording.resumeRecord() : recording.pauseRecord())}
+								aria-label={recording.isPaused ? m.resumeRecording() : m.pauseRecording()}
+								className="shrink-0 rounded-xl">
+								{recording.isPaused ? <Play className="h-4 w-4" /> : <Pause className="h-4 w-4" />}
+							</Button>
+						</TooltipTrigger>
+						<TooltipContent side="bottom">{recording.isPaused ? m.resumeRecording() : m.pauseRecording()}</TooltipContent>
+					</Tooltip>
+					<Button onClick={() => recording.stopRecord()} className="h-10 flex-1 rounded-xl">
+						<Square className="h-3.5 w-3.5 fill-current" />
+						{m.stopAndTranscribe()}
+					</Button>
+				</div>
+				{preference.recordingLiveTranscription && <LiveTranscript chunks={recording.liveChunks} />}
 			</div>
 		)
 	}
@@ -218,12 +261,21 @@ function LinkPanel() {
 				/>
 				<Button
 					onClick={() => link.downloadAudio()}
-					disabled={!preference.modelPath || !link.audioUrl}
+					disabled={!preference.modelPath || !link.audioUrl || link.listingPlaylist}
 					className="h-10 shrink-0 rounded-xl px-4 disabled:opacity-40">
-					{m.transcribe()}
+					{link.listingPlaylist ? (
+						<>
+							<Spinner className="h-3.5 w-3.5" />
+							{m.listingVideos()}
+						</>
+					) : (
+						m.transcribe()
+					)}
 				</Button>
 			</div>
 
+			<PlaylistDialog playlist={link.playlist} onOpenChange={(open) => !open && link.setPlaylist(null)} onConfirm={link.queuePlaylist} />
+
 			<div className="flex flex-col items-center gap-3">
 				<p className="text-[11px] font-medium tracking-[0.08em] text-muted-foreground/80 uppercase">{m.worksWith()}</p>
 				<div className="flex flex-wrap items-center justify-center gap-x-5 gap-y-3 text-muted-foreground/70">
@@ -0,0 +1,116 @@
+import formatDuration from 'format-duration'
+import { useEffect, useMemo, useState } from 'react'
+import { m } from '~/paraglide/messages.js'
+import { Button } from '~/components/ui/button'
+import { Dialog, DialogContent, DialogHeader, DialogTitle } from '~/components/ui/dialog'
+import { Input } from '~/components/ui/input'
+import { parseItemRange, type Playlist, type PlaylistEntry } from '~/lib/playlist'
+import { cn } from '~/lib/style'
+
+/**
+ * A course or a channel is rarely wanted whole: the entries are listed first, and a range such as
+ * `1-5, 8` or the checkboxes pick which ones are queued.
+ */
+export default function PlaylistDialog({
+	playlist,
+	onOpenChange,
+	onConfirm,
+}: {
+	playlist: Playlist | null
+	onOpenChange: (open: boolean) => void
+	onConfirm: (entries: PlaylistEntry[]) => void
+}) {
+	const entries = useMemo(() => playlist?.entries ?? [], [playlist])
+	const [selected, setSelected] = useState<Set<number>>(new Set())
+	const [range, setRange] = useState('')
+	const [rangeInvalid, setRangeInvalid] = useState(false)
+
+	useEffect(() => {
+		setSelected(new Set(entries.map((_, index) => index)))
+		setRange('')
+		setRangeInvalid(false)
+	}, [entries])
+
+	function applyRange(value: string) {
+		setRange(value)
+		if (!value.trim()) {
+			setRangeInvalid(false)
+			setSelected(new Set(entries.map((_, index) => index)))
+			return
+		}
+		const indexes = parseItemRange(value, entries.length)
+		setRangeInvalid(indexes === null)
+		if (indexes) setSelected(new Set(indexes))
+	}
+
+	function toggle(index: number) {
+		const next = new Set(selected)
+		if (next.has(index)) next.delete(index)
+		else next.add(index)
+		setSelected(next)
+	}
+
+	const picked = entries.filter((_, index) => selected.has(index))
+	const totalSeconds = picked.reduce((total, entry) => total + (entry.durationSeconds ?? 0), 0)
+	const allSelected = selected.size === entries.length
+
+	return (
+		<Dialog open={playlist !== null} onOpenChange={onOpenChange}>
+			<DialogContent className="max-w-lg rounded-2xl border-border/60 bg-card/95 p-6 shadow-xl">
+				<DialogHeader>
+					<DialogTitle className="truncate text-lg font-semibold">{playlist?.title || m.chooseVideos()}</DialogTitle>
+				</DialogHeader>
+
+				<div className="flex items-center gap-2">
+					<Input
+						value={range}
+						onChange={(event) => applyRange(event.target.value)}
+						placeholder={m.playlistRangePlaceholder()}
+						className={cn('h-9 flex-1 rounded-xl text-sm', rangeInvalid && 'border-destructive')}
+					/>
+					<Button
+						variant="ghost"
+						size="sm"
+						onClick={() => {
+							setRange('')
+							setRangeInvalid(false)
+							setSelected(allSelected ? new Set() : new Set(entries.map((_, index) => index)))
+						}}>
+						{allSelected ? m.selectNone() : m.selectAll()}
+					</Button>
+				</div>
+
+				<div className="max-h-80 overflow-y-auto rounded-xl border border-border/60">
+					{entries.map((entry, index) => (
+						<label
+							key={`${index}-${entry.url}`}
+							className="flex cursor-pointer items-center gap-3 px-3 py-2 text-[13px] transition-colors duration-150 hover:bg-muted/60">
+							<input type="checkbox" checked={selected.has(index)} onChange={() => toggle(index)} className="accent-primary" />
+							<span className="w-7 shrink-0 text-end font-mono text-[11px] tabular-nums text-muted-foreground">{index + 1}</span>
+							<span className="min-w-0 flex-1 truncate text-foreground" title={entry.url}>
+								{entry.title || entry.url}
+							</span>
+							<span className="shrink-0 font-mono text-[11px] tabular-nums text-muted-foreground">
+								{entry.durationSeconds != null ? formatDuration(entry.durationSeconds * 1000) : '—'}
+							</span>
+						</label>
+					))}
+				</div>
+
+				<div className="flex items-center justify-between gap-2 pt-1">
+					<span className="text-[12px] text-muted-foreground">
+						{m.playlistSelection({ count: String(picked.length), total: String(entries.length), duration: formatDuration(totalSeconds * 1000) })}
+					</span>
+					<div className="flex gap-2">
+						<Button variant="ghost" onClick={() => onOpenChange(false)}>
+							{m.cancel()}
+						</Button>
+						<Button disabled={picked.length === 0} onClick={() => onConfirm(picked)}>
+							{m.transcribeCount({ count: String(picked.length) })}
+						</Button>
+					</div>
+				</div>
+			</DialogContent>
+		</Dialog>
+	)
+}
@@ -9,6 +9,7 @@ import {
 	PilcrowLeft,
 	PilcrowRight,
 	Plus,
+	Scissors,
 	Search,
 	Settings2,
 	Sparkles,
@@ -247,7 +248,9 @@ export default function TranscriptToolbar({
 	setTab: (tab: TranscriptTab) => void
 }) {
 	const { queue, startNew } = useSession()
-	const { copy, exportAs } = useTranscriptExport(job?.segments ?? [], job ? { name: job.name, path: job.path } : null)
+	const { copy, exportAs, exportClips } = useTranscriptExport(job?.segments ?? [], job ? { name: job.name, path: job.path } : null)
+	// The search narrows the transcript down to the quotes worth cutting out
+	const matches = query ? (job?.segments ?? []).filter((segment) => segment.text.toLowerCase().includes(query.toLowerCase())) : []
 	const [copied, setCopied] = useState(false)
 	const [searching, setSearching] = useState(false)
 	const searchRef = useRef<HTMLInputElement>(null)
@@ -307,6 +310,26 @@ export default function TranscriptToolbar({
 							placeholder={m.searchTranscript()}
 							className="h-9 w-full bg-transparent text-sm text-foreground placeholder:text-muted-foreground focus:outline-none"
 						/>
+						<DropdownMenu>
+							<Tooltip>
+								<TooltipTrigger asChild>
+									<DropdownMenuTrigger asChild>
+										<Button variant="ghost" size="iconSm" disabled={matches.length === 0} className="rounded-full" aria-label={m.exportClips()}>
+											<Scissors className="h-4 w-4" />
+										</Button>
+									</DropdownMenuTrigger>
+								</TooltipTrigger>
+								<TooltipContent>{m.exportClips()}</TooltipContent>
+							</Tooltip>
+							<DropdownMenuContent align="end" className="min-w-[12rem] rounded-xl">
+								<DropdownMenuItem onSelect={() => void exportClips(matches, 'separate')} className="rounded-lg text-[13px]">
+									{m.exportSeparateClips({ count: matches.length })}
+								</DropdownMenuItem>
+								<DropdownMenuItem onSelect={() => void exportClips(matches, 'reel')} className="rounded-lg text-[13px]">
+									{m.exportHighlightReel()}
+								</DropdownMenuItem>
+							</DropdownMenuContent>
+						</DropdownMenu>
 						<Button variant="ghost" size="iconSm" onClick={closeSearch} className="rounded-full">
 							<X className="h-4 w-4" />
 						</Button>
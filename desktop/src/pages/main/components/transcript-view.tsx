@@ -1,12 +1,14 @@
 import { AnimatePresence, motion } from 'framer-motion'
 import { ArrowDownToLine, Play } from 'lucide-react'
-import { useCallback, useEffect, useMemo, useRef, useState } from 'react'
+import { Fragment, useCallback, useEffect, useMemo, useRef, useState } from 'react'
 import { m } from '~/paraglide/messages.js'
 import HTMLView from '~/components/html-view'
 import Markdown from 'react-markdown'
 import { Spinner } from '~/components/ui/spinner'
+import { wordAgreement } from '~/lib/captions'
 import { formatTimestamp, type Segment } from '~/lib/transcript'
 import { cn } from '~/lib/style'
+import type { Chapter } from '~/lib/ytdlp'
 import { usePreferenceProvider } from '~/providers/preference'
 import { useSession } from '../session'
 import type { Job } from '../hooks/use-transcribe-queue'
@@ -359,11 +361,31 @@ export default function TranscriptView({
 		return all.filter(({ segment }) => segment.text.toLowerCase().includes(query.toLowerCase()))
 	}, [job.segments, query])
 
+	// A downloaded video's chapters head the first line spoken in them.
+	const chapterHeadings = useMemo(() => {
+		const headings = new Map<number, Chapter[]>()
+		for (const chapter of job.source?.chapters ?? []) {
+			const index = job.segments.findIndex((segment) => segment.start >= chapter.start)
+			if (index < 0) continue
+			headings.set(index, [...(headings.get(index) ?? []), chapter])
+		}
+		return headings
+	}, [job.source, job.segments])
+
+	// The platform's own captions, to see how far they agree with ours once the transcript is complete.
+	const platformCaptions = job.source?.captions[0]
+	const [showCaptions, setShowCaptions] = useState(false)
+	const agreement = useMemo(
+		() => (platformCaptions && editable ? wordAgreement(job.segments, platformCaptions.segments) : null),
+		[platformCaptions, editable, job.segments],
+	)
+
 	// Switching file, searching or a new run must never leave an editor open over other text.
 	useEffect(() => {
 		setEditing(null)
 		setActiveIndex(-1)
 		setFollowing(true)
+		setShowCaptions(false)
 	}, [job.id])
 
 	useEffect(() => {
@@ -508,7 +530,26 @@ export default function TranscriptView({
 			{/* overflow-x-hidden: the offscreen export copy below must not become a sideways scroll in RTL. */}
 			<div ref={scrollRef} onScroll={onScroll} className="h-full min-h-0 overflow-x-hidden overflow-y-auto">
 				<div dir={preference.textAreaDirection} className="mx-auto w-full max-w-[86ch] px-8 py-10 xl:max-w-[96ch]">
-					<p className="mb-8 truncate text-[11px] font-medium tracking-[0.08em] text-muted-foreground uppercase">{job.name}</p>
+					<p className={cn('truncate text-[11px] font-medium tracking-[0.08em] text-muted-foreground uppercase', job.source ? 'mb-1' : 'mb-8')}>
+						{job.name}
+					</p>
+					{job.source && (
+						<p className="mb-8 truncate text-[12px] text-muted-foreground">
+							{[job.source.uploader, job.source.uploadDate].filter(Boolean).join(' · ')}
+							{platformCaptions && agreement !== null && (
+								<>
+									{job.source.uploader || job.source.uploadDate ? ' · ' : ''}
+									{m.platformCaptionsMatch({ language: platformCaptions.language, percent: String(Math.round(agreement * 100)) })}{' '}
+									<button
+										type="button"
+										onClick={() => setShowCaptions(!showCaptions)}
+										className="cursor-pointer text-foreground underline-offset-2 hover:underline">
+										{showCaptions ? m.hidePlatformCaptions() : m.showPlatformCaptions()}
+									</button>
+								</>
+							)}
+						</p>
+					)}
 
 					{job.status === 'error' && <p className="mb-8 text-sm text-destructive">{job.error}</p>}
 
@@ -520,24 +561,50 @@ export default function TranscriptView({
 
 					<div className="space-y-1">
 						{visible.map(({ segment, index }) => (
-							<SegmentBlock
-								key={`${segment.start}-${index}`}
-								segment={segment}
-								index={index}
-								query={query}
-								animate={running}
-								editable={editable}
-								editing={editing?.index === index ? editing : null}
-								active={activeIndex === index}
-								options={options}
-								onStartEdit={startEdit}
-								onCancel={cancelEdit}
-								onCommit={commitEdit}
-								onMove={moveEdit}
-							/>
+							<Fragment key={`${segment.start}-${index}`}>
+								{/* Headings would only break up search results. */}
+								{!query &&
+									chapterHeadings.get(index)?.map((chapter) => (
+										<h3 key={chapter.start} className="pt-6 pb-1 text-sm font-semibold text-foreground first:pt-0">
+											{chapter.title}
+										</h3>
+									))}
+								<SegmentBlock
+									segment={segment}
+									index={index}
+									query={query}
+									animate={running}
+									editable={editable}
+									editing={editing?.index === index ? editing : null}
+									active={activeIndex === index}
+									options={options}
+									onStartEdit={startEdit}
+									onCancel={cancelEdit}
+									onCommit={commitEdit}
+									onMove={moveEdit}
+								/>
+							</Fragment>
 						))}
 					</div>
 
+					{showCaptions && platformCaptions && (
+						<div className="mt-10 border-t border-border/60 pt-6">
+							<p className="mb-4 text-[11px] font-medium tracking-[0.08em] text-muted-foreground uppercase">
+								{m.platformCaptions({ language: platformCaptions.language })}
+							</p>
+							<div className="space-y-1">
+								{platformCaptions.segments.map((segment, index) => (
+									<div key={`${segment.start}-${index}`} className="flex gap-3 py-1">
+										<span className="mt-[3px] w-[52px] shrink-0 text-end font-mono text-[11px] tabular-nums text-muted-foreground">
+											{formatTimestamp(segment.start, false, '', false)}
+										</span>
+										<span className={cn('min-w-0 flex-1 text-muted-foreground', textSizeClass[options.textSize])}>{segment.text}</span>
+									</div>
+								))}
+							</div>
+						</div>
+					)}
+
 					{running && <div className={cn('mt-8 h-4 w-24 animate-pulse rounded-full bg-muted')} />}
 				</div>
 
@@ -7,6 +7,8 @@ import { toast } from 'sonner'
 import successSound from '~/assets/success.mp3'
 import { m } from '~/paraglide/messages.js'
 import { analyticsEvents, trackAnalyticsEvent } from '~/lib/analytics'
+import type { RecordedTrack } from '~/lib/audio'
+import { analyzeAudio, qualityWarningMessage, type AudioQuality } from '~/lib/audio-quality'
 import * as config from '~/lib/config'
 import { startKeepAwake, stopKeepAwake } from '~/lib/keep-awake'
 import { validPath } from '~/lib/media'
@@ -14,6 +16,8 @@ import { isUserError } from '~/lib/sona-errors'
 import type { Segment, Transcript } from '~/lib/transcript'
 import { notifyTranscriptsChanged, saveTranscript, updateTranscriptSegments, updateTranscriptSummary, type TranscriptRecord } from '~/lib/transcripts-store'
 import type { NamedPath } from '~/lib/types'
+import * as ytDlp from '~/lib/ytdlp'
+import type { MediaDetails } from '~/lib/ytdlp'
 import { ErrorModalContext } from '~/providers/error-modal'
 import { type Preference, usePreferenceProvider } from '~/providers/preference'
 
@@ -36,6 +40,16 @@ export interface Job {
 	hydrated?: boolean
 	/** Last AI summary of this transcript, when one was made. */
 	summary?: string
+	/** Audio analysis made before transcribing, when it succeeded. */
+	quality?: AudioQuality
+	/** Link downloaded with yt-dlp when the job starts; `path` is empty until then. */
+	url?: string
+	/** true while the job's link is downloading, before it is transcribed */
+	downloading?: boolean
+	/** What the platform said about a downloaded link: title, chapters, its own captions. */
+	source?: MediaDetails
+	/** A recording's devices on their own, transcribed per speaker instead of the mix at `path`. */
+	tracks?: RecordedTrack[]
 }
 
 export interface TranscribeQueue {
@@ -47,7 +61,9 @@ export interface TranscribeQueue {
 	isAborting: boolean
 	hasResults: boolean
 	selectJob: (id: string) => void
-	enqueue: (files: NamedPath[]) => void
+	enqueue: (files: (NamedPath & { source?: MediaDetails; tracks?: RecordedTrack[] })[]) => void
+	/** Queue links, such as the picked videos of a playlist, to download and transcribe one by one. */
+	enqueueLinks: (links: { name: string; url: string }[]) => void
 	/**
 	 * Replace the session with a transcript loaded from the store, shown as a finished job.
 	 * `audioPath` is the media copy kept in the project folder, when it still has one; it becomes the
@@ -146,6 +162,12 @@ export function useTranscribeQueue(): TranscribeQueue {
 				if (id && payload >= 0 && payload <= 100) patch(id, { progress: payload })
 			}),
 		)
+		unlisteners.push(
+			listen<number>('ytdlp-progress', ({ payload }) => {
+				const job = jobsRef.current.find((candidate) => candidate.id === activeIdRef.current)
+				if (job?.downloading) patch(job.id, { progress: Math.max(job.progress, Math.ceil(payload)) })
+			}),
+		)
 		unlisteners.push(
 			listen<Segment>('new_segment', ({ payload }) => {
 				const id = activeIdRef.current
@@ -180,6 +202,8 @@ export function useTranscribeQueue(): TranscribeQueue {
 				segments,
 				language: current.modelOptions.lang,
 				modelPath: current.modelPath,
+				audioQuality: job.quality,
+				source: job.source,
 			}).then((savedPath) => {
 				if (!savedPath) return
 				patch(job.id, { savedPath })
@@ -239,17 +263,66 @@ export function useTranscribeQueue(): TranscribeQueue {
 				if (!pinnedRef.current) select(next.id)
 				patch(next.id, { status: 'running', progress: 0, segments: [], error: undefined })
 				abortCurrentRef.current = false
+				const stopIfAborted = () => {
+					if (!abortCurrentRef.current && !abortAllRef.current) return false
+					patch(next.id, { status: 'cancelled', progress: 0, downloading: false })
+					abortCurrentRef.current = false
+					setIsAborting(false)
+					return true
+				}
+
+				let { path, name, source } = next
+				if (next.url && !path) {
+					patch(next.id, { downloading: true })
+					try {
+						const current = preferenceRef.current
+						const captions = current.ytDlpCaptions ? { language: current.modelOptions.lang } : undefined
+						const downloaded = await ytDlp.downloadAudio(next.url, current.storeRecordInDocuments, current.customRecordingPath, captions)
+						path = downloaded.path
+						source = downloaded.details
+						name = source.title || name
+					} catch (error) {
+						if (stopIfAborted()) continue
+						// One broken link must not stop the rest of a playlist.
+						patch(next.id, { status: 'error', progress: 0, downloading: false, error: errorParts(error).message })
+						continue
+					}
+					if (stopIfAborted()) continue
+					patch(next.id, { path, name, source, progress: 0, downloading: false })
+				}
+
+				// Warn about a bad recording while the long part runs, rather than have the model blamed for it.
+				// The warning is dropped when the job is cancelled first.
+				let cancelled = false
+				const analysis = analyzeAudio(path)
+					.then((quality): AudioQuality | undefined => {
+						if (cancelled || abortCurrentRef.current || abortAllRef.current) return undefined
+						patch(next.id, { quality })
+						if (quality.warnings.length > 0) {
+							toast.warning(m.audioQualityWarnings({ name }), {
+								description: quality.warnings.map(qualityWarningMessage).join(' '),
+								position: 'bottom-center',
+								duration: 10000,
+							})
+						}
+						return quality
+					})
+					.catch((error) => {
+						console.warn('failed to analyze audio:', path, error)
+						return undefined
+					})
 
 				const startedAt = performance.now()
 				trackAnalyticsEvent(analyticsEvents.TRANSCRIBE_STARTED, { source: 'main' })
 				try {
-					const result = await invoke<Transcript>('transcribe', {
-						options: { path: next.path, ...preferenceRef.current.modelOptions, ...shared },
-					})
+					const options = { path, ...preferenceRef.current.modelOptions, ...shared }
+					const result = next.tracks
+						? await invoke<Transcript>('transcribe_tracks', { options, tracks: next.tracks })
+						: await invoke<Transcript>('transcribe', { options })
 					const seconds = Math.round((performance.now() - startedAt) / 1000)
 					patch(next.id, { status: 'done', progress: 100, segments: result.segments, seconds })
 					completedAny = true
-					persist(next, result.segments)
+					persist({ ...next, path, name, source, quality: await analysis }, result.segments)
 					trackAnalyticsEvent(analyticsEvents.TRANSCRIBE_SUCCEEDED, {
 						source: 'main',
 						duration_seconds: seconds,
@@ -257,6 +330,7 @@ export function useTranscribeQueue(): TranscribeQueue {
 					})
 				} catch (error) {
 					if (abortCurrentRef.current || abortAllRef.current) {
+						cancelled = true
 						patch(next.id, { status: 'cancelled', progress: 0 })
 					} else {
 						const { code, message } = errorParts(error)
@@ -267,7 +341,7 @@ export function useTranscribeQueue(): TranscribeQueue {
 							trackAnalyticsEvent(analyticsEvents.TRANSCRIBE_FAILED, {
 								source: 'main',
 								error_message: message,
-								file_ext: next.path.split('.').pop() ?? 'unknown',
+								file_ext: path.split('.').pop() ?? 'unknown',
 							})
 							setErrorModal?.({ log: message, open: true })
 							// Every following file would fail the same way.
@@ -310,7 +384,7 @@ export function useTranscribeQueue(): TranscribeQueue {
 	}, [commit, failPending, patch, persist, select, setErrorModal])
 
 	const enqueue = useCallback(
-		(files: NamedPath[]) => {
+		(files: (NamedPath & { source?: MediaDetails; tracks?: RecordedTrack[] })[]) => {
 			const accepted = files.filter((file) => validPath(file.path.toLowerCase()))
 			if (accepted.length === 0) {
 				if (files.length > 0) toast.error(m.supportsFormats(), { position: 'bottom-center' })
@@ -321,6 +395,27 @@ export function useTranscribeQueue(): TranscribeQueue {
 				id: nextJobId(),
 				name: file.name,
 				path: file.path,
+				source: file.source,
+				tracks: file.tracks,
+				status: 'queued',
+				progress: 0,
+				segments: [],
+			}))
+			commit([...jobsRef.current, ...created])
+			if (!selectedIdRef.current) select(created[0].id)
+			void runLoop()
+		},
+		[commit, runLoop, select],
+	)
+
+	const enqueueLinks = useCallback(
+		(links: { name: string; url: string }[]) => {
+			if (links.length === 0) return
+			const created: Job[] = links.map((link) => ({
+				id: nextJobId(),
+				name: link.name,
+				path: '',
+				url: link.url,
 				status: 'queued',
 				progress: 0,
 				segments: [],
@@ -347,6 +442,8 @@ export function useTranscribeQueue(): TranscribeQueue {
 				savedPath,
 				hydrated: true,
 				summary: record.summary,
+				quality: record.audioQuality,
+				source: record.source,
 			}
 			pinnedRef.current = true
 			commit([job])
@@ -382,20 +479,26 @@ export function useTranscribeQueue(): TranscribeQueue {
 		[commit],
 	)
 
+	/** Stop whatever the active job is doing: its download, or its transcription. */
+	const abortActive = useCallback(() => {
+		const active = jobsRef.current.find((job) => job.id === activeIdRef.current)
+		emit(active?.downloading ? 'ytdlp-cancel' : 'abort_transcribe')
+	}, [])
+
 	const cancelCurrent = useCallback(() => {
 		if (!activeIdRef.current) return
 		abortCurrentRef.current = true
 		setIsAborting(true)
-		emit('abort_transcribe')
-	}, [])
+		abortActive()
+	}, [abortActive])
 
 	const cancelAll = useCallback(() => {
 		abortAllRef.current = true
 		abortCurrentRef.current = true
 		setIsAborting(true)
-		emit('abort_transcribe')
+		abortActive()
 		commit(jobsRef.current.map((job) => (job.status === 'queued' ? { ...job, status: 'cancelled' } : job)))
-	}, [commit])
+	}, [abortActive, commit])
 
 	const reset = useCallback(() => {
 		if (runningRef.current) cancelAll()
@@ -417,6 +520,7 @@ export function useTranscribeQueue(): TranscribeQueue {
 		hasResults,
 		selectJob,
 		enqueue,
+		enqueueLinks,
 		hydrate,
 		updateSegmentText,
 		setJobSummary,
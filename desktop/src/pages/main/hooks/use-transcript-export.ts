@@ -1,5 +1,6 @@
 import { path as pathApi } from '@tauri-apps/api'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import * as clipboard from '@tauri-apps/plugin-clipboard-manager'
 import * as dialog from '@tauri-apps/plugin-dialog'
 import * as fs from '@tauri-apps/plugin-fs'
@@ -9,10 +10,17 @@ import { m } from '~/paraglide/messages.js'
 import { formatExtensions, type TextFormat } from '~/components/format-select'
 import { openPath } from '~/lib/app'
 import { toDocx } from '~/lib/docx'
+import type { ConversionProgress } from '~/lib/media'
 import { asCsv, asJson, asSrt, asText, asVtt, type Segment } from '~/lib/transcript'
 import type { NamedPath } from '~/lib/types'
 import { usePreferenceProvider } from '~/providers/preference'
 
+// Clips keep a little air around each quote, and fade so the cuts don't click
+const CLIP_PADDING_MS = 250
+const CLIP_FADE_MS = 40
+
+export type ClipLayout = 'separate' | 'reel'
+
 /** Formats offered by the Export dropdown, in menu order. */
 export const exportFormats: TextFormat[] = ['normal', 'srt', 'vtt', 'html', 'pdf', 'json', 'docx']
 
@@ -93,5 +101,53 @@ export function useTranscriptExport(segments: Segment[], file: NamedPath | null)
 		[asFormat, file, preference.textAreaDirection, segments, speakerLabel],
 	)
 
-	return { plainText, copy, exportAs }
+	/** Cut the audio of `clips` out of the file: each into a folder, or all into one highlight reel. */
+	const exportClips = useCallback(
+		async (clips: Segment[], layout: ClipLayout) => {
+			if (!file || clips.length === 0) return
+			let output: string | null
+			if (layout === 'reel') {
+				const suggested = await invoke<NamedPath>('get_save_path', { srcPath: file.path, targetExt: 'highlights.wav' })
+				output = await dialog.save({ filters: [{ name: '', extensions: ['wav'] }], canCreateDirectories: true, defaultPath: suggested.path })
+			} else {
+				output = await dialog.open({ directory: true, canCreateDirectories: true })
+			}
+			if (!output) return
+
+			const toastId = toast.loading(m.exportingClips(), { position: 'bottom-center' })
+			// The backend names the conversion in its progress events; that id is what cancels it.
+			const unlisten = await listen<ConversionProgress>('conversion_progress', ({ payload }) => {
+				if (payload.kind !== 'clips') return
+				toast.loading(m.exportingClipsProgress({ progress: payload.progress }), {
+					id: toastId,
+					position: 'bottom-center',
+					action: { label: m.cancel(), onClick: () => void invoke('cancel_conversion', { id: payload.id }) },
+				})
+			})
+			try {
+				await invoke<string[]>('export_clips', {
+					path: file.path,
+					segments: clips,
+					output,
+					options: { paddingMs: CLIP_PADDING_MS, fadeMs: CLIP_FADE_MS, layout, nameFrom: 'text', speakerLabel },
+				})
+				const target = output
+				toast.success(m.saveSuccess(), {
+					id: toastId,
+					description: layout === 'reel' ? await pathApi.basename(target) : m.clipsExported({ count: clips.length }),
+					position: 'bottom-center',
+					action: { label: m.findHere(), onClick: () => openPath({ name: '', path: target }) },
+				})
+			} catch (error) {
+				const { code, message } = (error ?? {}) as { code?: string; message?: string }
+				if (code === 'cancelled') toast.dismiss(toastId)
+				else toast.error(m.couldNotExportClips({ error: message ?? String(error) }), { id: toastId, position: 'bottom-center' })
+			} finally {
+				unlisten()
+			}
+		},
+		[file, speakerLabel],
+	)
+
+	return { plainText, copy, exportAs, exportClips }
 }
@@ -108,8 +108,9 @@ function useShowSidebar() {
 function PlayerSlot() {
 	const { queue } = useSession()
 	const selected = queue.selectedJob
-	// The source media exists as soon as a job does — listening while it transcribes is fine.
-	if (!selected) return null
+	// The source media exists as soon as a file job does — listening while it transcribes is fine.
+	// A link has none until it is downloaded.
+	if (!selected?.path) return null
 	return <PlayerBar key={selected.id} job={selected} />
 }
 
@@ -7,12 +7,13 @@ import * as fs from '@tauri-apps/plugin-fs'
 import { createContext, useCallback, useContext, useEffect, useMemo, useRef, useState, type ReactNode } from 'react'
 import { useNavigate } from 'react-router-dom'
 import { m } from '~/paraglide/messages.js'
+import type { RecordedTrack } from '~/lib/audio'
 import * as config from '~/lib/config'
 import { ls, pathToNamedPath } from '~/lib/fs'
 import { isModelFile } from '~/lib/model'
 import type { NamedPath } from '~/lib/types'
+import type { MediaDetails } from '~/lib/ytdlp'
 import { useConfirmExit } from '~/lib/use-confirm-exit'
-import { hotkeyRecordingActive } from '~/providers/hotkey'
 import { usePreferenceProvider, type Preference } from '~/providers/preference'
 import { useAudioDownload } from '~/pages/home/hooks/use-audio-download'
 import { useRecording } from '~/pages/home/hooks/use-recording'
@@ -106,32 +107,47 @@ export function SessionProvider({ children }: { children: ReactNode }) {
 		),
 	)
 
-	/** Adapter for hooks that hand us a single produced file (recording / downloaded audio). */
-	const transcribeOne = useCallback(async (filePath: string) => {
-		await enqueuePaths([filePath])
+	/**
+	 * Adapter for hooks that hand us a single produced file (recording / downloaded audio). A download
+	 * is named after its title and keeps what the platform said about it.
+	 */
+	const transcribeOne = useCallback(async (filePath: string, details?: MediaDetails) => {
+		if (!details) {
+			await enqueuePaths([filePath])
+			return
+		}
+		const file = await pathToNamedPath(filePath)
+		enqueueRef.current([{ ...file, name: details.title || file.name, source: details }])
 	}, [])
 
 	const recording = useRecording(() => setPanel('record'))
-	const link = useAudioDownload(transcribeOne)
+	const link = useAudioDownload(transcribeOne, queue.enqueueLinks)
 
+	// Kept across pauses so the clock shows recorded time and resumes where it stopped.
+	const recordElapsedMsRef = useRef(0)
 	useEffect(() => {
 		if (!recording.isRecording) {
+			recordElapsedMsRef.current = 0
 			setRecordElapsed(0)
 			return
 		}
-		setRecordElapsed(0)
-		const startedAt = Date.now()
-		const timer = window.setInterval(() => setRecordElapsed(Math.floor((Date.now() - startedAt) / 1000)), 500)
+		if (recording.isPaused) return
+		const startedAt = Date.now() - recordElapsedMsRef.current
+		const timer = window.setInterval(() => {
+			recordElapsedMsRef.current = Date.now() - startedAt
+			setRecordElapsed(Math.floor(recordElapsedMsRef.current / 1000))
+		}, 500)
 		return () => window.clearInterval(timer)
-	}, [recording.isRecording])
+	}, [recording.isRecording, recording.isPaused])
 
 	// Recording finished in the backend -> the produced file goes straight into the queue.
 	useEffect(() => {
-		const unlisten: Promise<UnlistenFn> = listen<{ path: string; name: string }>('record_finish', ({ payload }) => {
-			if (hotkeyRecordingActive) return
+		const unlisten: Promise<UnlistenFn> = listen<{ path: string; name: string; tracks?: RecordedTrack[] }>('record_finish', ({ payload }) => {
 			recording.setIsRecording(false)
 			setPanel('none')
-			enqueueRef.current([{ name: payload.name, path: payload.path }])
+			// Empty unless separate tracks were kept and more than one device captured audio.
+			const tracks = payload.tracks?.length ? payload.tracks : undefined
+			enqueueRef.current([{ name: payload.name, path: payload.path, tracks }])
 		})
 		return () => {
 			unlisten.then((fn) => fn())
@@ -125,7 +125,7 @@ export default function SettingsPage({ setVisible, scrollTo }: SettingsPageProps
 
 					{activeSection === 'tuning' && <TuningSection vm={vm} />}
 
-					{activeSection === 'dictation' && <DictationSection />}
+					{activeSection === 'dictation' && <DictationSection vm={vm} />}
 
 					{activeSection === 'phone' && <PhoneSection vm={vm} />}
 
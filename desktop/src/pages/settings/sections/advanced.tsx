@@ -25,6 +25,9 @@ export function AdvancedSection({ vm }: { vm: SettingsViewModel }) {
 				<SettingsRow label={m.checkYtdlpUpdates()} description={m.ytdlpOptionsInfo()}>
 					<Switch checked={vm.preference.shouldCheckYtDlpVersion} onCheckedChange={vm.preference.setShouldCheckYtDlpVersion} />
 				</SettingsRow>
+				<SettingsRow label={m.fetchPlatformCaptions()} description={m.fetchPlatformCaptionsInfo()}>
+					<Switch checked={vm.preference.ytDlpCaptions} onCheckedChange={vm.preference.setYtDlpCaptions} />
+				</SettingsRow>
 			</SettingsGroup>
 
 			<SettingsGroup title={m.modelMemory()}>
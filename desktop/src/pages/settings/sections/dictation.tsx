@@ -1,10 +1,18 @@
 import { useEffect, useState } from 'react'
+import { Plus, X } from 'lucide-react'
 import { m } from '~/paraglide/messages.js'
 import ShortcutRecorder from '~/components/shortcut-recorder'
+import { Button } from '~/components/ui/button'
+import { Input } from '~/components/ui/input'
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '~/components/ui/select'
 import { Switch } from '~/components/ui/switch'
-import { DEFAULT_HOTKEY_SHORTCUT, useHotkeyProvider, type HotkeyActivationMode, type HotkeyOutputMode } from '~/providers/hotkey'
-import { SettingsGroup, SettingsRow } from './shared'
+import { DEFAULT_HOTKEY_SHORTCUT, useHotkeyProvider, type HotkeyActivationMode, type HotkeyInsertionMode, type HotkeyOutputMode, type HotkeySnippet } from '~/providers/hotkey'
+import { IconAction, SettingsField, SettingsGroup, SettingsRow, rowControlClass, type SettingsViewModel } from './shared'
 import { getDictationIndicatorEnabled, setDictationIndicatorEnabled } from '~/lib/dictation-indicator'
+import { getFriendlyModelName } from '~/lib/model'
+
+/** Radix selects cannot hold an empty value, so "use the transcription model" needs a stand-in. */
+const TRANSCRIPTION_MODEL = 'transcription-model'
 
 function SegmentedControl<T extends string>({ value, options, onChange }: { value: T; options: { value: T; label: string }[]; onChange: (v: T) => void }) {
 	return (
@@ -24,7 +32,22 @@ function SegmentedControl<T extends string>({ value, options, onChange }: { valu
 	)
 }
 
-export function DictationSection() {
+function InsertionModeSelect({ value, onChange }: { value: HotkeyInsertionMode; onChange: (mode: HotkeyInsertionMode) => void }) {
+	return (
+		<Select value={value} onValueChange={(mode) => onChange(mode as HotkeyInsertionMode)}>
+			<SelectTrigger className={`w-40 ${rowControlClass}`}>
+				<SelectValue />
+			</SelectTrigger>
+			<SelectContent>
+				<SelectItem value="auto">{m.hotkeyInsertionAuto()}</SelectItem>
+				<SelectItem value="paste">{m.hotkeyInsertionPaste()}</SelectItem>
+				<SelectItem value="keystrokes">{m.hotkeyInsertionKeystrokes()}</SelectItem>
+			</SelectContent>
+		</Select>
+	)
+}
+
+export function DictationSection({ vm }: { vm: SettingsViewModel }) {
 	const hotkey = useHotkeyProvider()
 	const [indicatorEnabled, setIndicatorEnabled] = useState(true)
 	useEffect(() => {
@@ -47,6 +70,18 @@ export function DictationSection() {
 		{ value: 'push-to-talk', label: m.hotkeyActivationPushToTalk() },
 		{ value: 'toggle', label: m.hotkeyActivationToggle() },
 	]
+	function updateSnippet(index: number, snippet: Partial<HotkeySnippet>) {
+		hotkey.setHotkeySnippets(hotkey.hotkeySnippets.map((existing, i) => (i === index ? { ...existing, ...snippet } : existing)))
+	}
+	/**
+	 * Edited as rows rather than straight on the saved map, so a row survives while its app name is
+	 * still empty or shared with another row; only named rows are saved.
+	 */
+	const [appModes, setAppModes] = useState(() => Object.entries(hotkey.hotkeyInsertionAppModes))
+	function updateAppModes(rows: [string, HotkeyInsertionMode][]) {
+		setAppModes(rows)
+		hotkey.setHotkeyInsertionAppModes(Object.fromEntries(rows.filter(([app]) => app.trim()).map(([app, mode]) => [app.trim(), mode])))
+	}
 	const outputOptions: { value: HotkeyOutputMode; label: string }[] = [
 		{ value: 'clipboard', label: m.hotkeyOutputClipboard() },
 		{ value: 'type', label: m.hotkeyOutputType() },
@@ -81,9 +116,96 @@ export function DictationSection() {
 							<SegmentedControl value={hotkey.hotkeyOutputMode} options={outputOptions} onChange={hotkey.setHotkeyOutputMode} />
 						</SettingsRow>
 
+						{hotkey.hotkeyOutputMode === 'type' && (
+							<SettingsRow label={m.hotkeyInsertionMode()} description={m.hotkeyInsertionModeInfo()}>
+								<InsertionModeSelect value={hotkey.hotkeyInsertionMode} onChange={hotkey.setHotkeyInsertionMode} />
+							</SettingsRow>
+						)}
+
+						{hotkey.hotkeyOutputMode === 'type' && (
+							<SettingsField label={m.hotkeyInsertionAppModes()} description={m.hotkeyInsertionAppModesInfo()}>
+								{appModes.map(([app, mode], index) => (
+									<div key={index} className="flex items-center gap-1.5">
+										<Input
+											className={rowControlClass}
+											placeholder={m.hotkeyInsertionAppName()}
+											value={app}
+											onChange={(event) => updateAppModes(appModes.map((row, i) => (i === index ? [event.target.value, mode] : row)))}
+										/>
+										<InsertionModeSelect
+											value={mode}
+											onChange={(value) => updateAppModes(appModes.map((row, i) => (i === index ? [app, value] : row)))}
+										/>
+										<IconAction label={m.delete()} icon={<X />} onClick={() => updateAppModes(appModes.filter((_, i) => i !== index))} />
+									</div>
+								))}
+								<Button variant="outline" size="sm" onClick={() => updateAppModes([...appModes, ['', 'paste']])}>
+									<Plus />
+									{m.addInsertionAppMode()}
+								</Button>
+							</SettingsField>
+						)}
+
+						<SettingsRow label={m.dictationModel()} description={m.dictationModelInfo()}>
+							<Select
+								value={hotkey.hotkeyModelPath ?? TRANSCRIPTION_MODEL}
+								onValueChange={(value) => hotkey.setHotkeyModelPath(value === TRANSCRIPTION_MODEL ? null : value)}
+								onOpenChange={(open) => {
+									if (open) vm.loadModels()
+								}}>
+								<SelectTrigger className={`w-56 ${rowControlClass}`}>
+									<SelectValue />
+								</SelectTrigger>
+								<SelectContent>
+									<SelectItem value={TRANSCRIPTION_MODEL}>{m.dictationModelSameAsTranscription()}</SelectItem>
+									{vm.models.map((model) => (
+										<SelectItem key={model.path} value={model.path}>
+											{vm.preference.modelDisplayNames[model.path] ?? getFriendlyModelName(model.name)}
+										</SelectItem>
+									))}
+								</SelectContent>
+							</Select>
+						</SettingsRow>
+
 						<SettingsRow label={m.normalizeHotkeyOutput()} description={m.normalizeHotkeyOutputInfo()}>
 							<Switch checked={hotkey.hotkeyNormalizeOutput} onCheckedChange={hotkey.setHotkeyNormalizeOutput} />
 						</SettingsRow>
+
+						<SettingsRow label={m.dictationSpokenCommands()} description={m.dictationSpokenCommandsInfo()}>
+							<Switch checked={hotkey.hotkeySpokenCommands} onCheckedChange={hotkey.setHotkeySpokenCommands} />
+						</SettingsRow>
+
+						<SettingsRow label={m.dictationTrailingSpace()} description={m.dictationTrailingSpaceInfo()}>
+							<Switch checked={hotkey.hotkeyTrailingSpace} onCheckedChange={hotkey.setHotkeyTrailingSpace} />
+						</SettingsRow>
+
+						<SettingsField label={m.dictationSnippets()} description={m.dictationSnippetsInfo()}>
+							{hotkey.hotkeySnippets.map((snippet, index) => (
+								<div key={index} className="flex items-center gap-1.5">
+									<Input
+										className={rowControlClass}
+										placeholder={m.dictationSnippetPhrase()}
+										value={snippet.phrase}
+										onChange={(event) => updateSnippet(index, { phrase: event.target.value })}
+									/>
+									<Input
+										className={rowControlClass}
+										placeholder={m.dictationSnippetText()}
+										value={snippet.text}
+										onChange={(event) => updateSnippet(index, { text: event.target.value })}
+									/>
+									<IconAction
+										label={m.delete()}
+										icon={<X />}
+										onClick={() => hotkey.setHotkeySnippets(hotkey.hotkeySnippets.filter((_, i) => i !== index))}
+									/>
+								</div>
+							))}
+							<Button variant="outline" size="sm" onClick={() => hotkey.setHotkeySnippets([...hotkey.hotkeySnippets, { phrase: '', text: '' }])}>
+								<Plus />
+								{m.addSnippet()}
+							</Button>
+						</SettingsField>
 					</>
 				)}
 			</SettingsGroup>
@@ -5,6 +5,7 @@ import { platform } from '@tauri-apps/plugin-os'
 import { Check, Copy, FolderOpen, RefreshCw } from 'lucide-react'
 import { useEffect, useMemo, useState } from 'react'
 import { m } from '~/paraglide/messages.js'
+import { errorMessage } from '~/lib/errors'
 import { Spinner } from '~/components/ui/spinner'
 import { Switch } from '~/components/ui/switch'
 import { ActionRow, IconAction, SettingsField, SettingsGroup, SettingsNote, SettingsRow, type SettingsViewModel } from './shared'
@@ -443,12 +444,6 @@ function normalizeStatus(payload: HandoffStatusPayload): HandoffStatus {
 	}
 }
 
-function errorMessage(error: unknown): string {
-	if (typeof error === 'string') return error
-	if (error && typeof error === 'object' && 'message' in error) return String((error as { message: unknown }).message)
-	return String(error)
-}
-
 /** The backend half of this feature may not be in the build yet — tell them so instead of crashing. */
 function isMissingCommand(error: unknown): boolean {
 	const text = errorMessage(error).toLowerCase()
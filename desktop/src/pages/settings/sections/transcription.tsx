@@ -1,8 +1,17 @@
 import { m } from '~/paraglide/messages.js'
 import LanguageInput from '~/components/language-input'
 import { Button } from '~/components/ui/button'
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '~/components/ui/select'
 import { Switch } from '~/components/ui/switch'
-import { SettingsGroup, SettingsRow, type SettingsViewModel } from './shared'
+import type { RecordingArchiveFormat } from '~/providers/preference'
+import { SettingsGroup, SettingsRow, rowControlClass, type SettingsViewModel } from './shared'
+
+const archiveFormats: { value: RecordingArchiveFormat; label: string }[] = [
+	{ value: 'wav', label: 'WAV' },
+	{ value: 'flac', label: 'FLAC' },
+	{ value: 'opus', label: 'Opus (OGG)' },
+	{ value: 'm4a', label: 'M4A (AAC)' },
+]
 
 export function TranscriptionSection({ vm }: { vm: SettingsViewModel }) {
 	const recordingPath = vm.preference.customRecordingPath ?? vm.defaultRecordingPath
@@ -41,6 +50,28 @@ export function TranscriptionSection({ vm }: { vm: SettingsViewModel }) {
 						{m.changeRecordingPath()}
 					</Button>
 				</SettingsRow>
+				<SettingsRow label={m.recordingArchiveFormat()} description={m.recordingArchiveFormatInfo()}>
+					<Select
+						value={vm.preference.recordingArchiveFormat}
+						onValueChange={(value) => vm.preference.setRecordingArchiveFormat(value as RecordingArchiveFormat)}>
+						<SelectTrigger className={`w-36 ${rowControlClass}`}>
+							<SelectValue />
+						</SelectTrigger>
+						<SelectContent>
+							{archiveFormats.map((format) => (
+								<SelectItem key={format.value} value={format.value}>
+									{format.label}
+								</SelectItem>
+							))}
+						</SelectContent>
+					</Select>
+				</SettingsRow>
+				<SettingsRow label={m.liveTranscription()} description={m.liveTranscriptionInfo()}>
+					<Switch checked={vm.preference.recordingLiveTranscription} onCheckedChange={vm.preference.setRecordingLiveTranscription} />
+				</SettingsRow>
+				<SettingsRow label={m.recordingSeparateTracks()} description={m.recordingSeparateTracksInfo()}>
+					<Switch checked={vm.preference.recordingSeparateTracks} onCheckedChange={vm.preference.setRecordingSeparateTracks} />
+				</SettingsRow>
 			</SettingsGroup>
 		</div>
 	)
@@ -1,20 +1,52 @@
+import { invoke } from '@tauri-apps/api/core'
 import { message } from '@tauri-apps/plugin-dialog'
 import { RotateCcw, Wand2 } from 'lucide-react'
+import { toast } from 'sonner'
 import { m } from '~/paraglide/messages.js'
 import NumberField from '~/components/number-field'
 import { Input } from '~/components/ui/input'
 import { Switch } from '~/components/ui/switch'
 import { Textarea } from '~/components/ui/textarea'
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '~/components/ui/select'
+import type { AudioPreset, FfmpegOptions } from '~/providers/preference'
 import { ActionRow, SettingsField, SettingsGroup, SettingsNote, SettingsRow, rowControlClass, type SettingsViewModel } from './shared'
 
 const textareaClass = 'min-h-24 max-h-60 w-full resize-none overflow-y-auto rounded-lg bg-muted/40 text-sm'
 
+// In the order ffmpeg applies them
+const audioPresets: { preset: AudioPreset; label: () => string; description: () => string }[] = [
+	{ preset: 'high_pass', label: m.audioPresetHighPass, description: m.audioPresetHighPassInfo },
+	{ preset: 'low_pass', label: m.audioPresetLowPass, description: m.audioPresetLowPassInfo },
+	{ preset: 'denoise', label: m.audioPresetDenoise, description: m.audioPresetDenoiseInfo },
+	{ preset: 'speech_enhance', label: m.audioPresetSpeechEnhance, description: m.audioPresetSpeechEnhanceInfo },
+	{ preset: 'volume_boost', label: m.audioPresetVolumeBoost, description: m.audioPresetVolumeBoostInfo },
+]
+
 export function TuningSection({ vm }: { vm: SettingsViewModel }) {
 	// More threads than cores only makes decoding slower, so the stepper stops there.
 	const maxThreads = Math.max(1, navigator.hardwareConcurrency || 8)
 	const promptLength = vm.preference.modelOptions?.init_prompt?.length ?? 0
 	const isGreedy = vm.preference.modelOptions.sampling_strategy === 'greedy'
+	const ffmpegOptions = vm.preference.ffmpegOptions
+	const presets = ffmpegOptions.presets ?? []
+
+	function setFfmpegOption(options: Partial<FfmpegOptions>) {
+		vm.preference.setFfmpegOptions({ ...ffmpegOptions, ...options })
+	}
+
+	function togglePreset(preset: AudioPreset, enabled: boolean) {
+		setFfmpegOption({ presets: enabled ? [...presets.filter((p) => p !== preset), preset] : presets.filter((p) => p !== preset) })
+	}
+
+	async function validateCustomFilter() {
+		const filter = ffmpegOptions.custom_command?.trim()
+		if (!filter) return
+		try {
+			await invoke('validate_audio_filter', { filter })
+		} catch (error) {
+			toast.error(m.invalidAudioFilter({ error: (error as { message?: string })?.message ?? String(error) }))
+		}
+	}
 
 	return (
 		<div className="space-y-6">
@@ -142,18 +174,30 @@ export function TuningSection({ vm }: { vm: SettingsViewModel }) {
 
 			<SettingsGroup title={m.ffmpegOptions()}>
 				<SettingsRow label={m.normalizeLoudness()} description={m.infoNormalizeLoudness()}>
-					<Switch
-						checked={vm.preference.ffmpegOptions.normalize_loudness}
-						onCheckedChange={(checked) => vm.preference.setFfmpegOptions({ ...vm.preference.ffmpegOptions, normalize_loudness: checked })}
-					/>
+					<Switch checked={ffmpegOptions.normalize_loudness} onCheckedChange={(checked) => setFfmpegOption({ normalize_loudness: checked })} />
 				</SettingsRow>
-				<SettingsRow
-					label={m.customFfmpegCommand()}
-					description="ffmpeg -i {input} -ar 16000 -ac 1 -c:a pcm_s16le {custom_command} -hide_banner -y -loglevel error">
+				{audioPresets.map(({ preset, label, description }) => (
+					<SettingsRow key={preset} label={label()} description={description()}>
+						<Switch checked={presets.includes(preset)} onCheckedChange={(checked) => togglePreset(preset, checked)} />
+					</SettingsRow>
+				))}
+				{presets.includes('denoise') && (
+					<SettingsRow label={m.denoiseModel()} description={m.denoiseModelInfo()}>
+						<Input
+							value={ffmpegOptions.denoise_model ?? ''}
+							onChange={(e) => setFfmpegOption({ denoise_model: e.target.value || null })}
+							placeholder="/path/to/model.rnnn"
+							type="text"
+							className={`w-64 ${rowControlClass}`}
+						/>
+					</SettingsRow>
+				)}
+				<SettingsRow label={m.customAudioFilter()} description={m.customAudioFilterInfo()}>
 					<Input
-						value={vm.preference.ffmpegOptions.custom_command ?? ''}
-						onChange={(e) => vm.preference.setFfmpegOptions({ ...vm.preference.ffmpegOptions, custom_command: e.target.value || null })}
-						placeholder={vm.preference.ffmpegOptions.normalize_loudness ? '-af loudnorm=I=-16:TP=-1.5:LRA=11' : '-af ...'}
+						value={ffmpegOptions.custom_command ?? ''}
+						onChange={(e) => setFfmpegOption({ custom_command: e.target.value || null })}
+						onBlur={validateCustomFilter}
+						placeholder="highpass=f=200,afftdn"
 						type="text"
 						className={`w-64 ${rowControlClass}`}
 					/>
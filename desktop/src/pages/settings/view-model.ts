@@ -10,6 +10,7 @@ import { fetch as tauriFetch } from '@tauri-apps/plugin-http'
 import * as fs from '@tauri-apps/plugin-fs'
 import { join } from '@tauri-apps/api/path'
 import * as config from '~/lib/config'
+import { modelUrls } from '~/lib/model-manifest'
 import { NamedPath } from '~/lib/types'
 import { ls } from '~/lib/fs'
 import { getIssueUrl, resetApp } from '~/lib/app'
@@ -237,7 +238,8 @@ export function viewModel() {
 		progressToast.setOpen(true)
 		progressToast.setProgress(0)
 		try {
-			await invoke('download_model', { url: config.vadModelUrl, path: vadPath })
+			const [url] = await modelUrls('vad')
+			await invoke('download_model', { url, path: vadPath })
 			toast.success(m.downloadComplete())
 			return true
 		} finally {
@@ -7,6 +7,8 @@ import { usePreferenceProvider } from '~/providers/preference'
 import * as utils from '~/lib/model'
 import * as osExt from '@tauri-apps/plugin-os'
 import * as config from '~/lib/config'
+import { getModelManifest, modelUrls } from '~/lib/model-manifest'
+import { errorMessage, isInsufficientSpace } from '~/lib/errors'
 import { ask } from '@tauri-apps/plugin-dialog'
 import * as fs from '@tauri-apps/plugin-fs'
 import { join } from '@tauri-apps/api/path'
@@ -53,7 +55,8 @@ export function viewModel() {
 			kind: 'info',
 		})
 		if (!confirmed) return false
-		await invoke('download_model', { url: config.vadModelUrl, path: vadPath })
+		const [url] = await modelUrls('vad')
+		await invoke('download_model', { url, path: vadPath })
 		return true
 	}
 
@@ -78,13 +81,16 @@ export function viewModel() {
 				urls = [location.state.downloadURL]
 				console.log(`[model] Using provided model URL: ${urls[0]}`)
 			} else {
-				urls = [...config.modelUrls.default]
+				// Setup is when the published manifest matters most; offline it falls back to the bundled one.
+				const manifest = await getModelManifest(true)
+				const manifestUrls = (id: string) => manifest.models.find((model) => model.id === id)?.urls ?? []
+				urls = manifestUrls('default')
 				const locale = await osExt.locale()
 				console.log(`[locale] Detected locale: ${locale}`)
 
 				if (locale?.endsWith('-IL')) {
 					console.log(`[model] Prioritizing Hebrew models`)
-					urls.unshift(...config.modelUrls.hebrew)
+					urls.unshift(...manifestUrls('hebrew'))
 				}
 			}
 
@@ -106,14 +112,15 @@ export function viewModel() {
 					return
 				} catch (err) {
 					console.error(`[model] Failed to download from ${url}:`, err)
+					if (isInsufficientSpace(err)) throw err
 					lastError = err
 				}
 			}
 
-			throw new Error(`All model downloads failed. Last error: ${lastError}`)
+			throw new Error(`All model downloads failed. Last error: ${errorMessage(lastError)}`)
 		} catch (err) {
 			console.error(`[model] Unhandled error:`, err)
-			setErrorModal?.({ open: true, log: String(err) })
+			setErrorModal?.({ open: true, log: errorMessage(err) })
 		}
 	}
 
@@ -2,6 +2,7 @@ import { ReactNode, createContext, useContext, useEffect, useState } from 'react
 import { useLocation } from 'react-router-dom'
 import { useDeepLinks } from '~/lib/use-deep-links'
 import { useSingleInstance } from '~/lib/use-single-instance'
+import { useRecoverRecordings } from '~/lib/use-recover-recordings'
 import { ModifyState, NamedPath } from '~/lib/types'
 
 type FilesProviderState = NamedPath[]
@@ -21,6 +22,7 @@ export function FilesProvider({ children }: { children: ReactNode }) {
 	const location = useLocation()
 	useDeepLinks({ setFiles })
 	useSingleInstance({ setFiles })
+	useRecoverRecordings({ setFiles })
 
 	useEffect(() => {
 		if (location?.state?.files) {
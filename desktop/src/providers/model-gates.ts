@@ -6,6 +6,8 @@ import { useCallback } from 'react'
 import { toast } from 'sonner'
 import { m } from '~/paraglide/messages.js'
 import * as config from '~/lib/config'
+import { errorMessage } from '~/lib/errors'
+import { modelUrls } from '~/lib/model-manifest'
 import { usePreferenceProvider } from '~/providers/preference'
 import { useToastProvider } from '~/providers/toast'
 
@@ -20,7 +22,7 @@ export function useModelGates() {
 	const progressToast = useToastProvider()
 
 	const ensureModel = useCallback(
-		async (options: { filename: string; url: string; title: string; question: string; downloading: string }) => {
+		async (options: { filename: string; modelId: string; title: string; question: string; downloading: string }) => {
 			const modelsFolder = await invoke<string>('get_models_folder')
 			const modelPath = await join(modelsFolder, options.filename)
 			if (await fs.exists(modelPath)) return true
@@ -32,7 +34,8 @@ export function useModelGates() {
 			progressToast.setOpen(true)
 			progressToast.setProgress(0)
 			try {
-				await invoke('download_model', { url: options.url, path: modelPath })
+				const [url] = await modelUrls(options.modelId)
+				await invoke('download_model', { url, path: modelPath })
 				toast.success(m.downloadComplete())
 				return true
 			} finally {
@@ -52,7 +55,7 @@ export function useModelGates() {
 			try {
 				const ready = await ensureModel({
 					filename: config.diarizeModelFilename,
-					url: config.diarizeModelUrl,
+					modelId: 'diarize',
 					title: m.diarization(),
 					question: m.downloadDiarizeModel(),
 					downloading: m.downloadingDiarizeModel(),
@@ -60,7 +63,7 @@ export function useModelGates() {
 				if (ready) preference.setDiarizeEnabled(true)
 			} catch (error) {
 				console.error('diarization setup failed:', error)
-				toast.error(String(error))
+				toast.error(errorMessage(error))
 			}
 		},
 		[ensureModel, preference],
@@ -75,7 +78,7 @@ export function useModelGates() {
 			try {
 				const ready = await ensureModel({
 					filename: config.vadModelFilename,
-					url: config.vadModelUrl,
+					modelId: 'vad',
 					title: m.stableTimestamps(),
 					question: m.stableTimestampsConfirm(),
 					downloading: m.downloadingVadModel(),
@@ -83,7 +86,7 @@ export function useModelGates() {
 				if (ready) preference.setStableTimestampsEnabled(true)
 			} catch (error) {
 				console.error('stable timestamps setup failed:', error)
-				toast.error(String(error))
+				toast.error(errorMessage(error))
 			}
 		},
 		[ensureModel, preference],
@@ -16,6 +16,8 @@ import type { ModelMetadata } from '~/lib/model'
 
 type Direction = 'ltr' | 'rtl'
 export type HomeTab = 'record' | 'file' | 'link'
+/** Format of the copy kept in the recordings folder; transcription always reads a WAV. */
+export type RecordingArchiveFormat = 'wav' | 'flac' | 'opus' | 'm4a'
 
 export interface AdvancedTranscribeOptions {
 	includeSubFolders: boolean
@@ -55,6 +57,12 @@ export interface Preference {
 	setStoreRecordInDocuments: ModifyState<boolean>
 	customRecordingPath: string | null
 	setCustomRecordingPath: ModifyState<string | null>
+	recordingArchiveFormat: RecordingArchiveFormat
+	setRecordingArchiveFormat: ModifyState<RecordingArchiveFormat>
+	recordingLiveTranscription: boolean
+	setRecordingLiveTranscription: ModifyState<boolean>
+	recordingSeparateTracks: boolean
+	setRecordingSeparateTracks: ModifyState<boolean>
 	setLanguageDirections: () => void
 	homeTab: HomeTab
 	setHomeTab: ModifyState<HomeTab>
@@ -69,6 +77,9 @@ export interface Preference {
 	setYtDlpVersion: ModifyState<string | null>
 	shouldCheckYtDlpVersion: boolean
 	setShouldCheckYtDlpVersion: ModifyState<boolean>
+	/** Also fetch the platform's own captions of downloaded links, to compare with the transcript. */
+	ytDlpCaptions: boolean
+	setYtDlpCaptions: ModifyState<boolean>
 
 	advancedTranscribeOptions: AdvancedTranscribeOptions
 	setAdvancedTranscribeOptions: ModifyState<AdvancedTranscribeOptions>
@@ -102,8 +113,14 @@ export function usePreferenceProvider() {
 	return useContext(PreferenceContext) as Preference
 }
 
+export type AudioPreset = 'high_pass' | 'low_pass' | 'denoise' | 'speech_enhance' | 'volume_boost'
+
 export interface FfmpegOptions {
 	normalize_loudness: boolean
+	presets?: AudioPreset[]
+	// RNNoise model (.rnnn) used by the denoise preset instead of ffmpeg's FFT denoiser
+	denoise_model?: string | null
+	// A custom ffmpeg audio filter chain, such as highpass=f=200,afftdn
 	custom_command: string | null
 }
 
@@ -144,12 +161,18 @@ const defaultOptions = {
 	},
 	ffmpegOptions: {
 		normalize_loudness: false,
+		presets: [],
+		denoise_model: null,
 		custom_command: null,
 	},
 	storeRecordInDocuments: true,
+	recordingArchiveFormat: 'wav' as RecordingArchiveFormat,
+	recordingLiveTranscription: false,
+	recordingSeparateTracks: false,
 	llmConfig: defaultOllamaConfig(),
 	ytDlpVersion: null,
 	shouldCheckYtDlpVersion: true,
+	ytDlpCaptions: false,
 }
 
 // Preference provider component
@@ -177,9 +200,22 @@ export function PreferenceProvider({ children }: { children: ReactNode }) {
 	const [ffmpegOptions, setFfmpegOptions] = usePersisted<FfmpegOptions>(CONFIG_KEYS.ffmpegOptions, defaultOptions.ffmpegOptions)
 	const [storeRecordInDocuments, setStoreRecordInDocuments] = usePersisted(CONFIG_KEYS.storeRecordInDocuments, defaultOptions.storeRecordInDocuments)
 	const [customRecordingPath, setCustomRecordingPath] = usePersisted<string | null>(CONFIG_KEYS.customRecordingPath, null)
+	const [recordingArchiveFormat, setRecordingArchiveFormat] = usePersisted<RecordingArchiveFormat>(
+		CONFIG_KEYS.recordingArchiveFormat,
+		defaultOptions.recordingArchiveFormat,
+	)
+	const [recordingLiveTranscription, setRecordingLiveTranscription] = usePersisted<boolean>(
+		CONFIG_KEYS.recordingLiveTranscription,
+		defaultOptions.recordingLiveTranscription,
+	)
+	const [recordingSeparateTracks, setRecordingSeparateTracks] = usePersisted<boolean>(
+		CONFIG_KEYS.recordingSeparateTracks,
+		defaultOptions.recordingSeparateTracks,
+	)
 	const [llmConfig, setLlmConfig] = usePersisted<LlmConfig>(CONFIG_KEYS.llmConfig, defaultOptions.llmConfig)
 	const [ytDlpVersion, setYtDlpVersion] = usePersisted<string | null>(CONFIG_KEYS.ytDlpVersion, null)
 	const [shouldCheckYtDlpVersion, setShouldCheckYtDlpVersion] = usePersisted<boolean>(CONFIG_KEYS.shouldCheckYtDlpVersion, true)
+	const [ytDlpCaptions, setYtDlpCaptions] = usePersisted<boolean>(CONFIG_KEYS.ytDlpCaptions, defaultOptions.ytDlpCaptions)
 	const [advancedTranscribeOptions, setAdvancedTranscribeOptions] = usePersisted<AdvancedTranscribeOptions>(CONFIG_KEYS.advancedOptions, {
 		includeSubFolders: false,
 		saveNextToAudioFile: true,
@@ -279,6 +315,9 @@ export function PreferenceProvider({ children }: { children: ReactNode }) {
 		setFfmpegOptions(defaultOptions.ffmpegOptions)
 		setStoreRecordInDocuments(defaultOptions.storeRecordInDocuments)
 		setCustomRecordingPath(null)
+		setRecordingArchiveFormat(defaultOptions.recordingArchiveFormat)
+		setRecordingLiveTranscription(defaultOptions.recordingLiveTranscription)
+		setRecordingSeparateTracks(defaultOptions.recordingSeparateTracks)
 		setLlmConfig(defaultOptions.llmConfig)
 		message(m.successAction())
 	}
@@ -301,6 +340,12 @@ export function PreferenceProvider({ children }: { children: ReactNode }) {
 		setStoreRecordInDocuments,
 		customRecordingPath,
 		setCustomRecordingPath,
+		recordingArchiveFormat,
+		setRecordingArchiveFormat,
+		recordingLiveTranscription,
+		setRecordingLiveTranscription,
+		recordingSeparateTracks,
+		setRecordingSeparateTracks,
 		textFormatTranscript,
 		setTextFormatTranscript,
 		textFormatSummary,
@@ -333,6 +378,8 @@ export function PreferenceProvider({ children }: { children: ReactNode }) {
 		setYtDlpVersion,
 		shouldCheckYtDlpVersion,
 		setShouldCheckYtDlpVersion,
+		ytDlpCaptions,
+		setYtDlpCaptions,
 		advancedTranscribeOptions,
 		setAdvancedTranscribeOptions,
 		recentLanguages,
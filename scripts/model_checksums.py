@@ -0,0 +1,102 @@
+#!/usr/bin/env -S uv run --script
+# /// script
+# requires-python = ">=3.12"
+# dependencies = [
+#     "httpx==0.28.1",
+# ]
+# ///
+
+"""
+Fill in the size and sha256 of every model in desktop/src-tauri/model-manifest.json.
+
+Hugging Face reports both for LFS files without downloading them (`x-linked-size` and
+`x-linked-etag` on the redirect of a `resolve` URL); other mirrors are downloaded and hashed.
+Every mirror of a model must serve the same size, or the script stops before writing anything.
+
+    uv run scripts/model_checksums.py          # update the manifest, bumping its revision
+    uv run scripts/model_checksums.py --check  # fail when a model lacks a checksum or is stale
+"""
+
+from __future__ import annotations
+
+import hashlib
+import json
+import sys
+from pathlib import Path
+
+import httpx
+
+MANIFEST_PATH = Path(__file__).resolve().parent.parent / "desktop/src-tauri/model-manifest.json"
+
+
+def huggingface_digest(client: httpx.Client, url: str) -> tuple[int, str] | None:
+    response = client.head(url, follow_redirects=False)
+    size = response.headers.get("x-linked-size")
+    etag = response.headers.get("x-linked-etag", "").strip('"')
+    if not size or len(etag) != 64:
+        return None
+    return int(size), etag.lower()
+
+
+def download_digest(client: httpx.Client, url: str) -> tuple[int, str]:
+    sha256 = hashlib.sha256()
+    size = 0
+    with client.stream("GET", url, follow_redirects=True) as response:
+        response.raise_for_status()
+        for chunk in response.iter_bytes(1024 * 1024):
+            sha256.update(chunk)
+            size += len(chunk)
+    return size, sha256.hexdigest()
+
+
+def served_size(client: httpx.Client, url: str) -> int | None:
+    response = client.head(url, follow_redirects=True)
+    response.raise_for_status()
+    length = response.headers.get("content-length")
+    return int(length) if length else None
+
+
+def model_digest(client: httpx.Client, model: dict) -> tuple[int, str]:
+    digest = None
+    for url in model["urls"]:
+        if "huggingface.co" in url:
+            digest = huggingface_digest(client, url)
+            if digest:
+                break
+    if digest is None:
+        print(f"{model['id']}: hashing {model['urls'][0]}", file=sys.stderr)
+        digest = download_digest(client, model["urls"][0])
+
+    size, _ = digest
+    for url in model["urls"]:
+        mirror_size = served_size(client, url)
+        if mirror_size is not None and mirror_size != size:
+            raise SystemExit(f"{model['id']}: {url} serves {mirror_size} bytes, expected {size}")
+    return digest
+
+
+def main() -> None:
+    check = "--check" in sys.argv[1:]
+    manifest = json.loads(MANIFEST_PATH.read_text())
+    changed = False
+    with httpx.Client(timeout=60) as client:
+        for model in manifest["models"]:
+            size, sha256 = model_digest(client, model)
+            if model.get("size") != size or model.get("sha256") != sha256:
+                print(f"{model['id']}: {size} bytes, sha256 {sha256}")
+                model["size"] = size
+                model["sha256"] = sha256
+                changed = True
+
+    if check:
+        if changed:
+            raise SystemExit(f"{MANIFEST_PATH} is missing or has stale checksums; run scripts/model_checksums.py")
+        return
+    if changed:
+        manifest["revision"] += 1
+        MANIFEST_PATH.write_text(json.dumps(manifest, indent="\t", ensure_ascii=False) + "\n")
+        print(f"updated {MANIFEST_PATH} to revision {manifest['revision']}")
+
+
+if __name__ == "__main__":
+    main()